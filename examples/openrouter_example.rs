@@ -1,6 +1,8 @@
 use loco_rs::cli::playground;
 use oxidized_canvas::{
-    app::App, common, services::service_provider::ServiceProvider,
+    app::App,
+    common::{self, settings::Flow},
+    services::service_provider::ServiceProvider,
 };
 
 #[tokio::main]
@@ -12,7 +14,7 @@ async fn main() -> loco_rs::Result<()> {
 
     // Text Generation
     println!("\nGenerating text...");
-    let txt_ai = ServiceProvider::random_txt_service(&settings)
+    let txt_ai = ServiceProvider::random_txt_service(&settings, Flow::Create)
         .map_err(|e| loco_rs::Error::Message(e.to_string()))?;
     println!("Using model: {}", txt_ai.model_name());
     match txt_ai.generate("What is the meaning of life?").await {
@@ -22,11 +24,14 @@ async fn main() -> loco_rs::Result<()> {
 
     // Image Generation
     println!("\nGenerating image...");
-    let img_ai = ServiceProvider::random_img_service(&settings)
+    let img_ai = ServiceProvider::random_img_service(&settings, Flow::Create)
         .map_err(|e| loco_rs::Error::Message(e.to_string()))?;
     println!("Using model: {}", img_ai.model_name());
     match img_ai
-        .generate("A photorealistic image of a cat programming on a laptop")
+        .generate(
+            "A photorealistic image of a cat programming on a laptop",
+            &settings.image_options,
+        )
         .await
     {
         Ok(base64_image) => {
@@ -8,6 +8,22 @@ mod m20241007_173307_arts;
 mod m20250828_101518_add_model_to_arts;
 mod m20250830_091407_mixes;
 mod m20250830_092716_mixarts;
+mod m20261018_090000_generation_jobs;
+mod m20261018_100000_generation_usage;
+mod m20261018_110000_add_provenance_to_arts_and_mixes;
+mod m20261018_120000_prompt_templates;
+mod m20261018_130000_add_generation_options_to_arts;
+mod m20261018_140000_art_candidates;
+mod m20261018_150000_add_status_to_arts_and_mixes;
+mod m20261018_160000_scheduled_runs;
+mod m20261018_170000_add_image_hash_to_arts_and_mixes;
+mod m20261018_180000_add_diversity_score_to_arts;
+mod m20261018_190000_style_presets;
+mod m20261018_200000_add_variation_to_arts;
+mod m20261018_210000_art_revisions;
+mod m20261018_220000_staged_mixes;
+mod m20261018_230000_add_changes_to_art_candidates;
+mod m20261018_231000_add_request_id_to_arts;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -19,6 +35,22 @@ impl MigratorTrait for Migrator {
             Box::new(m20250828_101518_add_model_to_arts::Migration),
             Box::new(m20250830_091407_mixes::Migration),
             Box::new(m20250830_092716_mixarts::Migration),
+            Box::new(m20261018_090000_generation_jobs::Migration),
+            Box::new(m20261018_100000_generation_usage::Migration),
+            Box::new(m20261018_110000_add_provenance_to_arts_and_mixes::Migration),
+            Box::new(m20261018_120000_prompt_templates::Migration),
+            Box::new(m20261018_130000_add_generation_options_to_arts::Migration),
+            Box::new(m20261018_140000_art_candidates::Migration),
+            Box::new(m20261018_150000_add_status_to_arts_and_mixes::Migration),
+            Box::new(m20261018_160000_scheduled_runs::Migration),
+            Box::new(m20261018_170000_add_image_hash_to_arts_and_mixes::Migration),
+            Box::new(m20261018_180000_add_diversity_score_to_arts::Migration),
+            Box::new(m20261018_190000_style_presets::Migration),
+            Box::new(m20261018_200000_add_variation_to_arts::Migration),
+            Box::new(m20261018_210000_art_revisions::Migration),
+            Box::new(m20261018_220000_staged_mixes::Migration),
+            Box::new(m20261018_230000_add_changes_to_art_candidates::Migration),
+            Box::new(m20261018_231000_add_request_id_to_arts::Migration),
             // inject-above (do not remove this comment)
         ]
     }
@@ -0,0 +1,92 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(GenerationJobs::Table)
+                    .col(pk_auto(GenerationJobs::Id))
+                    .col(uuid(GenerationJobs::Uuid))
+                    .col(string(GenerationJobs::Kind))
+                    .col(string(GenerationJobs::Status))
+                    .col(integer_null(GenerationJobs::ArtId))
+                    .col(integer_null(GenerationJobs::MixId))
+                    .col(json_null(GenerationJobs::Payload))
+                    .col(integer(GenerationJobs::Attempts).default(0))
+                    .col(integer(GenerationJobs::MaxAttempts))
+                    .col(text_null(GenerationJobs::LastError))
+                    .col(timestamp_with_time_zone(GenerationJobs::RunAt))
+                    .col(timestamp_with_time_zone_null(GenerationJobs::StartedAt))
+                    .col(timestamp_with_time_zone_null(GenerationJobs::FinishedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-generation_jobs-art_id")
+                            .from(GenerationJobs::Table, GenerationJobs::ArtId)
+                            .to(Arts::Table, Arts::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-generation_jobs-mix_id")
+                            .from(GenerationJobs::Table, GenerationJobs::MixId)
+                            .to(Mixes::Table, Mixes::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-generation_jobs-status")
+                    .table(GenerationJobs::Table)
+                    .col(GenerationJobs::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GenerationJobs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum GenerationJobs {
+    Table,
+    Id,
+    Uuid,
+    Kind,
+    Status,
+    ArtId,
+    MixId,
+    Payload,
+    Attempts,
+    MaxAttempts,
+    LastError,
+    RunAt,
+    StartedAt,
+    FinishedAt,
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Mixes {
+    Table,
+    Id,
+}
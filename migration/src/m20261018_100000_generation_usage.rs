@@ -0,0 +1,91 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(GenerationUsage::Table)
+                    .col(pk_auto(GenerationUsage::Id))
+                    .col(string(GenerationUsage::Flow))
+                    .col(string(GenerationUsage::Step))
+                    .col(string(GenerationUsage::Model))
+                    .col(integer_null(GenerationUsage::PromptTokens))
+                    .col(integer_null(GenerationUsage::CompletionTokens))
+                    .col(integer_null(GenerationUsage::TotalTokens))
+                    .col(double_null(GenerationUsage::Cost))
+                    .col(integer(GenerationUsage::LatencyMs))
+                    .col(boolean(GenerationUsage::Success))
+                    .col(integer_null(GenerationUsage::ArtId))
+                    .col(integer_null(GenerationUsage::MixId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-generation_usage-art_id")
+                            .from(GenerationUsage::Table, GenerationUsage::ArtId)
+                            .to(Arts::Table, Arts::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-generation_usage-mix_id")
+                            .from(GenerationUsage::Table, GenerationUsage::MixId)
+                            .to(Mixes::Table, Mixes::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-generation_usage-created_at")
+                    .table(GenerationUsage::Table)
+                    .col(GenerationUsage::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GenerationUsage::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum GenerationUsage {
+    Table,
+    Id,
+    CreatedAt,
+    Flow,
+    Step,
+    Model,
+    PromptTokens,
+    CompletionTokens,
+    TotalTokens,
+    Cost,
+    LatencyMs,
+    Success,
+    ArtId,
+    MixId,
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Mixes {
+    Table,
+    Id,
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden, Clone, Copy)]
+enum Arts {
+    Table,
+}
+
+#[derive(DeriveIden, Clone, Copy)]
+enum Mixes {
+    Table,
+}
+
+#[derive(DeriveIden, Clone, Copy)]
+enum Provenance {
+    PromptModel,
+    TitleModel,
+    PromptMetaPrompt,
+    TitleMetaPrompt,
+}
+
+const COLUMNS: [Provenance; 4] = [
+    Provenance::PromptModel,
+    Provenance::TitleModel,
+    Provenance::PromptMetaPrompt,
+    Provenance::TitleMetaPrompt,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // one column per statement, SQLite can't alter several at once
+        for table in [Arts::Table.into_iden(), Mixes::Table.into_iden()] {
+            for column in COLUMNS {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table.clone())
+                            .add_column_if_not_exists(text_null(column))
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [Arts::Table.into_iden(), Mixes::Table.into_iden()] {
+            for column in COLUMNS {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table.clone())
+                            .drop_column(column)
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden, Clone, Copy)]
+enum TemplateColumns {
+    PromptTemplateId,
+    TitleTemplateId,
+}
+
+const TEMPLATE_COLUMNS: [TemplateColumns; 2] = [
+    TemplateColumns::PromptTemplateId,
+    TemplateColumns::TitleTemplateId,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(PromptTemplates::Table)
+                    .col(pk_auto(PromptTemplates::Id))
+                    .col(string(PromptTemplates::Kind))
+                    .col(integer(PromptTemplates::Version))
+                    .col(text(PromptTemplates::Body))
+                    .col(text_null(PromptTemplates::Note))
+                    .col(boolean(PromptTemplates::Active).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-prompt_templates-kind-version")
+                    .table(PromptTemplates::Table)
+                    .col(PromptTemplates::Kind)
+                    .col(PromptTemplates::Version)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // one column per statement, SQLite can't alter several at once
+        for table in [Arts::Table.into_iden(), Mixes::Table.into_iden()] {
+            for column in TEMPLATE_COLUMNS {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table.clone())
+                            .add_column_if_not_exists(integer_null(column))
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [Arts::Table.into_iden(), Mixes::Table.into_iden()] {
+            for column in TEMPLATE_COLUMNS {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(table.clone())
+                            .drop_column(column)
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+        manager
+            .drop_table(Table::drop().table(PromptTemplates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PromptTemplates {
+    Table,
+    Id,
+    Kind,
+    Version,
+    Body,
+    Note,
+    Active,
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Mixes {
+    Table,
+}
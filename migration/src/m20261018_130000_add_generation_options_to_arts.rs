@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    AspectRatio,
+    ImageSize,
+    Seed,
+    NegativePrompt,
+    Width,
+    Height,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // one column per statement, SQLite can't alter several at once
+        for column in [
+            string_null(Arts::AspectRatio),
+            string_null(Arts::ImageSize),
+            big_integer_null(Arts::Seed),
+            text_null(Arts::NegativePrompt),
+            integer_null(Arts::Width),
+            integer_null(Arts::Height),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Arts::Table)
+                        .add_column_if_not_exists(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Arts::AspectRatio,
+            Arts::ImageSize,
+            Arts::Seed,
+            Arts::NegativePrompt,
+            Arts::Width,
+            Arts::Height,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Arts::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(ArtCandidates::Table)
+                    .col(pk_auto(ArtCandidates::Id))
+                    .col(integer(ArtCandidates::ArtId))
+                    .col(text(ArtCandidates::Image))
+                    .col(text_null(ArtCandidates::Model))
+                    .col(integer_null(ArtCandidates::Width))
+                    .col(integer_null(ArtCandidates::Height))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-art_candidates-art_id")
+                            .from(ArtCandidates::Table, ArtCandidates::ArtId)
+                            .to(Arts::Table, Arts::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-art_candidates-art_id")
+                    .table(ArtCandidates::Table)
+                    .col(ArtCandidates::ArtId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ArtCandidates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ArtCandidates {
+    Table,
+    Id,
+    ArtId,
+    Image,
+    Model,
+    Width,
+    Height,
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    Id,
+}
@@ -0,0 +1,78 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(ScheduledRuns::Table)
+                    .col(pk_auto(ScheduledRuns::Id))
+                    .col(string(ScheduledRuns::Job))
+                    .col(string(ScheduledRuns::Kind))
+                    .col(string(ScheduledRuns::Status))
+                    .col(timestamp_with_time_zone(ScheduledRuns::ScheduledFor))
+                    .col(timestamp_with_time_zone_null(ScheduledRuns::StartedAt))
+                    .col(timestamp_with_time_zone_null(ScheduledRuns::FinishedAt))
+                    .col(text_null(ScheduledRuns::Message))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-scheduled_runs-status-scheduled_for")
+                    .table(ScheduledRuns::Table)
+                    .col(ScheduledRuns::Status)
+                    .col(ScheduledRuns::ScheduledFor)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Arts::Table)
+                    .add_column_if_not_exists(boolean(Arts::RerenderFlagged).default(false))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Arts::Table)
+                    .drop_column(Arts::RerenderFlagged)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ScheduledRuns::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledRuns {
+    Table,
+    Id,
+    Job,
+    Kind,
+    Status,
+    ScheduledFor,
+    StartedAt,
+    FinishedAt,
+    Message,
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    RerenderFlagged,
+}
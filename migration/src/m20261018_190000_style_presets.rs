@@ -0,0 +1,69 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(StylePresets::Table)
+                    .col(pk_auto(StylePresets::Id))
+                    .col(string_uniq(StylePresets::Name))
+                    .col(text(StylePresets::Directive))
+                    .to_owned(),
+            )
+            .await?;
+
+        // one column per statement, SQLite can't alter several at once
+        for column in [
+            text_null(Arts::Theme),
+            text_null(Arts::Style),
+            integer_null(Arts::StylePresetId),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Arts::Table)
+                        .add_column_if_not_exists(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Arts::Theme, Arts::Style, Arts::StylePresetId] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Arts::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .drop_table(Table::drop().table(StylePresets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StylePresets {
+    Table,
+    Id,
+    Name,
+    Directive,
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    Theme,
+    Style,
+    StylePresetId,
+}
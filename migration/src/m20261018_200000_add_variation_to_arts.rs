@@ -0,0 +1,46 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // one column per statement, SQLite can't alter several at once
+        for column in [
+            integer_null(Arts::ParentId),
+            text_null(Arts::VariationInstruction),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Arts::Table)
+                        .add_column_if_not_exists(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Arts::ParentId, Arts::VariationInstruction] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Arts::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    ParentId,
+    VariationInstruction,
+}
@@ -0,0 +1,102 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(ArtRevisions::Table)
+                    .col(pk_auto(ArtRevisions::Id))
+                    .col(integer(ArtRevisions::ArtId))
+                    .col(string(ArtRevisions::Reason))
+                    .col(text(ArtRevisions::Image))
+                    .col(text(ArtRevisions::Prompt))
+                    .col(string(ArtRevisions::Title))
+                    .col(text_null(ArtRevisions::Model))
+                    .col(text_null(ArtRevisions::PromptModel))
+                    .col(text_null(ArtRevisions::TitleModel))
+                    .col(text_null(ArtRevisions::PromptMetaPrompt))
+                    .col(text_null(ArtRevisions::TitleMetaPrompt))
+                    .col(integer_null(ArtRevisions::PromptTemplateId))
+                    .col(integer_null(ArtRevisions::TitleTemplateId))
+                    .col(string_null(ArtRevisions::AspectRatio))
+                    .col(string_null(ArtRevisions::ImageSize))
+                    .col(big_integer_null(ArtRevisions::Seed))
+                    .col(text_null(ArtRevisions::NegativePrompt))
+                    .col(integer_null(ArtRevisions::Width))
+                    .col(integer_null(ArtRevisions::Height))
+                    .col(big_integer_null(ArtRevisions::ImageHash))
+                    .col(double_null(ArtRevisions::DiversityScore))
+                    .col(text_null(ArtRevisions::Theme))
+                    .col(text_null(ArtRevisions::Style))
+                    .col(integer_null(ArtRevisions::StylePresetId))
+                    .col(text_null(ArtRevisions::VariationInstruction))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-art_revisions-art_id")
+                            .from(ArtRevisions::Table, ArtRevisions::ArtId)
+                            .to(Arts::Table, Arts::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-art_revisions-art_id")
+                    .table(ArtRevisions::Table)
+                    .col(ArtRevisions::ArtId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ArtRevisions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ArtRevisions {
+    Table,
+    Id,
+    ArtId,
+    Reason,
+    Image,
+    Prompt,
+    Title,
+    Model,
+    PromptModel,
+    TitleModel,
+    PromptMetaPrompt,
+    TitleMetaPrompt,
+    PromptTemplateId,
+    TitleTemplateId,
+    AspectRatio,
+    ImageSize,
+    Seed,
+    NegativePrompt,
+    Width,
+    Height,
+    ImageHash,
+    DiversityScore,
+    Theme,
+    Style,
+    StylePresetId,
+    VariationInstruction,
+}
+
+#[derive(DeriveIden)]
+enum Arts {
+    Table,
+    Id,
+}
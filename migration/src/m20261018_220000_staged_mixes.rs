@@ -0,0 +1,113 @@
+use loco_rs::schema::table_auto_tz;
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto_tz(StagedMixes::Table)
+                    .col(pk_auto(StagedMixes::Id))
+                    .col(uuid(StagedMixes::RequestId))
+                    .col(json(StagedMixes::ArtIds))
+                    .col(text(StagedMixes::Image))
+                    .col(text(StagedMixes::Prompt))
+                    .col(string(StagedMixes::Title))
+                    .col(string(StagedMixes::Model))
+                    .col(text_null(StagedMixes::PromptModel))
+                    .col(text_null(StagedMixes::TitleModel))
+                    .col(text_null(StagedMixes::PromptMetaPrompt))
+                    .col(text_null(StagedMixes::TitleMetaPrompt))
+                    .col(integer_null(StagedMixes::PromptTemplateId))
+                    .col(integer_null(StagedMixes::TitleTemplateId))
+                    .col(string(StagedMixes::Status))
+                    .col(text_null(StagedMixes::LastError))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-staged_mixes-request_id")
+                    .table(StagedMixes::Table)
+                    .col(StagedMixes::RequestId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // a retried mix job finds the mix it already saved by its request
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Mixes::Table)
+                    .add_column_if_not_exists(uuid_null(Mixes::RequestId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-mixes-request_id")
+                    .table(Mixes::Table)
+                    .col(Mixes::RequestId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-mixes-request_id")
+                    .table(Mixes::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Mixes::Table)
+                    .drop_column(Mixes::RequestId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StagedMixes::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StagedMixes {
+    Table,
+    Id,
+    RequestId,
+    ArtIds,
+    Image,
+    Prompt,
+    Title,
+    Model,
+    PromptModel,
+    TitleModel,
+    PromptMetaPrompt,
+    TitleMetaPrompt,
+    PromptTemplateId,
+    TitleTemplateId,
+    Status,
+    LastError,
+}
+
+#[derive(DeriveIden)]
+enum Mixes {
+    Table,
+    RequestId,
+}
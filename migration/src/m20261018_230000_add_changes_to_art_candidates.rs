@@ -0,0 +1,37 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // the prompt, title and provenance a replace or variation would set,
+        // applied to the art only when one of its candidates is picked
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ArtCandidates::Table)
+                    .add_column_if_not_exists(json_null(ArtCandidates::Changes))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ArtCandidates::Table)
+                    .drop_column(ArtCandidates::Changes)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ArtCandidates {
+    Table,
+    Changes,
+}
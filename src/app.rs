@@ -4,8 +4,7 @@ use async_trait::async_trait;
 use loco_rs::{
     Result,
     app::{AppContext, Hooks, Initializer},
-    // AppWorker,
-    bgworker::Queue,
+    bgworker::{BackgroundWorker, Queue},
     boot::{BootResult, StartMode, create_app},
     config::Config,
     controller::AppRoutes,
@@ -15,7 +14,10 @@ use loco_rs::{
 };
 use migration::Migrator;
 
-use crate::{controllers, initializers, tasks};
+use crate::{
+    controllers, initializers, services::mix_limits::MixLimiter, tasks,
+    workers::generation::GenerationWorker,
+};
 
 pub struct App;
 #[async_trait]
@@ -46,10 +48,17 @@ impl Hooks for App {
         crate::common::config::load(env)
     }
 
+    async fn after_context(ctx: AppContext) -> Result<AppContext> {
+        ctx.shared_store.insert(MixLimiter::default());
+        Ok(ctx)
+    }
+
     async fn initializers(_ctx: &AppContext) -> Result<Vec<Box<dyn Initializer>>> {
         Ok(vec![
             Box::new(initializers::view_engine::ViewEngineInitializer),
             Box::new(initializers::realtime::RealtimeInitializer),
+            Box::new(initializers::generation_jobs::GenerationJobsInitializer),
+            Box::new(initializers::scheduler::SchedulerInitializer),
         ])
     }
 
@@ -58,10 +67,11 @@ impl Hooks for App {
             .add_route(controllers::arts::routes())
             .add_route(controllers::backoffice::routes())
             .add_route(controllers::mixes::routes())
+            .add_route(controllers::mixes::gallery_routes())
     }
 
-    async fn connect_workers(_ctx: &AppContext, _queue: &Queue) -> Result<()> {
-        //     queue.register(DownloadWorker::build(ctx)).await?;
+    async fn connect_workers(ctx: &AppContext, queue: &Queue) -> Result<()> {
+        queue.register(GenerationWorker::build(ctx)).await?;
         Ok(())
     }
 
@@ -71,6 +81,9 @@ impl Hooks for App {
         tasks.register(tasks::create_art::CreateArt);
         tasks.register(tasks::replace_art::ReplaceArt);
         tasks.register(tasks::seed::SeedData);
+        tasks.register(tasks::rerender_flagged::RerenderFlagged);
+        tasks.register(tasks::hash_images::HashImages);
+        tasks.register(tasks::repair_mixes::RepairMixes);
         // tasks-inject (do not remove)
     }
 
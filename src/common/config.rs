@@ -26,9 +26,8 @@ pub fn load(env: &Environment) -> Result<Config> {
 
 fn load_from_folder(env: &Environment, folder: &Path) -> Result<Config> {
     let defaults_path = folder.join("defaults.yaml");
-    let defaults = fs::read_to_string(&defaults_path).map_err(|e| {
-        Error::Message(format!("could not read {}: {e}", defaults_path.display()))
-    })?;
+    let defaults = fs::read_to_string(&defaults_path)
+        .map_err(|e| Error::Message(format!("could not read {}: {e}", defaults_path.display())))?;
 
     let overlay_path = folder.join(format!("{env}.yaml"));
     let overlay = fs::read_to_string(&overlay_path).ok();
@@ -118,10 +117,15 @@ settings:
 
     #[test]
     fn merge_deep_merges_maps_keeping_base_keys() {
-        let base: serde_yaml::Value = serde_yaml::from_str("settings:\n  key: base\n  pool:\n    - m1\n").unwrap();
-        let overlay: serde_yaml::Value = serde_yaml::from_str("settings:\n  key: overlay\n").unwrap();
+        let base: serde_yaml::Value =
+            serde_yaml::from_str("settings:\n  key: base\n  pool:\n    - m1\n").unwrap();
+        let overlay: serde_yaml::Value =
+            serde_yaml::from_str("settings:\n  key: overlay\n").unwrap();
         let merged = merge_values(base, overlay);
-        assert_eq!(merged["settings"]["key"], serde_yaml::Value::from("overlay"));
+        assert_eq!(
+            merged["settings"]["key"],
+            serde_yaml::Value::from("overlay")
+        );
         assert_eq!(
             merged["settings"]["pool"],
             serde_yaml::Value::from(vec![serde_yaml::Value::from("m1")])
@@ -0,0 +1,119 @@
+//! Applies the theme and style directives of a generation. The theme is
+//! handed to the text Model with the image meta-prompt, so the prompt it
+//! writes is about the theme; the style is appended to the prompt it wrote, so
+//! the image Model renders it whatever the text Model made of it. A style
+//! naming a preset of the library stands for the preset's directive, as
+//! looked up by `style_presets::Model::resolve`.
+
+use serde::{Deserialize, Serialize};
+
+/// `Directives` steer what a new art is about and how it looks, beyond the
+/// gallery's own variety: a `theme` for its subject (e.g. `winter`) and a
+/// `style`, either the name of a style preset or a style written out (e.g.
+/// `linocut print`).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Directives {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+}
+
+/// `non_blank` trims `value`, `None` when nothing is left.
+fn non_blank(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+impl Directives {
+    /// `new` takes the directives as typed, treating blank ones as unset.
+    #[must_use]
+    pub fn new(theme: Option<&str>, style: Option<&str>) -> Self {
+        Self {
+            theme: non_blank(theme),
+            style: non_blank(style),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.theme.is_none() && self.style.is_none()
+    }
+}
+
+/// `Resolved` is a generation's directives with the style preset looked up.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Resolved {
+    pub theme: Option<String>,
+    /// The style as asked for: a preset name or a style written out.
+    pub style: Option<String>,
+    /// What the image Model is told about the style.
+    pub style_directive: Option<String>,
+    pub style_preset_id: Option<i32>,
+}
+
+impl Resolved {
+    /// `meta_prompt` is the image meta-prompt asking for the theme.
+    #[must_use]
+    pub fn meta_prompt(&self, meta_prompt: &str) -> String {
+        match self.theme.as_deref() {
+            Some(theme) => format!(
+                "{meta_prompt}\n\nThe image must be about this theme: {theme}. Keep to the theme while still differing from the other prompts."
+            ),
+            None => meta_prompt.to_string(),
+        }
+    }
+
+    /// `image_prompt` is the written prompt with the style to render it in.
+    #[must_use]
+    pub fn image_prompt(&self, prompt: &str) -> String {
+        match self.style_directive.as_deref() {
+            Some(style) => format!("{prompt} Style: {style}"),
+            None => prompt.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_directives_are_unset() {
+        let directives = Directives::new(Some("  winter "), Some("   "));
+        assert_eq!(directives.theme.as_deref(), Some("winter"));
+        assert_eq!(directives.style, None);
+        assert!(!directives.is_empty());
+        assert!(Directives::new(None, Some("")).is_empty());
+    }
+
+    #[test]
+    fn directives_extend_the_meta_prompt_and_the_image_prompt() {
+        let resolved = Resolved {
+            theme: Some("winter".to_string()),
+            style: Some("Linocut".to_string()),
+            style_directive: Some("linocut print, bold carved lines".to_string()),
+            style_preset_id: Some(1),
+        };
+
+        assert!(
+            resolved
+                .meta_prompt("Write a prompt.")
+                .starts_with("Write a prompt.\n\nThe image must be about this theme: winter.")
+        );
+        assert_eq!(
+            resolved.image_prompt("A fox in the snow."),
+            "A fox in the snow. Style: linocut print, bold carved lines"
+        );
+    }
+
+    #[test]
+    fn no_directives_leave_the_prompts_alone() {
+        let resolved = Resolved::default();
+        assert_eq!(resolved.meta_prompt("Write a prompt."), "Write a prompt.");
+        assert_eq!(resolved.image_prompt("A fox."), "A fox.");
+    }
+}
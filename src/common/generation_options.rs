@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+/// The aspect ratio of renders that don't ask for one.
+pub const DEFAULT_ASPECT_RATIO: &str = "1:1";
+
+/// `GenerationOptions` shape an image render beyond its prompt. Every field
+/// is optional; unset fields are filled from the configured defaults, and
+/// what a Model can't honour it ignores.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationOptions {
+    /// Width to height, as `W:H` (e.g. `3:4` for a portrait).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<String>,
+    /// Pixel size, as `WxH` (e.g. `1024x1536`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+    /// Seed for Models that render reproducibly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// What the image should not show.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub negative_prompt: Option<String>,
+}
+
+/// `parse_pair` reads two positive integers joined by `separator`.
+fn parse_pair(value: &str, separator: char) -> Option<(u32, u32)> {
+    let (width, height) = value.trim().split_once(separator)?;
+    let width = width.trim().parse::<u32>().ok()?;
+    let height = height.trim().parse::<u32>().ok()?;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+impl GenerationOptions {
+    /// `or` fills the unset fields from `defaults`.
+    #[must_use]
+    pub fn or(self, defaults: &Self) -> Self {
+        Self {
+            aspect_ratio: self.aspect_ratio.or_else(|| defaults.aspect_ratio.clone()),
+            size: self.size.or_else(|| defaults.size.clone()),
+            seed: self.seed.or(defaults.seed),
+            negative_prompt: self
+                .negative_prompt
+                .or_else(|| defaults.negative_prompt.clone()),
+        }
+    }
+
+    /// The requested aspect ratio, square when none is set.
+    #[must_use]
+    pub fn aspect_ratio(&self) -> &str {
+        self.aspect_ratio.as_deref().unwrap_or(DEFAULT_ASPECT_RATIO)
+    }
+
+    /// `validate` checks that the ratio and size are well formed.
+    ///
+    /// # Errors
+    ///
+    /// A message naming the malformed option.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ratio) = &self.aspect_ratio
+            && parse_pair(ratio, ':').is_none()
+        {
+            return Err(format!(
+                "Invalid aspect ratio {ratio:?}, expected W:H such as 3:4"
+            ));
+        }
+        if let Some(size) = &self.size
+            && parse_pair(size, 'x').is_none()
+        {
+            return Err(format!(
+                "Invalid size {size:?}, expected WxH such as 1024x1536"
+            ));
+        }
+        Ok(())
+    }
+
+    /// `dimensions` is the pixel size to render at: the requested size, or
+    /// the aspect ratio with its longer side at `long_side` pixels.
+    #[must_use]
+    pub fn dimensions(&self, long_side: u32) -> (u32, u32) {
+        if let Some(size) = self.size.as_deref().and_then(|size| parse_pair(size, 'x')) {
+            return size;
+        }
+        let (width, height) = parse_pair(self.aspect_ratio(), ':').unwrap_or((1, 1));
+        let scale = |side: u32| {
+            let scaled = u64::from(long_side) * u64::from(side) / u64::from(width.max(height));
+            u32::try_from(scaled).unwrap_or(long_side).max(1)
+        };
+        (scale(width), scale(height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_fields_come_from_the_defaults() {
+        let defaults = GenerationOptions {
+            aspect_ratio: Some("1:1".to_string()),
+            negative_prompt: Some("text".to_string()),
+            ..Default::default()
+        };
+        let options = GenerationOptions {
+            aspect_ratio: Some("3:4".to_string()),
+            seed: Some(7),
+            ..Default::default()
+        }
+        .or(&defaults);
+
+        assert_eq!(options.aspect_ratio(), "3:4");
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.negative_prompt.as_deref(), Some("text"));
+        assert_eq!(GenerationOptions::default().aspect_ratio(), "1:1");
+    }
+
+    #[test]
+    fn validates_ratio_and_size() {
+        let ok = GenerationOptions {
+            aspect_ratio: Some("16:9".to_string()),
+            size: Some("1024x576".to_string()),
+            ..Default::default()
+        };
+        assert!(ok.validate().is_ok());
+
+        for (ratio, size) in [
+            (Some("wide"), None),
+            (Some("0:1"), None),
+            (None, Some("big")),
+        ] {
+            let options = GenerationOptions {
+                aspect_ratio: ratio.map(str::to_string),
+                size: size.map(str::to_string),
+                ..Default::default()
+            };
+            assert!(options.validate().is_err(), "{options:?}");
+        }
+    }
+
+    #[test]
+    fn dimensions_follow_size_then_ratio() {
+        let portrait = GenerationOptions {
+            aspect_ratio: Some("3:4".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(portrait.dimensions(512), (384, 512));
+        assert_eq!(GenerationOptions::default().dimensions(512), (512, 512));
+
+        let sized = GenerationOptions {
+            size: Some("640x360".to_string()),
+            ..portrait
+        };
+        assert_eq!(sized.dimensions(512), (640, 360));
+    }
+}
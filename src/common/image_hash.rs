@@ -0,0 +1,79 @@
+use base64::{Engine as _, engine::general_purpose};
+use image::imageops::FilterType;
+
+/// `dhash` is the 64-bit difference hash of an encoded image: it is shrunk
+/// to 9x8 grayscale pixels and every bit tells whether a pixel is brighter
+/// than its right neighbour. Near-identical images get hashes a small
+/// Hamming distance apart. `None` when the image can't be decoded.
+#[must_use]
+pub fn dhash(bytes: &[u8]) -> Option<i64> {
+    let pixels = image::load_from_memory(bytes)
+        .ok()?
+        .resize_exact(9, 8, FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = pixels.get_pixel(x, y)[0];
+            let right = pixels.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    // stored in a signed BIGINT column; only the bits matter
+    Some(i64::from_ne_bytes(hash.to_ne_bytes()))
+}
+
+/// `dhash_base64` is the [`dhash`] of a Base64 encoded image.
+#[must_use]
+pub fn dhash_base64(image: &str) -> Option<i64> {
+    dhash(&general_purpose::STANDARD.decode(image).ok()?)
+}
+
+/// `distance` is the number of bits two hashes differ in, from 0 for the
+/// same image to 64.
+#[must_use]
+pub const fn distance(a: i64, b: i64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, Rgb, RgbImage};
+
+    use super::*;
+
+    fn encode(image: &RgbImage) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn gradient(width: u32, height: u32, flip: bool) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let x = if flip { width - 1 - x } else { x };
+            let value = u8::try_from((x * 255 / width + y * 64 / height) % 256).unwrap();
+            Rgb([value, value / 2, 255 - value])
+        })
+    }
+
+    #[test]
+    fn resized_copies_hash_alike_and_other_images_do_not() {
+        let original = dhash(&encode(&gradient(128, 96, false))).unwrap();
+        let resized = dhash(&encode(&gradient(64, 48, false))).unwrap();
+        let flipped = dhash(&encode(&gradient(128, 96, true))).unwrap();
+
+        assert!(distance(original, resized) <= 4);
+        assert!(distance(original, flipped) > 32);
+        assert_eq!(distance(original, original), 0);
+    }
+
+    #[test]
+    fn undecodable_images_have_no_hash() {
+        assert_eq!(dhash(b"not an image"), None);
+        assert_eq!(dhash_base64("%%%"), None);
+        assert_eq!(distance(0, -1), 64);
+    }
+}
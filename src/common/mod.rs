@@ -1,2 +1,6 @@
 pub mod config;
+pub mod directives;
+pub mod generation_options;
+pub mod image_hash;
 pub mod settings;
+pub mod webp;
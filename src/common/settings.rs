@@ -1,16 +1,469 @@
 use loco_rs::prelude::Result;
 use serde::{Deserialize, Serialize};
 
+use super::generation_options::GenerationOptions;
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Settings {
     pub openrouter_api_key: String,
-    pub text_models: Vec<String>,
-    pub image_models: Vec<String>,
+    pub text_models: Vec<ModelEntry>,
+    pub image_models: Vec<ModelEntry>,
+    /// Flow-specific pools; a flow without its own pool uses the global one.
+    #[serde(default)]
+    pub flows: FlowPools,
+    /// How many Models of a pool a single generation may try before giving up.
+    #[serde(default = "default_model_attempts")]
+    pub model_attempts: usize,
     pub old_db_url: String,
     pub backoffice_password: String,
+    #[serde(default)]
+    pub generation_jobs: GenerationJobSettings,
+    /// Whether mixes send their source images to the image Model.
+    #[serde(default)]
+    pub mix_mode: MixMode,
+    #[serde(default)]
+    pub titles: TitleSettings,
+    /// Options every render starts from; a generation may override any.
+    #[serde(default)]
+    pub image_options: GenerationOptions,
+    /// Images rendered per create, replace or rerender for an editor to pick
+    /// from in the backoffice; 1 saves the single render directly.
+    #[serde(default = "default_candidates")]
+    pub candidates: usize,
+    /// Whether new arts and mixes are saved as drafts that an editor has to
+    /// approve before they are shown publicly.
+    #[serde(default)]
+    pub require_approval: bool,
+    #[serde(default)]
+    pub scheduler: SchedulerSettings,
+    #[serde(default)]
+    pub duplicates: DuplicateSettings,
+    #[serde(default)]
+    pub diversity: DiversitySettings,
+    #[serde(default)]
+    pub mix_limits: MixLimitSettings,
+    #[serde(default)]
+    pub mix_sources: MixSourceSettings,
+}
+
+/// `MixMode` is what a mix hands the image Model besides the written prompt.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MixMode {
+    /// The source arts' images go along as reference inputs, for Models that
+    /// take image input; other Models render from the prompt alone.
+    #[default]
+    References,
+    /// Only the prompt written from the source arts' prompts.
+    Text,
+}
+
+const fn default_model_attempts() -> usize {
+    3
+}
+
+const fn default_candidates() -> usize {
+    1
+}
+
+const fn default_weight() -> u32 {
+    1
+}
+
+const fn default_enabled() -> bool {
+    true
+}
+
+/// `ModelEntry` is one Model of a Model Pool. In config it is either a plain
+/// model ID or a map with `id`, `weight` (default 1), `enabled` (default
+/// true) and `image_input` (default false); a Model is drawn with probability
+/// `weight / sum of weights`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "ModelEntryConfig")]
+pub struct ModelEntry {
+    pub id: String,
+    pub weight: u32,
+    pub enabled: bool,
+    /// Whether the Model takes reference images along with the prompt.
+    pub image_input: bool,
+}
+
+impl ModelEntry {
+    #[must_use]
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            weight: default_weight(),
+            enabled: default_enabled(),
+            image_input: false,
+        }
+    }
+
+    /// Whether the entry can be drawn at all.
+    #[must_use]
+    pub const fn is_drawable(&self) -> bool {
+        self.enabled && self.weight > 0
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ModelEntryConfig {
+    Id(String),
+    Entry {
+        id: String,
+        #[serde(default = "default_weight")]
+        weight: u32,
+        #[serde(default = "default_enabled")]
+        enabled: bool,
+        #[serde(default)]
+        image_input: bool,
+    },
+}
+
+impl From<ModelEntryConfig> for ModelEntry {
+    fn from(value: ModelEntryConfig) -> Self {
+        match value {
+            ModelEntryConfig::Id(id) => Self::new(&id),
+            ModelEntryConfig::Entry {
+                id,
+                weight,
+                enabled,
+                image_input,
+            } => Self {
+                id,
+                weight,
+                enabled,
+                image_input,
+            },
+        }
+    }
+}
+
+/// `Flow` is a generation flow that can have its own Model Pools.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Create,
+    Replace,
+    Rerender,
+    Variation,
+    Mix,
+}
+
+impl Flow {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Replace => "replace",
+            Self::Rerender => "rerender",
+            Self::Variation => "variation",
+            Self::Mix => "mix",
+        }
+    }
+}
+
+/// `FlowPool` overrides the global text and/or image Model Pool for a flow.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct FlowPool {
+    pub text_models: Option<Vec<ModelEntry>>,
+    pub image_models: Option<Vec<ModelEntry>>,
+}
+
+/// `FlowPools` holds the optional per-flow pools.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct FlowPools {
+    pub create: Option<FlowPool>,
+    pub replace: Option<FlowPool>,
+    pub rerender: Option<FlowPool>,
+    pub variation: Option<FlowPool>,
+    pub mix: Option<FlowPool>,
+}
+
+impl FlowPools {
+    const fn get(&self, flow: Flow) -> Option<&FlowPool> {
+        match flow {
+            Flow::Create => self.create.as_ref(),
+            Flow::Replace => self.replace.as_ref(),
+            Flow::Rerender => self.rerender.as_ref(),
+            Flow::Variation => self.variation.as_ref(),
+            Flow::Mix => self.mix.as_ref(),
+        }
+    }
+}
+
+/// `GenerationJobSettings` controls how persisted generation jobs are retried.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct GenerationJobSettings {
+    /// Attempts per job, including the first one.
+    pub max_attempts: i32,
+    /// Delay before the first retry; it doubles on every further attempt.
+    pub backoff_seconds: u64,
+    /// Whether unfinished jobs are picked back up when the server boots.
+    pub resume_on_boot: bool,
+    /// How long a running job holds on to it; only jobs started longer ago
+    /// are taken as interrupted and requeued on boot.
+    pub lease_seconds: u64,
+}
+
+impl Default for GenerationJobSettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_seconds: 30,
+            resume_on_boot: true,
+            lease_seconds: 1800,
+        }
+    }
+}
+
+/// `TitleSettings` is what a generated title must satisfy to be saved.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TitleSettings {
+    /// Longest title, in characters.
+    pub max_length: usize,
+    /// Answers asked for before giving up, including the first one.
+    pub attempts: usize,
+}
+
+impl Default for TitleSettings {
+    fn default() -> Self {
+        Self {
+            max_length: 27,
+            attempts: 3,
+        }
+    }
+}
+
+/// `DiversitySettings` is how different a new prompt must be from the recent
+/// prompts of the gallery.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DiversitySettings {
+    /// Recent prompts a new one is compared with.
+    pub window: u64,
+    /// Highest Jaccard similarity, from 0 to 1, to any recent prompt.
+    pub threshold: f64,
+    /// Words per n-gram compared; 1 compares the sets of words.
+    pub ngram: usize,
+    /// Answers asked for before the last one is kept, including the first.
+    pub attempts: usize,
+}
+
+impl Default for DiversitySettings {
+    fn default() -> Self {
+        Self {
+            window: 10,
+            threshold: 0.5,
+            ngram: 1,
+            attempts: 3,
+        }
+    }
+}
+
+/// `DuplicateSettings` is how near-identical images are caught.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DuplicateSettings {
+    /// Largest Hamming distance, out of 64 bits, between the perceptual
+    /// hashes of two images considered near-duplicates.
+    pub threshold: u32,
+    pub action: DuplicateAction,
+    /// Renders tried, including the first one, before a generation whose
+    /// every render is a near-duplicate fails.
+    pub attempts: usize,
+}
+
+impl Default for DuplicateSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 8,
+            action: DuplicateAction::Flag,
+            attempts: 3,
+        }
+    }
+}
+
+/// `MixLimitSettings` caps what visitors of the public mix page can spend;
+/// 0 turns a limit off.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct MixLimitSettings {
+    /// Mixes one visitor, told apart by IP, may start per `window_seconds`.
+    pub per_ip: usize,
+    pub window_seconds: u64,
+    /// Mixes started by all visitors together per UTC day.
+    pub daily_budget: u64,
+    /// Mixes queued or rendering at once.
+    pub max_concurrent: u64,
+}
+
+impl Default for MixLimitSettings {
+    fn default() -> Self {
+        Self {
+            per_ip: 5,
+            window_seconds: 3600,
+            daily_budget: 200,
+            max_concurrent: 3,
+        }
+    }
+}
+
+/// `MixSourceSettings` bounds how many distinct arts a mix is made from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct MixSourceSettings {
+    pub min_arts: usize,
+    pub max_arts: usize,
+}
+
+impl Default for MixSourceSettings {
+    fn default() -> Self {
+        Self {
+            min_arts: 2,
+            max_arts: 6,
+        }
+    }
+}
+
+impl MixSourceSettings {
+    /// `bounds` is the least and most arts a mix takes, at least one and
+    /// never a maximum below the minimum.
+    #[must_use]
+    pub fn bounds(&self) -> (usize, usize) {
+        let min = self.min_arts.max(1);
+        (min, self.max_arts.max(min))
+    }
+}
+
+/// `DuplicateAction` is what happens to a render that is a near-duplicate of
+/// an existing art or mix.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateAction {
+    /// Saved anyway; it shows up in the backoffice duplicates report.
+    #[default]
+    Flag,
+    /// Dropped and rendered again.
+    Reject,
+}
+
+/// `SchedulerSettings` configures the in-process generation scheduler.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SchedulerSettings {
+    /// Whether the server runs the scheduled jobs.
+    pub enabled: bool,
+    /// A daily window, in UTC, in which no job starts; a run due inside it
+    /// is deferred to its end.
+    pub quiet_hours: Option<QuietHours>,
+    /// How long a run may take; runs still unfinished this long after they
+    /// started, or were due, are marked as interrupted when the scheduler
+    /// starts.
+    pub run_timeout_seconds: u64,
+    pub jobs: Vec<ScheduledJob>,
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quiet_hours: None,
+            run_timeout_seconds: 7200,
+            jobs: Vec::new(),
+        }
+    }
+}
+
+/// `QuietHours` is a daily window from `start` to `end` (`HH:MM`, UTC), which
+/// may wrap past midnight.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+/// `ScheduledJob` is one job of the scheduler.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledJob {
+    /// Unique name the runs are recorded under.
+    pub name: String,
+    pub kind: ScheduledJobKind,
+    /// Cron expression in UTC, with 5 fields (`min hour dom month dow`) or
+    /// with a leading seconds field.
+    pub cron: String,
+    /// Up to this many seconds are randomly added to every run.
+    #[serde(default)]
+    pub jitter_seconds: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// `ScheduledJobKind` is what a scheduled job does.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledJobKind {
+    /// Creates a new art, like `cargo loco task create_art`.
+    CreateArt,
+    /// Rerenders the image of every art flagged for it in the backoffice.
+    RerenderFlagged,
+}
+
+impl ScheduledJobKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CreateArt => "create_art",
+            Self::RerenderFlagged => "rerender_flagged",
+        }
+    }
 }
 
 impl Settings {
+    /// `text_pool` is the text Model Pool for `flow`: its own pool when it
+    /// has a non-empty one, the global pool otherwise.
+    #[must_use]
+    pub fn text_pool(&self, flow: Flow) -> &[ModelEntry] {
+        self.flows
+            .get(flow)
+            .and_then(|pool| pool.text_models.as_deref())
+            .filter(|pool| !pool.is_empty())
+            .unwrap_or(&self.text_models)
+    }
+
+    /// `image_pool` is the image Model Pool for `flow`: its own pool when it
+    /// has a non-empty one, the global pool otherwise.
+    #[must_use]
+    pub fn image_pool(&self, flow: Flow) -> &[ModelEntry] {
+        self.flows
+            .get(flow)
+            .and_then(|pool| pool.image_models.as_deref())
+            .filter(|pool| !pool.is_empty())
+            .unwrap_or(&self.image_models)
+    }
+
+    /// `pinned_image_pool` is a pool of just `model` when it is a member of
+    /// the image Model Pool of `flow`, so a generation can ask for a specific
+    /// Model without reaching outside the configured ones. The Model is drawn
+    /// even when its entry is disabled.
+    #[must_use]
+    pub fn pinned_image_pool(&self, flow: Flow, model: &str) -> Option<Vec<ModelEntry>> {
+        self.image_pool(flow)
+            .iter()
+            .find(|entry| entry.id == model)
+            .map(|entry| {
+                vec![ModelEntry {
+                    weight: 1,
+                    enabled: true,
+                    ..entry.clone()
+                }]
+            })
+    }
+
     /// `from_json` unmarshalls our config into a type checked settings struct
     /// # Errors
     ///
@@ -20,3 +473,91 @@ impl Settings {
         Ok(serde_json::from_value(value.clone())?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(yaml: &str) -> Settings {
+        let value: serde_json::Value = serde_yaml::from_str(yaml).unwrap();
+        Settings::from_json(&value).unwrap()
+    }
+
+    const BASE: &str = r#"
+openrouter_api_key: key
+old_db_url: postgres://
+backoffice_password: pw
+text_models:
+  - text/plain
+image_models:
+  - image/plain
+  - id: image/ramping
+    weight: 9
+  - id: image/off
+    enabled: false
+  - id: image/multimodal
+    image_input: true
+"#;
+
+    #[test]
+    fn model_entries_accept_plain_ids_and_maps() {
+        let s = settings(BASE);
+        assert_eq!(s.text_models, vec![ModelEntry::new("text/plain")]);
+        assert_eq!(s.image_models[1].weight, 9);
+        assert!(s.image_models[1].enabled);
+        assert!(!s.image_models[2].enabled);
+        assert!(!s.image_models[2].is_drawable());
+        assert!(!s.image_models[0].image_input);
+        assert!(s.image_models[3].image_input);
+    }
+
+    #[test]
+    fn mix_source_bounds_stay_in_order() {
+        assert_eq!(settings(BASE).mix_sources.bounds(), (2, 6));
+        let s = settings(&format!(
+            "{BASE}mix_sources:\n  min_arts: 0\n  max_arts: 0\n"
+        ));
+        assert_eq!(s.mix_sources.bounds(), (1, 1));
+        let s = settings(&format!(
+            "{BASE}mix_sources:\n  min_arts: 4\n  max_arts: 3\n"
+        ));
+        assert_eq!(s.mix_sources.bounds(), (4, 4));
+    }
+
+    #[test]
+    fn mix_mode_defaults_to_references() {
+        assert_eq!(settings(BASE).mix_mode, MixMode::References);
+        assert_eq!(
+            settings(&format!("{BASE}mix_mode: text\n")).mix_mode,
+            MixMode::Text
+        );
+    }
+
+    #[test]
+    fn flows_fall_back_to_the_global_pools() {
+        let s = settings(&format!(
+            "{BASE}flows:\n  mix:\n    image_models:\n      - image/expensive\n  replace:\n    image_models: []\n"
+        ));
+        assert_eq!(
+            s.image_pool(Flow::Mix),
+            [ModelEntry::new("image/expensive")]
+        );
+        assert_eq!(s.text_pool(Flow::Mix), s.text_models.as_slice());
+        assert_eq!(s.image_pool(Flow::Replace), s.image_models.as_slice());
+        assert_eq!(s.image_pool(Flow::Create), s.image_models.as_slice());
+    }
+
+    #[test]
+    fn pinned_pools_only_hold_members_of_the_flow_pool() {
+        let s = settings(BASE);
+        let pinned = s.pinned_image_pool(Flow::Create, "image/off").unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert!(pinned[0].is_drawable());
+        assert!(
+            s.pinned_image_pool(Flow::Create, "image/multimodal")
+                .unwrap()[0]
+                .image_input
+        );
+        assert!(s.pinned_image_pool(Flow::Create, "image/unknown").is_none());
+    }
+}
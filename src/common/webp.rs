@@ -0,0 +1,82 @@
+use std::io::Cursor;
+
+use image::{ImageError, ImageFormat};
+
+/// The formats an image can be uploaded in.
+pub const UPLOAD_FORMATS: [ImageFormat; 3] =
+    [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP];
+
+/// `to_webp` takes in a slice of bytes of an image and converts it to `.webp`
+///
+/// # Errors
+///
+/// When the bytes can't be decoded as an image or re-encoded.
+pub fn to_webp(image_bytes: &[u8]) -> Result<Vec<u8>, ImageError> {
+    let img = image::load_from_memory(image_bytes)?;
+    let mut webp_buffer = Cursor::new(Vec::new());
+    img.write_to(&mut webp_buffer, ImageFormat::WebP)?;
+    Ok(webp_buffer.into_inner())
+}
+
+/// `upload_to_webp` is [`to_webp`] for an uploaded file, which must be one of
+/// the [`UPLOAD_FORMATS`] and decode completely.
+///
+/// # Errors
+///
+/// With a message for the uploader when the file is of another format or
+/// can't be decoded.
+pub fn upload_to_webp(image_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let format = image::guess_format(image_bytes)
+        .map_err(|_| "The file is not a PNG, JPEG or WebP image.".to_string())?;
+    if !UPLOAD_FORMATS.contains(&format) {
+        return Err(format!(
+            "{} images can't be uploaded, only PNG, JPEG and WebP.",
+            format
+                .extensions_str()
+                .first()
+                .map_or("These", |ext| ext)
+                .to_uppercase()
+        ));
+    }
+    to_webp(image_bytes).map_err(|e| format!("The image could not be decoded: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    fn encoded(format: ImageFormat) -> Vec<u8> {
+        let image = RgbImage::from_fn(4, 3, |x, y| Rgb([x as u8 * 60, y as u8 * 80, 90]));
+        let mut buffer = Cursor::new(Vec::new());
+        image.write_to(&mut buffer, format).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn uploads_are_converted_to_webp() {
+        for format in UPLOAD_FORMATS {
+            let webp = upload_to_webp(&encoded(format)).unwrap();
+            assert_eq!(image::guess_format(&webp).unwrap(), ImageFormat::WebP);
+            let decoded = image::load_from_memory(&webp).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (4, 3));
+        }
+    }
+
+    #[test]
+    fn other_formats_and_broken_files_are_rejected() {
+        let gif = upload_to_webp(&encoded(ImageFormat::Gif)).unwrap_err();
+        assert!(gif.starts_with("GIF images can't be uploaded"), "{gif}");
+
+        assert!(upload_to_webp(b"not an image").is_err());
+
+        let mut truncated = encoded(ImageFormat::Png);
+        truncated.truncate(truncated.len() / 2);
+        let broken = upload_to_webp(&truncated).unwrap_err();
+        assert!(
+            broken.starts_with("The image could not be decoded"),
+            "{broken}"
+        );
+    }
+}
@@ -11,16 +11,17 @@ use sitemap_rs::url::{ChangeFrequency, Url};
 use sitemap_rs::url_set::UrlSet;
 
 use crate::models::arts::ArtTitleId;
-use crate::{
-    models::_entities::arts::{Entity, Model},
-    views,
-};
+use crate::{models::_entities::arts::Model, views};
 
 use super::utils::ExtractId;
 
 async fn load_item(ctx: &AppContext, id: i32) -> Result<Model> {
-    let item = Entity::find_by_id(id).one(&ctx.db).await?;
-    item.ok_or_else(|| Error::NotFound)
+    Model::find_published(&ctx.db, id)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })
 }
 
 #[debug_handler]
@@ -29,11 +30,11 @@ pub async fn show(
     ViewEngine(v): ViewEngine<TeraView>,
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
-    let latest_id = Model::find_latest_id(&ctx.db).await?;
     let item = load_item(&ctx, id).await?;
-    let latest = latest_id == item.id;
+    let previous_id = Model::find_previous_published_id(&ctx.db, item.id).await?;
+    let next_id = Model::find_next_published_id(&ctx.db, item.id).await?;
 
-    views::arts::show(&v, &item, latest)
+    views::arts::show(&v, &item, previous_id, next_id)
 }
 
 #[debug_handler]
@@ -42,7 +43,9 @@ pub async fn show_latest(
     State(ctx): State<AppContext>,
 ) -> Result<Response> {
     let item = Model::find_latest(&ctx.db).await?;
-    views::arts::show(&v, &item, true)
+    let previous_id = Model::find_previous_published_id(&ctx.db, item.id).await?;
+
+    views::arts::show(&v, &item, previous_id, None)
 }
 
 #[derive(Serialize, Deserialize)]
@@ -57,11 +60,7 @@ pub async fn show_infinite(
     Query(id): Query<IdQuery>,
 ) -> Result<Response> {
     let latest: ArtTitleId = match id.id {
-        Some(i) => Entity::find_by_id(i)
-            .one(&ctx.db)
-            .await?
-            .ok_or_else(|| Error::NotFound)?
-            .into(),
+        Some(i) => load_item(&ctx, i).await?.into(),
         _ => Model::find_latest(&ctx.db).await?.into(),
     };
 
@@ -98,7 +97,12 @@ pub async fn serve_image(
 ) -> Result<Response> {
     //TODO: support png too.
     let (id, _format) = id.extract_id().ok_or_else(|| Error::NotFound)?;
-    let bytes = Model::find_img_slice_by_id(&ctx.db, id).await?;
+    let bytes = Model::find_img_slice_by_id(&ctx.db, id)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })?;
 
     Ok((
         StatusCode::OK,
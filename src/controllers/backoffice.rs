@@ -3,19 +3,34 @@
 
 use axum::{
     Form, debug_handler,
-    extract::Query,
+    extract::{Multipart, Query, multipart::MultipartError},
+    http::{StatusCode, header},
     response::{IntoResponse, Redirect},
 };
 use axum_extra::extract::cookie::CookieJar;
 use loco_rs::prelude::*;
 use sea_orm::EntityTrait;
-use serde::Deserialize;
-use tracing::error;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    models::arts::{self, ArtUpdateParams},
-    models::{mixarts, mixes},
-    services::{art_service, backoffice_auth},
+    common::{
+        directives::Directives,
+        settings::{Flow, Settings},
+    },
+    models::arts::{self, ArtUpdateParams, PublicationStatus},
+    models::{
+        art_candidates, art_revisions,
+        generation_jobs::{self as jobs, JobKind, JobPayload},
+        generation_usage, mixarts, mixes,
+        prompt_templates::{self, TemplateKind, TemplateParams},
+        scheduled_runs,
+        style_presets::{self, StylePresetParams},
+    },
+    services::{
+        art_service::{self, ArtUpload},
+        backoffice_auth, duplicates as duplicates_service, generation_jobs,
+        prompt_templates::{self as prompt_templates_service, PromptTemplates},
+    },
     views,
 };
 
@@ -26,15 +41,50 @@ pub fn routes() -> Routes {
         .add("/login", get(login_form))
         .add("/login", post(login))
         .add("/logout", post(logout))
+        .add("/approvals", get(approvals))
+        .add("/duplicates", get(duplicates))
         .add("/arts", get(index))
+        .add("/arts", post(create))
+        .add("/upload", get(upload_form))
+        .add("/upload", post(upload))
         .add("/arts/{id}", get(show))
         .add("/arts/{id}", post(update))
+        .add("/arts/{id}/image", get(art_image))
         .add("/arts/{id}/delete", post(delete))
+        .add("/arts/{id}/status", post(art_status))
+        .add("/arts/{id}/rerender-flag", post(rerender_flag))
+        .add("/arts/{id}/export.json", get(export))
         .add("/arts/{id}/replace", post(replace))
         .add("/arts/{id}/rerender", post(rerender))
+        .add("/arts/{id}/variation", post(variation))
+        .add("/arts/{id}/candidates/discard", post(candidates_discard))
+        .add("/arts/{id}/candidates/{candidate_id}", get(candidate_image))
+        .add(
+            "/arts/{id}/candidates/{candidate_id}/pick",
+            post(candidate_pick),
+        )
+        .add("/arts/{id}/revisions/{revision_id}", get(revision_image))
+        .add(
+            "/arts/{id}/revisions/{revision_id}/restore",
+            post(revision_restore),
+        )
         .add("/mixes", get(mix_index))
         .add("/mixes/{id}", get(mix_show))
+        .add("/mixes/{id}/image", get(mix_image))
         .add("/mixes/{id}/delete", post(mix_delete))
+        .add("/mixes/{id}/status", post(mix_status))
+        .add("/mixes/{id}/export.json", get(mix_export))
+        .add("/styles", get(style_index))
+        .add("/styles", post(style_save))
+        .add("/styles/{id}/delete", post(style_delete))
+        .add("/templates", get(template_index))
+        .add("/templates/{kind}", get(template_show))
+        .add("/templates/{kind}", post(template_submit))
+        .add("/templates/{kind}/builtin", post(template_use_builtin))
+        .add(
+            "/templates/{kind}/versions/{id}/activate",
+            post(template_activate),
+        )
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -65,6 +115,60 @@ pub struct ArtUpdateForm {
     model: String,
 }
 
+#[derive(Debug, Deserialize, Default)]
+pub struct StatusForm {
+    status: String,
+    /// `approvals` to return to the approval queue instead of the item.
+    from: Option<String>,
+}
+
+/// `CreateArtForm` steers a new art; blank fields are left to the generation.
+/// `request_id` names the progress room the page listens to.
+#[derive(Debug, Deserialize, Default)]
+pub struct CreateArtForm {
+    request_id: Option<String>,
+    theme: Option<String>,
+    style: Option<String>,
+    model: Option<String>,
+    prompt: Option<String>,
+}
+
+/// `DirectivesForm` is the theme and style a regeneration should follow; blank
+/// fields are unset.
+#[derive(Debug, Deserialize, Default)]
+pub struct DirectivesForm {
+    theme: Option<String>,
+    style: Option<String>,
+}
+
+/// `VariationForm` is what a variation should change, and whether it is
+/// saved over the art (`replace`) or as a new art (`new`).
+#[derive(Debug, Deserialize, Default)]
+pub struct VariationForm {
+    instruction: String,
+    #[serde(default)]
+    mode: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct StylePresetForm {
+    name: String,
+    directive: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RerenderFlagForm {
+    flagged: bool,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct TemplateForm {
+    body: String,
+    note: Option<String>,
+    activate: Option<String>,
+    action: Option<String>,
+}
+
 #[debug_handler]
 pub async fn login_form(
     ViewEngine(v): ViewEngine<TeraView>,
@@ -116,9 +220,208 @@ pub async fn dashboard(
     }
 
     let stats = arts::Model::backoffice_stats(&ctx.db).await?;
+    let spend = generation_usage::Model::spend_stats(&ctx.db).await?;
     let recent_arts = arts::Model::find_n_latest(&ctx.db, 2).await?;
     let recent_mixes = mixes::Model::find_n_latest(&ctx.db, 4).await?;
-    views::backoffice::dashboard(&v, &stats, &recent_arts, &recent_mixes)
+    let recent_jobs = jobs::Model::find_n_latest(&ctx.db, 8).await?;
+    let upcoming_runs = scheduled_runs::Model::find_upcoming(&ctx.db).await?;
+    let recent_runs = scheduled_runs::Model::find_n_latest(&ctx.db, 8).await?;
+    let presets = style_presets::Model::find_all(&ctx.db).await?;
+    let image_models = create_image_models(&ctx)?;
+    views::backoffice::dashboard(
+        &v,
+        &stats,
+        &spend,
+        &recent_arts,
+        &recent_mixes,
+        &recent_jobs,
+        &upcoming_runs,
+        &recent_runs,
+        &presets,
+        &image_models,
+    )
+}
+
+/// `create` queues a new art. The page follows it in the creation progress
+/// room of the submitted request id, and is sent to the art when it is done.
+#[debug_handler]
+pub async fn create(
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Form(form): Form<CreateArtForm>,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let non_blank = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let model = non_blank(form.model);
+    if let Some(model) = &model
+        && !create_image_models(&ctx)?.contains(model)
+    {
+        return Err(Error::BadRequest(format!(
+            "Image model {model} is not in the create pool"
+        )));
+    }
+    let request_id = form
+        .request_id
+        .as_deref()
+        .and_then(|request_id| Uuid::parse_str(request_id).ok())
+        .unwrap_or_else(Uuid::new_v4);
+
+    generation_jobs::enqueue(
+        &ctx,
+        JobKind::Create,
+        None,
+        JobPayload {
+            request_id: Some(request_id),
+            directives: Directives::new(form.theme.as_deref(), form.style.as_deref()),
+            model,
+            prompt: non_blank(form.prompt),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(Redirect::to("/backoffice").into_response())
+}
+
+#[debug_handler]
+pub async fn upload_form(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    views::backoffice::upload(&v, &UploadValues::default(), None)
+}
+
+/// `UploadValues` are the text fields of the upload form, kept to fill it in
+/// again when the upload is rejected.
+#[derive(Debug, Default, Serialize)]
+pub struct UploadValues {
+    title: String,
+    prompt: String,
+    model: String,
+}
+
+/// `upload` saves an image made elsewhere as a new art. Rejected uploads show
+/// the form again with the reason; a body over the `limit_payload` size is
+/// rejected while it is read.
+#[debug_handler]
+pub async fn upload(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    mut multipart: Multipart,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let mut values = UploadValues::default();
+    let mut image = Vec::new();
+    let read: std::result::Result<(), MultipartError> = async {
+        while let Some(field) = multipart.next_field().await? {
+            match field.name() {
+                Some("image") => image = field.bytes().await?.to_vec(),
+                Some("title") => values.title = field.text().await?,
+                Some("prompt") => values.prompt = field.text().await?,
+                Some("model") => values.model = field.text().await?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(err) = read {
+        let error = if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            "The upload is larger than the payload limit.".to_string()
+        } else {
+            format!("The upload could not be read: {}", err.body_text())
+        };
+        return rejected_upload(&v, &values, err.status(), &error);
+    }
+
+    let uploaded = art_service::upload_art(
+        &ctx,
+        &ArtUpload {
+            image,
+            title: values.title.clone(),
+            prompt: values.prompt.clone(),
+            model: Some(values.model.clone()),
+        },
+    )
+    .await;
+    match uploaded {
+        Ok(art) => Ok(Redirect::to(&format!("/backoffice/arts/{}", art.id)).into_response()),
+        Err(Error::BadRequest(error)) => {
+            rejected_upload(&v, &values, StatusCode::UNPROCESSABLE_ENTITY, &error)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn rejected_upload(
+    v: &TeraView,
+    values: &UploadValues,
+    status: StatusCode,
+    error: &str,
+) -> Result<Response> {
+    let mut response = views::backoffice::upload(v, values, Some(error))?;
+    *response.status_mut() = status;
+    Ok(response)
+}
+
+/// `create_image_models` are the Models a new art can be pinned to.
+fn create_image_models(ctx: &AppContext) -> Result<Vec<String>> {
+    let settings = Settings::from_json(
+        &ctx.config
+            .settings
+            .clone()
+            .ok_or(Error::Message("Invalid settings".into()))?,
+    )?;
+    Ok(settings
+        .image_pool(Flow::Create)
+        .iter()
+        .map(|entry| entry.id.clone())
+        .collect())
+}
+
+#[debug_handler]
+pub async fn approvals(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let arts = arts::Model::find_by_status(&ctx.db, PublicationStatus::Draft).await?;
+    let mixes = mixes::Model::find_by_status(&ctx.db, PublicationStatus::Draft).await?;
+    views::backoffice::approvals(&v, &arts, &mixes)
+}
+
+#[debug_handler]
+pub async fn duplicates(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let (pairs, threshold) = duplicates_service::report(&ctx).await?;
+    views::backoffice::duplicates(&v, &pairs, threshold)
 }
 
 #[debug_handler]
@@ -157,6 +460,24 @@ pub async fn show(
     render_art_detail(&ctx, &v, id, notice, None).await
 }
 
+#[debug_handler]
+pub async fn export(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let item = arts::Entity::find_by_id(id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+
+    views::backoffice::art_export(&item)
+}
+
 #[debug_handler]
 pub async fn update(
     Path(id): Path<i32>,
@@ -203,20 +524,18 @@ pub async fn replace(
     Path(id): Path<i32>,
     State(ctx): State<AppContext>,
     jar: CookieJar,
+    Form(form): Form<DirectivesForm>,
 ) -> Result<Response> {
     if let Some(response) = require_auth(&ctx, &jar)? {
         return Ok(response);
     }
 
     let item = load_item(&ctx, id).await?;
-    let art_uuid = item.uuid;
-
-    let ctx = ctx.clone();
-    tokio::spawn(async move {
-        if let Err(err) = art_service::replace_art_with_progress(&ctx, id, art_uuid).await {
-            error!(art_id = id, error = %err, "background art regeneration failed");
-        }
-    });
+    let payload = JobPayload {
+        directives: Directives::new(form.theme.as_deref(), form.style.as_deref()),
+        ..Default::default()
+    };
+    generation_jobs::enqueue(&ctx, JobKind::Replace, Some(item.id), payload).await?;
 
     Ok(Redirect::to(&format!("/backoffice/arts/{id}?queued=1")).into_response())
 }
@@ -232,18 +551,214 @@ pub async fn rerender(
     }
 
     let item = load_item(&ctx, id).await?;
-    let art_uuid = item.uuid;
+    generation_jobs::enqueue(
+        &ctx,
+        JobKind::Rerender,
+        Some(item.id),
+        JobPayload::default(),
+    )
+    .await?;
 
-    let ctx = ctx.clone();
-    tokio::spawn(async move {
-        if let Err(err) = art_service::rerender_art_image_with_progress(&ctx, id, art_uuid).await {
-            error!(art_id = id, error = %err, "background image-only regeneration failed");
-        }
-    });
+    Ok(Redirect::to(&format!("/backoffice/arts/{id}?queued=1")).into_response())
+}
+
+#[debug_handler]
+pub async fn variation(
+    Path(id): Path<i32>,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Form(form): Form<VariationForm>,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let instruction = form.instruction.trim();
+    if instruction.is_empty() {
+        return render_art_detail(
+            &ctx,
+            &v,
+            id,
+            None,
+            Some("Tell what the variation should change."),
+        )
+        .await;
+    }
+
+    let item = load_item(&ctx, id).await?;
+    let payload = JobPayload {
+        instruction: Some(instruction.to_string()),
+        as_new: form.mode == "new",
+        request_id: Some(Uuid::new_v4()),
+        ..Default::default()
+    };
+    generation_jobs::enqueue(&ctx, JobKind::Variation, Some(item.id), payload).await?;
 
     Ok(Redirect::to(&format!("/backoffice/arts/{id}?queued=1")).into_response())
 }
 
+#[debug_handler]
+pub async fn art_status(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Form(form): Form<StatusForm>,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let status = form.status.parse().map_err(Error::BadRequest)?;
+    arts::Model::set_status(&ctx.db, id, status).await?;
+    Ok(redirect_after_status(
+        form.from.as_deref(),
+        &format!("/backoffice/arts/{id}"),
+    ))
+}
+
+#[debug_handler]
+pub async fn rerender_flag(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Form(form): Form<RerenderFlagForm>,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    arts::Model::set_rerender_flag(&ctx.db, id, form.flagged)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })?;
+    Ok(Redirect::to(&format!("/backoffice/arts/{id}")).into_response())
+}
+
+/// `private_image` serves image bytes to a signed-in editor only, so drafts
+/// and rejected work never land in a shared cache.
+fn private_image(bytes: Vec<u8>) -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "image/webp"),
+            (header::CACHE_CONTROL, "private, max-age=86400"),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
+#[debug_handler]
+pub async fn art_image(
+    Path(id): Path<u32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let bytes = arts::Model::find_any_img_slice_by_id(&ctx.db, id)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })?;
+    Ok(private_image(bytes))
+}
+
+#[debug_handler]
+pub async fn mix_image(
+    Path(id): Path<u32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let bytes = mixes::Model::find_any_img_slice_by_id(&ctx.db, id)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })?;
+    Ok(private_image(bytes))
+}
+
+#[debug_handler]
+pub async fn candidate_image(
+    Path((id, candidate_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let bytes = art_candidates::Model::find_img_slice(&ctx.db, id, candidate_id).await?;
+    Ok(private_image(bytes))
+}
+
+#[debug_handler]
+pub async fn candidate_pick(
+    Path((id, candidate_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    art_candidates::Model::pick(&ctx.db, id, candidate_id).await?;
+    Ok(Redirect::to(&format!("/backoffice/arts/{id}")).into_response())
+}
+
+#[debug_handler]
+pub async fn revision_image(
+    Path((id, revision_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let bytes = art_revisions::Model::find_img_slice(&ctx.db, id, revision_id).await?;
+    Ok(private_image(bytes))
+}
+
+#[debug_handler]
+pub async fn revision_restore(
+    Path((id, revision_id)): Path<(i32, i32)>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    art_revisions::Model::restore(&ctx.db, id, revision_id).await?;
+    Ok(Redirect::to(&format!("/backoffice/arts/{id}")).into_response())
+}
+
+#[debug_handler]
+pub async fn candidates_discard(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    art_candidates::Model::discard_all(&ctx.db, id).await?;
+    Ok(Redirect::to(&format!("/backoffice/arts/{id}")).into_response())
+}
+
 #[debug_handler]
 pub async fn delete(
     Path(id): Path<i32>,
@@ -289,8 +804,35 @@ pub async fn mix_show(
         .await?
         .ok_or_else(|| Error::NotFound)?;
     let art_ids = mixarts::Model::find_art_ids(&ctx.db, id).await?;
+    let templates = prompt_templates::Model::find_in(
+        &ctx.db,
+        [item.prompt_template_id, item.title_template_id]
+            .into_iter()
+            .flatten()
+            .collect(),
+    )
+    .await?;
 
-    views::backoffice::mix_detail(&v, &item, &art_ids)
+    views::backoffice::mix_detail(&v, &item, &art_ids, &templates)
+}
+
+#[debug_handler]
+pub async fn mix_export(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let item = mixes::Entity::find_by_id(id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::NotFound)?;
+    let art_ids = mixarts::Model::find_art_ids(&ctx.db, id).await?;
+
+    views::backoffice::mix_export(&item, &art_ids)
 }
 
 #[debug_handler]
@@ -307,11 +849,253 @@ pub async fn mix_delete(
     Ok(Redirect::to("/backoffice/mixes").into_response())
 }
 
+#[debug_handler]
+pub async fn mix_status(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Form(form): Form<StatusForm>,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let status = form.status.parse().map_err(Error::BadRequest)?;
+    mixes::Model::set_status(&ctx.db, id, status).await?;
+    Ok(redirect_after_status(
+        form.from.as_deref(),
+        &format!("/backoffice/mixes/{id}"),
+    ))
+}
+
+#[debug_handler]
+pub async fn style_index(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let presets = style_presets::Model::find_all(&ctx.db).await?;
+    views::backoffice::style_index(&v, &presets, None)
+}
+
+/// `style_save` adds a preset, or replaces the directive of the preset with
+/// the same name.
+#[debug_handler]
+pub async fn style_save(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Form(form): Form<StylePresetForm>,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let saved = style_presets::Model::save(
+        &ctx.db,
+        &StylePresetParams {
+            name: form.name,
+            directive: form.directive,
+        },
+    )
+    .await;
+
+    match saved {
+        Ok(_) => Ok(Redirect::to("/backoffice/styles").into_response()),
+        Err(ModelError::Message(error)) => {
+            let presets = style_presets::Model::find_all(&ctx.db).await?;
+            views::backoffice::style_index(&v, &presets, Some(&error))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[debug_handler]
+pub async fn style_delete(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    style_presets::Model::delete_by_id(&ctx.db, id)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })?;
+    Ok(Redirect::to("/backoffice/styles").into_response())
+}
+
+#[debug_handler]
+pub async fn template_index(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let templates = PromptTemplates::load(&ctx.db).await?;
+    let mut kinds = Vec::new();
+    for kind in TemplateKind::ALL {
+        let versions = prompt_templates::Model::find_versions(&ctx.db, kind).await?;
+        kinds.push((kind, templates.get(kind).clone(), versions.len()));
+    }
+    views::backoffice::template_index(&v, &kinds)
+}
+
+#[debug_handler]
+pub async fn template_show(
+    Path(kind): Path<String>,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let kind = parse_template_kind(&kind)?;
+    let templates = PromptTemplates::load(&ctx.db).await?;
+    let body = templates.get(kind).body.clone();
+    render_template_editor(&ctx, &v, kind, &body, None, None).await
+}
+
+/// `template_submit` previews the submitted draft, or saves it as a new
+/// version, depending on the button used.
+#[debug_handler]
+pub async fn template_submit(
+    Path(kind): Path<String>,
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+    Form(form): Form<TemplateForm>,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let kind = parse_template_kind(&kind)?;
+    let missing = kind.missing_placeholders(&form.body);
+    if !missing.is_empty() {
+        let error = format!("The template is missing {}.", missing.join(", "));
+        return render_template_editor(&ctx, &v, kind, &form.body, None, Some(&error)).await;
+    }
+
+    if form.action.as_deref() == Some("preview") {
+        let preview = prompt_templates_service::preview(&ctx.db, kind, &form.body).await?;
+        return render_template_editor(&ctx, &v, kind, &form.body, Some(&preview), None).await;
+    }
+
+    let saved = prompt_templates::Model::create_version(
+        &ctx.db,
+        &TemplateParams {
+            kind,
+            body: form.body.clone(),
+            note: form
+                .note
+                .as_deref()
+                .map(str::trim)
+                .filter(|note| !note.is_empty())
+                .map(ToString::to_string),
+            activate: form.activate.is_some(),
+        },
+    )
+    .await;
+
+    match saved {
+        Ok(_) => Ok(Redirect::to(&format!("/backoffice/templates/{kind}")).into_response()),
+        Err(ModelError::Message(error)) => {
+            render_template_editor(&ctx, &v, kind, &form.body, None, Some(&error)).await
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[debug_handler]
+pub async fn template_activate(
+    Path((kind, id)): Path<(String, i32)>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let kind = parse_template_kind(&kind)?;
+    let template = prompt_templates::Entity::find_by_id(id)
+        .one(&ctx.db)
+        .await?
+        .filter(|template| template.kind == kind.as_str())
+        .ok_or_else(|| Error::NotFound)?;
+    prompt_templates::Model::activate(&ctx.db, template.id).await?;
+
+    Ok(Redirect::to(&format!("/backoffice/templates/{kind}")).into_response())
+}
+
+#[debug_handler]
+pub async fn template_use_builtin(
+    Path(kind): Path<String>,
+    State(ctx): State<AppContext>,
+    jar: CookieJar,
+) -> Result<Response> {
+    if let Some(response) = require_auth(&ctx, &jar)? {
+        return Ok(response);
+    }
+
+    let kind = parse_template_kind(&kind)?;
+    prompt_templates::Model::use_builtin(&ctx.db, kind).await?;
+
+    Ok(Redirect::to(&format!("/backoffice/templates/{kind}")).into_response())
+}
+
+fn parse_template_kind(kind: &str) -> Result<TemplateKind> {
+    kind.parse().map_err(|_| Error::NotFound)
+}
+
+async fn render_template_editor(
+    ctx: &AppContext,
+    v: &TeraView,
+    kind: TemplateKind,
+    body: &str,
+    preview: Option<&prompt_templates_service::Preview>,
+    error: Option<&str>,
+) -> Result<Response> {
+    let templates = PromptTemplates::load(&ctx.db).await?;
+    let versions = prompt_templates::Model::find_versions(&ctx.db, kind).await?;
+
+    views::backoffice::template_editor(
+        v,
+        kind,
+        templates.get(kind),
+        &versions,
+        body,
+        preview,
+        error,
+    )
+}
+
 fn normalize_model(model: &str) -> Option<String> {
     let model = model.trim();
     (!model.is_empty()).then(|| model.to_string())
 }
 
+/// Status changes made from the approval queue return to it.
+fn redirect_after_status(from: Option<&str>, item_path: &str) -> Response {
+    match from {
+        Some("approvals") => Redirect::to("/backoffice/approvals").into_response(),
+        _ => Redirect::to(item_path).into_response(),
+    }
+}
+
 fn require_auth(ctx: &AppContext, jar: &CookieJar) -> Result<Option<Response>> {
     if backoffice_auth::is_authenticated(ctx, jar)? {
         return Ok(None);
@@ -330,8 +1114,34 @@ async fn render_art_detail(
     let item = load_item(ctx, id).await?;
     let previous_id = arts::Model::find_previous_id(&ctx.db, id).await?;
     let next_id = arts::Model::find_next_id(&ctx.db, id).await?;
+    let active_jobs = jobs::Model::find_active_for_art(&ctx.db, id).await?;
+    let templates = prompt_templates::Model::find_in(
+        &ctx.db,
+        [item.prompt_template_id, item.title_template_id]
+            .into_iter()
+            .flatten()
+            .collect(),
+    )
+    .await?;
+    let candidates = art_candidates::Model::find_by_art(&ctx.db, id).await?;
+    let presets = style_presets::Model::find_all(&ctx.db).await?;
+    let lineage = arts::Model::find_lineage(&ctx.db, &item).await?;
+    let revisions = art_revisions::Model::find_history(&ctx.db, &item).await?;
 
-    views::backoffice::art_detail(v, &item, previous_id, next_id, notice, error)
+    views::backoffice::art_detail(
+        v,
+        &item,
+        previous_id,
+        next_id,
+        &active_jobs,
+        &templates,
+        &candidates,
+        &presets,
+        &lineage,
+        &revisions,
+        notice,
+        error,
+    )
 }
 
 async fn load_item(ctx: &AppContext, id: i32) -> Result<arts::Model> {
@@ -3,6 +3,7 @@
 #![allow(clippy::unused_async)]
 use axum::{
     debug_handler,
+    extract::Query,
     http::{StatusCode, header},
 };
 use loco_rs::prelude::*;
@@ -10,19 +11,18 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::{
-    common::settings::Settings,
+    common::{generation_options::GenerationOptions, settings::Settings},
     models::{
-        _entities::{mixarts, mixes},
-        arts::{self, ModelVec},
-        mixarts::MixArtParams,
-        mixes::MixParams,
+        _entities::mixarts,
+        arts,
+        generation_jobs::JobPayload,
+        mixes::{self, MixTitleId},
     },
-    services::{realtime, service_provider::ServiceProvider},
-    tasks::art_prompts::{MIX_IMAGE_PROMPT, TITLE_PROMPT},
+    services::{mix_limits, mix_service, realtime},
     views,
 };
 
-use super::utils::ExtractId;
+use super::utils::{ClientIp, ExtractId};
 
 pub fn routes() -> Routes {
     Routes::new()
@@ -33,15 +33,41 @@ pub fn routes() -> Routes {
         .add("/img/{id}", get(serve_image))
 }
 
+pub fn gallery_routes() -> Routes {
+    Routes::new()
+        .prefix("/mixes")
+        .add("/", get(index))
+        .add("/infinite", get(show_infinite))
+        .add("/api/before/{id}", get(cursor_before_json))
+        .add("/api/after/{id}", get(cursor_after_json))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IndexQuery {
+    before: Option<i32>,
+    after: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdQuery {
+    id: Option<i32>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct MixReqParams {
     art_ids: Vec<i32>,
     request_id: String,
+    #[serde(default)]
+    options: Option<GenerationOptions>,
 }
 
 async fn load_item(ctx: &AppContext, id: i32) -> Result<mixes::Model> {
-    let item = mixes::Entity::find_by_id(id).one(&ctx.db).await?;
-    item.ok_or_else(|| Error::NotFound)
+    mixes::Model::find_published(&ctx.db, id)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })
 }
 
 #[debug_handler]
@@ -57,155 +83,194 @@ pub async fn show(
     views::mixes::show(&v, &item, &ids)
 }
 
+/// `with_sources` lists `items` along with the source arts of each mix.
+async fn with_sources(
+    ctx: &AppContext,
+    items: &[MixTitleId],
+) -> Result<Vec<views::mixes::ListResponse>> {
+    let ids = items.iter().map(|mix| mix.id).collect::<Vec<_>>();
+    let art_ids = mixarts::Model::find_art_ids_by_mix(&ctx.db, &ids).await?;
+    Ok(views::mixes::list_response(items, &art_ids))
+}
+
 #[debug_handler]
-pub async fn show_form(
+pub async fn index(
     ViewEngine(v): ViewEngine<TeraView>,
     State(ctx): State<AppContext>,
+    Query(cursor): Query<IndexQuery>,
 ) -> Result<Response> {
-    let title_ids = arts::Model::find_all_title_ids(&ctx.db).await?;
-    views::mixes::show_form(&v, &title_ids)
+    let items = match (cursor.before, cursor.after) {
+        (None, Some(after)) => {
+            let mut items =
+                mixes::Model::find_page_after(&ctx.db, after, mixes::INDEX_PAGE_SIZE).await?;
+            items.reverse();
+            items
+        }
+        (before, _) => {
+            let before = before.unwrap_or(i32::MAX);
+            mixes::Model::find_page_before(&ctx.db, before, mixes::INDEX_PAGE_SIZE).await?
+        }
+    };
+
+    let newer_id = match items.first() {
+        Some(first)
+            if !mixes::Model::find_page_after(&ctx.db, first.id, 1)
+                .await?
+                .is_empty() =>
+        {
+            Some(first.id)
+        }
+        _ => None,
+    };
+    let older_id = match items.last() {
+        Some(last)
+            if !mixes::Model::find_page_before(&ctx.db, last.id, 1)
+                .await?
+                .is_empty() =>
+        {
+            Some(last.id)
+        }
+        _ => None,
+    };
+
+    let items = with_sources(&ctx, &items).await?;
+    views::mixes::show_index(&v, &items, newer_id, older_id)
 }
 
 #[debug_handler]
-pub async fn create(
+pub async fn show_infinite(
+    ViewEngine(v): ViewEngine<TeraView>,
     State(ctx): State<AppContext>,
-    Json(params): Json<MixReqParams>,
+    Query(id): Query<IdQuery>,
 ) -> Result<Response> {
-    let request_id = Uuid::parse_str(&params.request_id)
-        .map_err(|_| Error::Message("Invalid mix request id".into()))?;
-
-    let result = async {
-        realtime::emit_mix_progress(
-            &request_id,
-            &realtime::ProgressUpdate::new("preparing", "Collecting the selected source images..."),
-        )
-        .await;
-
-        let settings = Settings::from_json(
-            &ctx.config
-                .settings
-                .clone()
-                .ok_or(Error::Message("Invalid settings".into()))?,
-        )?;
-
-        let arts = arts::Model::find_in(&ctx.db, params.art_ids.clone()).await?;
-
-        let img_gen = ServiceProvider::random_img_service(&settings)
-            .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?;
-        let text_gen = ServiceProvider::random_txt_service(&settings)
-            .map_err(|e| Error::Message(format!("Unable to configure text generator: {e}")))?;
-
-        realtime::emit_mix_progress(
-            &request_id,
-            &realtime::ProgressUpdate::new(
-                "prompting",
-                "Writing a shared prompt from the chosen artworks...",
-            ),
-        )
-        .await;
-
-        let prompt = MIX_IMAGE_PROMPT.replace("{{PROMPTS}}", &arts.to_formatted_prompts());
-
-        let prompt = text_gen
-            .generate(&prompt)
+    let latest: MixTitleId = match id.id {
+        Some(i) => load_item(&ctx, i).await?.into(),
+        _ => mixes::Model::find_latest_published(&ctx.db)
             .await
-            .map_err(|e| Error::Message(format!("Unable to gen prompt for mix: {e}")))?;
+            .map_err(|e| match e {
+                ModelError::EntityNotFound => Error::NotFound,
+                e => e.into(),
+            })?,
+    };
 
-        realtime::emit_mix_progress(
-            &request_id,
-            &realtime::ProgressUpdate::new(
-                "titling",
-                "Prompt ready. Creating a title for the new mix...",
-            ),
-        )
-        .await;
-
-        let titles = arts.to_formatted_titles();
-        let title_prompt = TITLE_PROMPT
-            .replace("{{TITLES}}", &titles)
-            .replace("{{DESCRIPTION}}", &prompt);
-
-        let title = text_gen
-            .generate(&title_prompt)
-            .await
-            .map_err(|_| Error::Message("Unable to create title for mix".into()))?;
-
-        println!("Generating mix: {title} - {prompt}");
+    let mut rest = mixes::Model::find_before_id(&ctx.db, latest.id).await?;
+    let mut items = vec![latest];
+    items.append(&mut rest);
+    let items = with_sources(&ctx, &items).await?;
+    views::mixes::show_infinite(&v, &items)
+}
 
-        realtime::emit_mix_progress(
-            &request_id,
-            &realtime::ProgressUpdate::new(
-                "rendering",
-                "Title locked in. Rendering the mixed image now...",
-            ),
-        )
-        .await;
+#[debug_handler]
+pub async fn cursor_before_json(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let results = mixes::Model::find_before_id(&ctx.db, id).await?;
+    let results = serde_json::json!({"results": with_sources(&ctx, &results).await?});
+    format::json(results)
+}
 
-        let image = img_gen
-            .generate(&prompt)
-            .await
-            .map_err(|e| Error::Message(format!("Unable to generate image: {e}")))?;
+#[debug_handler]
+pub async fn cursor_after_json(
+    Path(id): Path<i32>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let results = mixes::Model::find_after_id(&ctx.db, id).await?;
+    let results = serde_json::json!({"results": with_sources(&ctx, &results).await?});
+    format::json(results)
+}
 
-        realtime::emit_mix_progress(
-            &request_id,
-            &realtime::ProgressUpdate::new(
-                "saving",
-                "Image finished. Saving the mix and linking the source art...",
-            ),
-        )
-        .await;
+#[debug_handler]
+pub async fn show_form(
+    ViewEngine(v): ViewEngine<TeraView>,
+    State(ctx): State<AppContext>,
+) -> Result<Response> {
+    let title_ids = arts::Model::find_published_title_ids(&ctx.db).await?;
+    let settings = Settings::from_json(
+        &ctx.config
+            .settings
+            .clone()
+            .ok_or(Error::Message("Invalid settings".into()))?,
+    )?;
+    let bounds = settings.mix_sources.bounds();
+    views::mixes::show_form(&v, &title_ids, bounds)
+}
 
-        let mix = mixes::Model::create(
-            &ctx.db,
-            &MixParams {
-                image,
-                prompt,
-                title,
-                model: img_gen.model_name(),
-            },
-        )
-        .await?;
-
-        //NOTE: what happens if just this step fails?
-        //I'd get a dangling mix.
-        mixarts::Model::create(
-            &ctx.db,
-            &MixArtParams {
-                mix_id: mix.id,
-                art_ids: params.art_ids,
-            },
-        )
-        .await?;
+#[debug_handler]
+pub async fn create(
+    State(ctx): State<AppContext>,
+    ClientIp(ip): ClientIp,
+    Json(params): Json<MixReqParams>,
+) -> Result<Response> {
+    let request_id = Uuid::parse_str(&params.request_id)
+        .map_err(|_| Error::Message("Invalid mix request id".into()))?;
+    if let Some(options) = &params.options {
+        options.validate().map_err(Error::BadRequest)?;
+    }
 
-        let redirect_to = format!("/mix/{}", mix.id);
+    let selection = mix_service::check_sources(&ctx, &params.art_ids).await?;
+    if !selection.is_valid() {
+        let message = selection.errors.join(" ");
         realtime::emit_mix_progress(
             &request_id,
-            &realtime::ProgressUpdate::done("complete", "Your mix is ready. Opening it now...")
-                .with_redirect_to(&redirect_to),
+            &realtime::ProgressUpdate::failed("invalid", &message),
         )
         .await;
-
-        Ok((
-            StatusCode::SEE_OTHER,
-            [(header::LOCATION, redirect_to)],
-            "Redirecting to mix result...",
+        return Ok((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({
+                "message": message,
+                "errors": {"art_ids": selection.errors},
+                "request_id": request_id,
+            })),
         )
-            .into_response())
+            .into_response());
     }
+
+    let job = mix_limits::enqueue(
+        &ctx,
+        ip,
+        JobPayload {
+            art_ids: selection.art_ids,
+            request_id: Some(request_id),
+            options: params.options,
+            ..Default::default()
+        },
+    )
     .await;
 
-    if result.is_err() {
-        realtime::emit_mix_progress(
-            &request_id,
-            &realtime::ProgressUpdate::failed(
-                "failed",
-                "The mix could not be completed. Please try again.",
-            ),
-        )
-        .await;
-    }
+    let job = match job {
+        Ok(Ok(job)) => job,
+        Ok(Err(refusal)) => {
+            realtime::emit_mix_progress(
+                &request_id,
+                &realtime::ProgressUpdate::failed(refusal.stage(), refusal.message()),
+            )
+            .await;
+            return Ok((
+                refusal.status(),
+                Json(serde_json::json!({"message": refusal.message(), "request_id": request_id})),
+            )
+                .into_response());
+        }
+        Err(err) => {
+            realtime::emit_mix_progress(
+                &request_id,
+                &realtime::ProgressUpdate::failed(
+                    "failed",
+                    "The mix could not be completed. Please try again.",
+                ),
+            )
+            .await;
+            return Err(err);
+        }
+    };
 
-    result
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({"job_id": job.id, "request_id": request_id})),
+    )
+        .into_response())
 }
 
 #[debug_handler]
@@ -215,7 +280,12 @@ pub async fn serve_image(
 ) -> Result<Response> {
     //TODO: support png too.
     let (id, _format) = id.extract_id().ok_or_else(|| Error::NotFound)?;
-    let bytes = mixes::Model::find_img_slice_by_id(&ctx.db, id).await?;
+    let bytes = mixes::Model::find_img_slice_by_id(&ctx.db, id)
+        .await
+        .map_err(|e| match e {
+            ModelError::EntityNotFound => Error::NotFound,
+            e => e.into(),
+        })?;
 
     Ok((
         StatusCode::OK,
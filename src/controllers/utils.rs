@@ -1,3 +1,8 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use loco_rs::controller::middleware::remote_ip::RemoteIP;
 use regex::Regex;
 
 pub trait ExtractId {
@@ -24,3 +29,26 @@ pub enum ImageFormat {
     Png,
     WebP,
 }
+
+/// `ClientIp` is the visitor's IP: the one the `remote_ip` middleware worked
+/// out when it is enabled, the socket's otherwise, and `None` when neither is
+/// known.
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ip = match parts.extensions.get::<RemoteIP>() {
+            Some(RemoteIP::Forwarded(ip) | RemoteIP::Socket(ip)) => Some(*ip),
+            _ => parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip()),
+        };
+        Ok(Self(ip))
+    }
+}
@@ -1,7 +1,26 @@
 use thiserror::Error;
 
+use crate::services::ai::error::ProviderFailure;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Error while talking to Ai: {0}")]
     AIError(String),
+    #[error("Error while talking to Ai: {message}")]
+    ProviderError {
+        failure: ProviderFailure,
+        message: String,
+    },
+}
+
+impl Error {
+    /// `provider_failure` is the classification of a failed Provider call, if
+    /// this error came from one.
+    #[must_use]
+    pub const fn provider_failure(&self) -> Option<ProviderFailure> {
+        match self {
+            Self::ProviderError { failure, .. } => Some(*failure),
+            Self::AIError(_) => None,
+        }
+    }
 }
@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use loco_rs::{
+    Error, Result,
+    app::{AppContext, Initializer},
+};
+use tracing::info;
+
+use crate::{common::settings::Settings, services::generation_jobs};
+
+pub struct GenerationJobsInitializer;
+
+#[async_trait]
+impl Initializer for GenerationJobsInitializer {
+    fn name(&self) -> String {
+        "generation-jobs".to_string()
+    }
+
+    async fn before_run(&self, ctx: &AppContext) -> Result<()> {
+        let settings = Settings::from_json(
+            &ctx.config
+                .settings
+                .clone()
+                .ok_or(Error::Message("Invalid settings".into()))?,
+        )?;
+        if !settings.generation_jobs.resume_on_boot {
+            return Ok(());
+        }
+
+        let resumed = generation_jobs::resume_pending(ctx, &settings.generation_jobs).await?;
+        if resumed > 0 {
+            info!(resumed, "resumed pending generation jobs");
+        }
+        Ok(())
+    }
+}
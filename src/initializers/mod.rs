@@ -1,3 +1,5 @@
 #![allow(clippy::module_name_repetitions)]
+pub mod generation_jobs;
 pub mod realtime;
+pub mod scheduler;
 pub mod view_engine;
@@ -56,6 +56,21 @@ impl Initializer for RealtimeInitializer {
                     }
                 },
             );
+
+            socket.on(
+                "subscribe-art-create",
+                |socket: SocketRef, Data::<String>(request_id)| async move {
+                    if let Some(request_id) = realtime::parse_subscription_id(&request_id) {
+                        socket.join(realtime::art_create_room(&request_id));
+                        socket.emit("subscription-confirmed", &"art-create").ok();
+                    } else {
+                        warn!(subscription = %request_id, "invalid art creation subscription");
+                        socket
+                            .emit("subscription-error", &"Invalid art creation subscription.")
+                            .ok();
+                    }
+                },
+            );
         });
 
         realtime::install(io);
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use loco_rs::{
+    Error, Result,
+    app::{AppContext, Initializer},
+};
+
+use crate::{common::settings::Settings, services::scheduler};
+
+pub struct SchedulerInitializer;
+
+#[async_trait]
+impl Initializer for SchedulerInitializer {
+    fn name(&self) -> String {
+        "scheduler".to_string()
+    }
+
+    async fn before_run(&self, ctx: &AppContext) -> Result<()> {
+        let settings = Settings::from_json(
+            &ctx.config
+                .settings
+                .clone()
+                .ok_or(Error::Message("Invalid settings".into()))?,
+        )?;
+        scheduler::start(ctx, &settings.scheduler).await
+    }
+}
@@ -7,3 +7,4 @@ pub mod models;
 pub mod services;
 pub mod tasks;
 pub mod views;
+pub mod workers;
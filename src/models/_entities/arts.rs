@@ -3,7 +3,7 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "arts")]
 pub struct Model {
     pub created_at: DateTimeWithTimeZone,
@@ -16,14 +16,77 @@ pub struct Model {
     pub uuid: Uuid,
     #[sea_orm(column_type = "Text", nullable)]
     pub model: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub prompt_model: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub title_model: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub prompt_meta_prompt: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub title_meta_prompt: Option<String>,
+    pub prompt_template_id: Option<i32>,
+    pub title_template_id: Option<i32>,
+    pub aspect_ratio: Option<String>,
+    pub image_size: Option<String>,
+    pub seed: Option<i64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub negative_prompt: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub status: String,
+    pub rerender_flagged: bool,
+    pub image_hash: Option<i64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub diversity_score: Option<f64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub theme: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub style: Option<String>,
+    pub style_preset_id: Option<i32>,
+    pub parent_id: Option<i32>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub variation_instruction: Option<String>,
+    pub request_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::art_candidates::Entity")]
+    ArtCandidates,
+    #[sea_orm(has_many = "super::art_revisions::Entity")]
+    ArtRevisions,
+    #[sea_orm(has_many = "super::generation_jobs::Entity")]
+    GenerationJobs,
+    #[sea_orm(has_many = "super::generation_usage::Entity")]
+    GenerationUsage,
     #[sea_orm(has_many = "super::mixarts::Entity")]
     Mixarts,
 }
 
+impl Related<super::art_candidates::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ArtCandidates.def()
+    }
+}
+
+impl Related<super::art_revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ArtRevisions.def()
+    }
+}
+
+impl Related<super::generation_jobs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GenerationJobs.def()
+    }
+}
+
+impl Related<super::generation_usage::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GenerationUsage.def()
+    }
+}
+
 impl Related<super::mixarts::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Mixarts.def()
@@ -15,14 +15,44 @@ pub struct Model {
     pub title: String,
     pub uuid: Uuid,
     pub model: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub prompt_model: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub title_model: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub prompt_meta_prompt: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub title_meta_prompt: Option<String>,
+    pub prompt_template_id: Option<i32>,
+    pub title_template_id: Option<i32>,
+    pub status: String,
+    pub image_hash: Option<i64>,
+    #[sea_orm(unique)]
+    pub request_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::generation_jobs::Entity")]
+    GenerationJobs,
+    #[sea_orm(has_many = "super::generation_usage::Entity")]
+    GenerationUsage,
     #[sea_orm(has_many = "super::mixarts::Entity")]
     Mixarts,
 }
 
+impl Related<super::generation_jobs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GenerationJobs.def()
+    }
+}
+
+impl Related<super::generation_usage::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GenerationUsage.def()
+    }
+}
+
 impl Related<super::mixarts::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Mixarts.def()
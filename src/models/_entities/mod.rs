@@ -2,7 +2,15 @@
 
 pub mod prelude;
 
+pub mod art_candidates;
+pub mod art_revisions;
 pub mod arts;
+pub mod generation_jobs;
+pub mod generation_usage;
 pub mod mixarts;
 pub mod mixes;
+pub mod prompt_templates;
+pub mod scheduled_runs;
+pub mod staged_mixes;
+pub mod style_presets;
 pub mod users;
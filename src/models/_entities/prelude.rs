@@ -1,6 +1,14 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.12
 
+pub use super::art_candidates::Entity as ArtCandidates;
+pub use super::art_revisions::Entity as ArtRevisions;
 pub use super::arts::Entity as Arts;
+pub use super::generation_jobs::Entity as GenerationJobs;
+pub use super::generation_usage::Entity as GenerationUsage;
 pub use super::mixarts::Entity as Mixarts;
 pub use super::mixes::Entity as Mixes;
+pub use super::prompt_templates::Entity as PromptTemplates;
+pub use super::scheduled_runs::Entity as ScheduledRuns;
+pub use super::staged_mixes::Entity as StagedMixes;
+pub use super::style_presets::Entity as StylePresets;
 pub use super::users::Entity as Users;
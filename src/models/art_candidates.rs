@@ -0,0 +1,326 @@
+pub use super::_entities::art_candidates::{self, ActiveModel, Entity, Model};
+use base64::{Engine as _, engine::general_purpose};
+use loco_rs::model::ModelResult;
+use loco_rs::prelude::ModelError;
+use sea_orm::{
+    ActiveValue, FromQueryResult, QueryOrder, QuerySelect, TransactionTrait, entity::prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::_entities::arts;
+use super::{
+    art_revisions::{self, RevisionReason},
+    arts::{PublicationStatus, image_dimensions},
+};
+pub type ArtCandidates = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// `CandidateParams` is one rendered image offered for an art, with the
+/// `changes` to the art that go along with it when it is picked.
+#[derive(Debug, Default)]
+pub struct CandidateParams {
+    pub image: String,
+    pub model: Option<String>,
+    pub changes: Option<ArtChanges>,
+}
+
+/// `ArtChanges` is what a replace or a variation would change on an art
+/// besides its image. A field left out keeps the art's value; one given as
+/// `Some(None)` clears it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArtChanges {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub prompt_model: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub title_model: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub prompt_meta_prompt: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub title_meta_prompt: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub prompt_template_id: Option<Option<i32>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub title_template_id: Option<Option<i32>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub diversity_score: Option<Option<f64>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub theme: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub style: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub style_preset_id: Option<Option<i32>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub variation_instruction: Option<Option<String>>,
+}
+
+/// `present` tells a field given as `null` from one left out.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl ArtChanges {
+    /// Sets the given fields on `art`.
+    pub fn apply(self, art: &mut arts::ActiveModel) {
+        fn set<T: Into<sea_orm::Value>>(field: &mut ActiveValue<T>, value: Option<T>) {
+            if let Some(value) = value {
+                *field = ActiveValue::set(value);
+            }
+        }
+        set(&mut art.prompt, self.prompt);
+        set(&mut art.title, self.title);
+        set(&mut art.prompt_model, self.prompt_model);
+        set(&mut art.title_model, self.title_model);
+        set(&mut art.prompt_meta_prompt, self.prompt_meta_prompt);
+        set(&mut art.title_meta_prompt, self.title_meta_prompt);
+        set(&mut art.prompt_template_id, self.prompt_template_id);
+        set(&mut art.title_template_id, self.title_template_id);
+        set(&mut art.diversity_score, self.diversity_score);
+        set(&mut art.theme, self.theme);
+        set(&mut art.style, self.style);
+        set(&mut art.style_preset_id, self.style_preset_id);
+        set(&mut art.variation_instruction, self.variation_instruction);
+    }
+}
+
+impl super::_entities::art_candidates::Model {
+    /// Replaces the candidates of an art with `params`, so a new render never
+    /// shows next to the drafts of an earlier one. An empty `params` only
+    /// discards them.
+    ///
+    /// # Errors
+    ///
+    /// When the candidates could not be saved.
+    pub async fn replace_for_art(
+        db: &DatabaseConnection,
+        art_id: i32,
+        params: &[CandidateParams],
+    ) -> ModelResult<()> {
+        let txn = db.begin().await?;
+        art_candidates::Entity::delete_many()
+            .filter(art_candidates::Column::ArtId.eq(art_id))
+            .exec(&txn)
+            .await?;
+
+        if !params.is_empty() {
+            let rows = params.iter().map(|params| {
+                let dimensions = image_dimensions(&params.image);
+                art_candidates::ActiveModel {
+                    art_id: ActiveValue::set(art_id),
+                    image: ActiveValue::set(params.image.clone()),
+                    model: ActiveValue::set(params.model.clone()),
+                    width: ActiveValue::set(dimensions.map(|(width, _)| width)),
+                    height: ActiveValue::set(dimensions.map(|(_, height)| height)),
+                    changes: ActiveValue::set(
+                        params
+                            .changes
+                            .as_ref()
+                            .map(|changes| serde_json::json!(changes)),
+                    ),
+                    ..Default::default()
+                }
+            });
+            art_candidates::Entity::insert_many(rows).exec(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// finds the candidates of an art, without their images
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_by_art(
+        db: &DatabaseConnection,
+        art_id: i32,
+    ) -> ModelResult<Vec<CandidateSummary>> {
+        Ok(art_candidates::Entity::find()
+            .filter(art_candidates::Column::ArtId.eq(art_id))
+            .order_by_asc(art_candidates::Column::Id)
+            .select_only()
+            .columns([
+                art_candidates::Column::Id,
+                art_candidates::Column::ArtId,
+                art_candidates::Column::Model,
+                art_candidates::Column::Width,
+                art_candidates::Column::Height,
+                art_candidates::Column::CreatedAt,
+            ])
+            .into_partial_model::<CandidateSummary>()
+            .all(db)
+            .await?)
+    }
+
+    /// finds a candidate of an art and returns just its decoded image
+    ///
+    /// # Errors
+    ///
+    /// When db fails or when the candidate is missing
+    pub async fn find_img_slice(
+        db: &DatabaseConnection,
+        art_id: i32,
+        id: i32,
+    ) -> ModelResult<Vec<u8>> {
+        let CandidateImage { image } = art_candidates::Entity::find_by_id(id)
+            .filter(art_candidates::Column::ArtId.eq(art_id))
+            .select_only()
+            .column(art_candidates::Column::Image)
+            .into_partial_model::<CandidateImage>()
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+
+        general_purpose::STANDARD
+            .decode(image)
+            .map_err(|e| ModelError::Any(Box::new(e)))
+    }
+
+    /// Publishes a candidate: its image and Model become the art's, keeping
+    /// the options the art was rendered with, along with the prompt, title
+    /// and provenance it was rendered for. The state being replaced is kept
+    /// as a revision, a draft art is published, and every candidate of the
+    /// art is discarded.
+    ///
+    /// # Errors
+    ///
+    /// When the art or the candidate is missing, or on DB error.
+    pub async fn pick(db: &DatabaseConnection, art_id: i32, id: i32) -> ModelResult<arts::Model> {
+        let txn = db.begin().await?;
+        let candidate = art_candidates::Entity::find_by_id(id)
+            .filter(art_candidates::Column::ArtId.eq(art_id))
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+        let art = arts::Entity::find_by_id(art_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+        art_revisions::Model::record(&txn, &art, RevisionReason::Pick).await?;
+
+        let options = art.generation_options();
+        let status = art.status.clone();
+        let mut art_active_model =
+            arts::ActiveModel::from(art).with_image(&candidate.image, &options);
+        art_active_model.model = ActiveValue::set(candidate.model);
+        if let Some(changes) = candidate.changes {
+            let changes: ArtChanges =
+                serde_json::from_value(changes).map_err(|e| ModelError::Any(Box::new(e)))?;
+            changes.apply(&mut art_active_model);
+        }
+        if status == PublicationStatus::Draft.as_str() {
+            art_active_model.status = ActiveValue::set(PublicationStatus::Published.to_string());
+        }
+        art_active_model.updated_at = ActiveValue::set(chrono::Utc::now().into());
+        let art = art_active_model.update(&txn).await?;
+
+        art_candidates::Entity::delete_many()
+            .filter(art_candidates::Column::ArtId.eq(art_id))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(art)
+    }
+
+    /// Discards every candidate of an art, keeping its current image.
+    ///
+    /// # Errors
+    ///
+    /// On DB error
+    pub async fn discard_all<C: ConnectionTrait>(db: &C, art_id: i32) -> ModelResult<u64> {
+        Ok(art_candidates::Entity::delete_many()
+            .filter(art_candidates::Column::ArtId.eq(art_id))
+            .exec(db)
+            .await?
+            .rows_affected)
+    }
+}
+
+/// `CandidateSummary` is a candidate as listed in the backoffice; the image is
+/// served separately.
+#[derive(DerivePartialModel, FromQueryResult, Serialize, Deserialize, Debug)]
+#[sea_orm(entity = "Entity")]
+pub struct CandidateSummary {
+    pub id: i32,
+    pub art_id: i32,
+    pub model: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(DerivePartialModel, FromQueryResult)]
+#[sea_orm(entity = "Entity")]
+struct CandidateImage {
+    pub image: String,
+}
@@ -0,0 +1,410 @@
+use std::{fmt, str::FromStr};
+
+pub use super::_entities::art_revisions::{self, ActiveModel, Entity, Model};
+use base64::{Engine as _, engine::general_purpose};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{
+    ActiveValue, FromQueryResult, QueryOrder, QuerySelect, TransactionTrait,
+    entity::prelude::*,
+    sea_query::{Alias, Expr, Func, SimpleExpr},
+};
+use serde::{Deserialize, Serialize};
+
+use super::_entities::{art_candidates, arts};
+pub type ArtRevisions = Entity;
+
+/// `RevisionReason` is what was about to overwrite the art when its previous
+/// state was kept as a revision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RevisionReason {
+    Edit,
+    Replace,
+    Rerender,
+    Variation,
+    Restore,
+    Pick,
+}
+
+impl RevisionReason {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Edit => "edit",
+            Self::Replace => "replace",
+            Self::Rerender => "rerender",
+            Self::Variation => "variation",
+            Self::Restore => "restore",
+            Self::Pick => "pick",
+        }
+    }
+}
+
+impl fmt::Display for RevisionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RevisionReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "edit" => Ok(Self::Edit),
+            "replace" => Ok(Self::Replace),
+            "rerender" => Ok(Self::Rerender),
+            "variation" => Ok(Self::Variation),
+            "restore" => Ok(Self::Restore),
+            "pick" => Ok(Self::Pick),
+            _ => Err(format!("Invalid revision reason: {s}")),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl super::_entities::art_revisions::Model {
+    /// Keeps the current state of `art` as a revision, before `reason`
+    /// overwrites it. Call it in the transaction doing the overwrite.
+    ///
+    /// # Errors
+    ///
+    /// When the revision could not be saved.
+    pub async fn record<C: ConnectionTrait>(
+        db: &C,
+        art: &arts::Model,
+        reason: RevisionReason,
+    ) -> ModelResult<Self> {
+        Ok(art_revisions::ActiveModel {
+            art_id: ActiveValue::set(art.id),
+            reason: ActiveValue::set(reason.to_string()),
+            image: ActiveValue::set(art.image.clone()),
+            prompt: ActiveValue::set(art.prompt.clone()),
+            title: ActiveValue::set(art.title.clone()),
+            model: ActiveValue::set(art.model.clone()),
+            prompt_model: ActiveValue::set(art.prompt_model.clone()),
+            title_model: ActiveValue::set(art.title_model.clone()),
+            prompt_meta_prompt: ActiveValue::set(art.prompt_meta_prompt.clone()),
+            title_meta_prompt: ActiveValue::set(art.title_meta_prompt.clone()),
+            prompt_template_id: ActiveValue::set(art.prompt_template_id),
+            title_template_id: ActiveValue::set(art.title_template_id),
+            aspect_ratio: ActiveValue::set(art.aspect_ratio.clone()),
+            image_size: ActiveValue::set(art.image_size.clone()),
+            seed: ActiveValue::set(art.seed),
+            negative_prompt: ActiveValue::set(art.negative_prompt.clone()),
+            width: ActiveValue::set(art.width),
+            height: ActiveValue::set(art.height),
+            image_hash: ActiveValue::set(art.image_hash),
+            diversity_score: ActiveValue::set(art.diversity_score),
+            theme: ActiveValue::set(art.theme.clone()),
+            style: ActiveValue::set(art.style.clone()),
+            style_preset_id: ActiveValue::set(art.style_preset_id),
+            variation_instruction: ActiveValue::set(art.variation_instruction.clone()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// Puts a revision's state back on its art. The state being replaced is
+    /// kept as a revision first, so a restore can be undone, and the art's
+    /// candidates are discarded since they were drafts of the replaced render.
+    ///
+    /// # Errors
+    ///
+    /// When the art or the revision is missing, or on DB error.
+    pub async fn restore(
+        db: &DatabaseConnection,
+        art_id: i32,
+        id: i32,
+    ) -> ModelResult<arts::Model> {
+        let txn = db.begin().await?;
+        let revision = art_revisions::Entity::find_by_id(id)
+            .filter(art_revisions::Column::ArtId.eq(art_id))
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+        let art = arts::Entity::find_by_id(art_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+        Self::record(&txn, &art, RevisionReason::Restore).await?;
+
+        let mut art_active_model = arts::ActiveModel::from(art);
+        art_active_model.image = ActiveValue::set(revision.image);
+        art_active_model.prompt = ActiveValue::set(revision.prompt);
+        art_active_model.title = ActiveValue::set(revision.title);
+        art_active_model.model = ActiveValue::set(revision.model);
+        art_active_model.prompt_model = ActiveValue::set(revision.prompt_model);
+        art_active_model.title_model = ActiveValue::set(revision.title_model);
+        art_active_model.prompt_meta_prompt = ActiveValue::set(revision.prompt_meta_prompt);
+        art_active_model.title_meta_prompt = ActiveValue::set(revision.title_meta_prompt);
+        art_active_model.prompt_template_id = ActiveValue::set(revision.prompt_template_id);
+        art_active_model.title_template_id = ActiveValue::set(revision.title_template_id);
+        art_active_model.aspect_ratio = ActiveValue::set(revision.aspect_ratio);
+        art_active_model.image_size = ActiveValue::set(revision.image_size);
+        art_active_model.seed = ActiveValue::set(revision.seed);
+        art_active_model.negative_prompt = ActiveValue::set(revision.negative_prompt);
+        art_active_model.width = ActiveValue::set(revision.width);
+        art_active_model.height = ActiveValue::set(revision.height);
+        art_active_model.image_hash = ActiveValue::set(revision.image_hash);
+        art_active_model.diversity_score = ActiveValue::set(revision.diversity_score);
+        art_active_model.theme = ActiveValue::set(revision.theme);
+        art_active_model.style = ActiveValue::set(revision.style);
+        art_active_model.style_preset_id = ActiveValue::set(revision.style_preset_id);
+        art_active_model.variation_instruction = ActiveValue::set(revision.variation_instruction);
+        art_active_model.updated_at = ActiveValue::set(chrono::Utc::now().into());
+        let art = art_active_model.update(&txn).await?;
+
+        art_candidates::Entity::delete_many()
+            .filter(art_candidates::Column::ArtId.eq(art_id))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(art)
+    }
+
+    /// finds the revisions of `art`, newest first, each with the fields that
+    /// changed between it and the state that replaced it. Images are left
+    /// out; an image change is told by its hash and length.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_history(
+        db: &DatabaseConnection,
+        art: &arts::Model,
+    ) -> ModelResult<Vec<RevisionSummary>> {
+        let revisions = art_revisions::Entity::find()
+            .filter(art_revisions::Column::ArtId.eq(art.id))
+            .order_by_desc(art_revisions::Column::Id)
+            .select_only()
+            .columns([
+                art_revisions::Column::Id,
+                art_revisions::Column::ArtId,
+                art_revisions::Column::Reason,
+                art_revisions::Column::Prompt,
+                art_revisions::Column::Title,
+                art_revisions::Column::Model,
+                art_revisions::Column::PromptModel,
+                art_revisions::Column::TitleModel,
+                art_revisions::Column::Seed,
+                art_revisions::Column::NegativePrompt,
+                art_revisions::Column::Width,
+                art_revisions::Column::Height,
+                art_revisions::Column::ImageHash,
+                art_revisions::Column::Theme,
+                art_revisions::Column::Style,
+                art_revisions::Column::VariationInstruction,
+                art_revisions::Column::CreatedAt,
+            ])
+            .column_as(
+                SimpleExpr::from(Func::cast_as(
+                    Func::char_length(Expr::col(art_revisions::Column::Image)),
+                    Alias::new("bigint"),
+                )),
+                "image_length",
+            )
+            .into_model::<RevisionMeta>()
+            .all(db)
+            .await?;
+
+        let mut after = Snapshot::from(art);
+        let mut history = Vec::with_capacity(revisions.len());
+        for revision in &revisions {
+            let before = Snapshot::from(revision);
+            history.push(RevisionSummary {
+                id: revision.id,
+                art_id: revision.art_id,
+                reason: revision.reason.clone(),
+                title: revision.title.clone(),
+                width: revision.width,
+                height: revision.height,
+                created_at: revision.created_at,
+                changes: before.changes(&after),
+            });
+            after = before;
+        }
+
+        Ok(history)
+    }
+
+    /// finds a revision of an art and returns just its decoded image
+    ///
+    /// # Errors
+    ///
+    /// When db fails or when the revision is missing
+    pub async fn find_img_slice(
+        db: &DatabaseConnection,
+        art_id: i32,
+        id: i32,
+    ) -> ModelResult<Vec<u8>> {
+        let RevisionImage { image } = art_revisions::Entity::find_by_id(id)
+            .filter(art_revisions::Column::ArtId.eq(art_id))
+            .select_only()
+            .column(art_revisions::Column::Image)
+            .into_partial_model::<RevisionImage>()
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+
+        general_purpose::STANDARD
+            .decode(image)
+            .map_err(|e| ModelError::Any(Box::new(e)))
+    }
+}
+
+/// `RevisionSummary` is a revision as listed in the backoffice, with the
+/// fields the next state changed; the image is served separately.
+#[derive(Serialize, Debug)]
+pub struct RevisionSummary {
+    pub id: i32,
+    pub art_id: i32,
+    pub reason: String,
+    pub title: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+    pub changes: Vec<FieldChange>,
+}
+
+/// `FieldChange` is a field's value in a revision and in the state after it.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// `RevisionMeta` is a revision without its image, as compared in the
+/// history.
+#[derive(FromQueryResult)]
+struct RevisionMeta {
+    id: i32,
+    art_id: i32,
+    reason: String,
+    prompt: String,
+    title: String,
+    model: Option<String>,
+    prompt_model: Option<String>,
+    title_model: Option<String>,
+    seed: Option<i64>,
+    negative_prompt: Option<String>,
+    width: Option<i32>,
+    height: Option<i32>,
+    image_hash: Option<i64>,
+    image_length: Option<i64>,
+    theme: Option<String>,
+    style: Option<String>,
+    variation_instruction: Option<String>,
+    created_at: DateTimeWithTimeZone,
+}
+
+/// `Snapshot` is the part of an art's state a revision keeps that is worth
+/// comparing, from the art or the revision. The image is known by its hash
+/// and length only.
+struct Snapshot {
+    image: (Option<i64>, Option<i64>),
+    dimensions: Option<String>,
+    fields: [(&'static str, Option<String>); 10],
+}
+
+fn dimensions(width: Option<i32>, height: Option<i32>) -> Option<String> {
+    width
+        .zip(height)
+        .map(|(width, height)| format!("{width} × {height} px"))
+}
+
+impl From<&arts::Model> for Snapshot {
+    fn from(art: &arts::Model) -> Self {
+        Self {
+            image: (art.image_hash, i64::try_from(art.image.len()).ok()),
+            dimensions: dimensions(art.width, art.height),
+            fields: [
+                ("Title", Some(art.title.clone())),
+                ("Prompt", Some(art.prompt.clone())),
+                ("Image model", art.model.clone()),
+                ("Prompt model", art.prompt_model.clone()),
+                ("Title model", art.title_model.clone()),
+                ("Theme", art.theme.clone()),
+                ("Style", art.style.clone()),
+                ("Variation instruction", art.variation_instruction.clone()),
+                ("Seed", art.seed.map(|seed| seed.to_string())),
+                ("Negative prompt", art.negative_prompt.clone()),
+            ],
+        }
+    }
+}
+
+impl From<&RevisionMeta> for Snapshot {
+    fn from(revision: &RevisionMeta) -> Self {
+        Self {
+            image: (revision.image_hash, revision.image_length),
+            dimensions: dimensions(revision.width, revision.height),
+            fields: [
+                ("Title", Some(revision.title.clone())),
+                ("Prompt", Some(revision.prompt.clone())),
+                ("Image model", revision.model.clone()),
+                ("Prompt model", revision.prompt_model.clone()),
+                ("Title model", revision.title_model.clone()),
+                ("Theme", revision.theme.clone()),
+                ("Style", revision.style.clone()),
+                (
+                    "Variation instruction",
+                    revision.variation_instruction.clone(),
+                ),
+                ("Seed", revision.seed.map(|seed| seed.to_string())),
+                ("Negative prompt", revision.negative_prompt.clone()),
+            ],
+        }
+    }
+}
+
+impl Snapshot {
+    fn changes(&self, after: &Self) -> Vec<FieldChange> {
+        let image = (self.image != after.image).then(|| FieldChange {
+            field: "Image",
+            before: self.dimensions.clone(),
+            after: after.dimensions.clone(),
+        });
+
+        image
+            .into_iter()
+            .chain(
+                self.fields
+                    .iter()
+                    .zip(&after.fields)
+                    .filter(|((_, before), (_, after))| before != after)
+                    .map(|((field, before), (_, after))| FieldChange {
+                        field,
+                        before: before.clone(),
+                        after: after.clone(),
+                    }),
+            )
+            .collect()
+    }
+}
+
+#[derive(DerivePartialModel, FromQueryResult)]
+#[sea_orm(entity = "Entity")]
+struct RevisionImage {
+    pub image: String,
+}
@@ -1,4 +1,7 @@
+use std::{fmt, io::Cursor, str::FromStr};
+
 use base64::{Engine as _, engine::general_purpose};
+use image::ImageReader;
 use loco_rs::Error;
 use loco_rs::model::ModelResult;
 use loco_rs::prelude::ActiveModelTrait;
@@ -15,12 +18,71 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use super::_entities::mixes;
+use super::art_revisions::{self, RevisionReason};
+use crate::common::{generation_options::GenerationOptions, image_hash};
 
 pub use super::_entities::arts::{self, ActiveModel, Entity, Model};
 
 pub const PAGE_SIZE: u64 = 5;
 pub const BACKOFFICE_PAGE_SIZE: u64 = 24;
 
+/// `PublicationStatus` is whether an art or mix is shown publicly. Only
+/// published work reaches the gallery, the feeds, the sitemap and the mix
+/// picker; drafts wait in the backoffice approval queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublicationStatus {
+    Draft,
+    #[default]
+    Published,
+    Rejected,
+}
+
+impl PublicationStatus {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Published => "published",
+            Self::Rejected => "rejected",
+        }
+    }
+
+    /// The status new work is saved with: a draft when it has to be approved.
+    #[must_use]
+    pub const fn for_new_work(require_approval: bool) -> Self {
+        if require_approval {
+            Self::Draft
+        } else {
+            Self::Published
+        }
+    }
+}
+
+impl fmt::Display for PublicationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PublicationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(Self::Draft),
+            "published" => Ok(Self::Published),
+            "rejected" => Ok(Self::Rejected),
+            _ => Err(format!("Invalid publication status: {s}")),
+        }
+    }
+}
+
+/// The condition for arts shown publicly.
+fn published() -> Condition {
+    Condition::all().add(arts::Column::Status.eq(PublicationStatus::Published.as_str()))
+}
+
 #[must_use]
 pub fn image_version(updated_at: &DateTimeWithTimeZone) -> String {
     updated_at.format("%s-%f").to_string()
@@ -31,6 +93,19 @@ pub fn image_url(id: i32, updated_at: &DateTimeWithTimeZone) -> String {
     format!("/img/{id}.webp?v={}", image_version(updated_at))
 }
 
+/// `image_dimensions` reads the width and height of a Base64 encoded image
+/// from its header, `None` when it can't be decoded.
+#[must_use]
+pub fn image_dimensions(image: &str) -> Option<(i32, i32)> {
+    let bytes = general_purpose::STANDARD.decode(image).ok()?;
+    let (width, height) = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    Some((i32::try_from(width).ok()?, i32::try_from(height).ok()?))
+}
+
 #[async_trait::async_trait]
 impl ActiveModelBehavior for super::_entities::arts::ActiveModel {
     async fn before_save<C>(self, _db: &C, insert: bool) -> Result<Self, DbErr>
@@ -47,6 +122,24 @@ impl ActiveModelBehavior for super::_entities::arts::ActiveModel {
     }
 }
 
+impl ActiveModel {
+    /// `with_image` sets the image along with the options it was rendered
+    /// with, its measured dimensions and its perceptual hash.
+    #[must_use]
+    pub fn with_image(mut self, image: &str, options: &GenerationOptions) -> Self {
+        let dimensions = image_dimensions(image);
+        self.image = ActiveValue::set(image.to_string());
+        self.aspect_ratio = ActiveValue::set(options.aspect_ratio.clone());
+        self.image_size = ActiveValue::set(options.size.clone());
+        self.seed = ActiveValue::set(options.seed);
+        self.negative_prompt = ActiveValue::set(options.negative_prompt.clone());
+        self.width = ActiveValue::set(dimensions.map(|(width, _)| width));
+        self.height = ActiveValue::set(dimensions.map(|(_, height)| height));
+        self.image_hash = ActiveValue::set(image_hash::dhash_base64(image));
+        self
+    }
+}
+
 pub trait ModelVec {
     fn to_formatted_prompts(&self) -> String;
     fn to_formatted_titles(&self) -> String;
@@ -81,12 +174,26 @@ impl super::_entities::arts::Model {
         let txn = db.begin().await?;
 
         let art = arts::ActiveModel {
-            image: ActiveValue::set(params.image.to_string()),
             prompt: ActiveValue::set(params.prompt.to_string()),
             title: ActiveValue::set(params.title.to_string()),
             model: ActiveValue::set(params.model.clone()),
+            prompt_model: ActiveValue::set(params.provenance.prompt_model.clone()),
+            title_model: ActiveValue::set(params.provenance.title_model.clone()),
+            prompt_meta_prompt: ActiveValue::set(params.provenance.prompt_meta_prompt.clone()),
+            title_meta_prompt: ActiveValue::set(params.provenance.title_meta_prompt.clone()),
+            prompt_template_id: ActiveValue::set(params.provenance.prompt_template_id),
+            title_template_id: ActiveValue::set(params.provenance.title_template_id),
+            status: ActiveValue::set(params.status.to_string()),
+            diversity_score: ActiveValue::set(params.diversity_score),
+            theme: ActiveValue::set(params.theme.clone()),
+            style: ActiveValue::set(params.style.clone()),
+            style_preset_id: ActiveValue::set(params.style_preset_id),
+            parent_id: ActiveValue::set(params.parent_id),
+            variation_instruction: ActiveValue::set(params.variation_instruction.clone()),
+            request_id: ActiveValue::set(params.request_id),
             ..Default::default()
         }
+        .with_image(&params.image, &params.options)
         .insert(&txn)
         .await?;
 
@@ -95,14 +202,30 @@ impl super::_entities::arts::Model {
         Ok(art)
     }
 
+    /// finds the art created or last replaced by a generation request
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_by_request_id(
+        db: &DatabaseConnection,
+        request_id: Uuid,
+    ) -> ModelResult<Option<Self>> {
+        Ok(arts::Entity::find()
+            .filter(arts::Column::RequestId.eq(request_id))
+            .one(db)
+            .await?)
+    }
+
     ///
-    /// fetches the most recently created `arts::Model`
+    /// fetches the most recently created published `arts::Model`
     ///
     /// # Errors
     ///
     /// When could not find art or DB query error
     pub async fn find_latest(db: &DatabaseConnection) -> ModelResult<Self> {
         let arts = arts::Entity::find()
+            .filter(published())
             .order_by_desc(arts::Column::CreatedAt)
             .limit(1)
             .one(db)
@@ -118,6 +241,7 @@ impl super::_entities::arts::Model {
     /// When could not find latest art or DB query error
     pub async fn find_latest_id(db: &DatabaseConnection) -> ModelResult<i32> {
         let ArtId { id } = arts::Entity::find()
+            .filter(published())
             .order_by_desc(arts::Column::CreatedAt)
             .limit(1)
             .select_only()
@@ -144,13 +268,45 @@ impl super::_entities::arts::Model {
         Ok(arts)
     }
 
-    /// finds the ids of all of the created arts
+    /// finds the published arts with the specified ids
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_published_in(
+        db: &DatabaseConnection,
+        ids: Vec<i32>,
+    ) -> ModelResult<Vec<Model>> {
+        let arts = arts::Entity::find()
+            .filter(published())
+            .filter(arts::Column::Id.is_in(ids))
+            .all(db)
+            .await?;
+
+        Ok(arts)
+    }
+
+    /// finds a published art by id
+    ///
+    /// # Errors
+    ///
+    /// When the art is missing or not published, or on DB query error
+    pub async fn find_published(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        arts::Entity::find_by_id(id)
+            .filter(published())
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// finds the ids of all of the published arts
     ///
     /// # Errors
     ///
     /// When could not find arts or DB query error
     pub async fn find_ids(db: &DatabaseConnection) -> ModelResult<Vec<i32>> {
         let ids = arts::Entity::find()
+            .filter(published())
             .order_by_asc(arts::Column::CreatedAt)
             .select_only()
             .column(arts::Column::Id)
@@ -193,13 +349,59 @@ impl super::_entities::arts::Model {
         Ok(arts)
     }
 
-    /// finds an art an returns just its base64 encoded image
+    /// finds the prompts of the latest n arts (most recent first), leaving
+    /// out the art `except`
     /// # Errors
     ///
-    /// When db fails or when the item is missing
+    /// When DB query error
+    pub async fn find_n_latest_prompts(
+        db: &DatabaseConnection,
+        n: u64,
+        except: Option<i32>,
+    ) -> ModelResult<Vec<String>> {
+        let mut query = arts::Entity::find();
+        if let Some(id) = except {
+            query = query.filter(arts::Column::Id.ne(id));
+        }
+        Ok(query
+            .order_by_desc(arts::Column::CreatedAt)
+            .order_by_desc(arts::Column::Id)
+            .limit(n)
+            .select_only()
+            .column(arts::Column::Prompt)
+            .into_tuple::<String>()
+            .all(db)
+            .await?)
+    }
+
+    /// finds a published art and returns just its decoded image
+    /// # Errors
+    ///
+    /// When db fails or when the item is missing or not published
     pub async fn find_img_slice_by_id(db: &DatabaseConnection, id: u32) -> ModelResult<Vec<u8>> {
+        Self::find_img_slice(db, id, published()).await
+    }
+
+    /// finds an art whatever its status and returns just its decoded
+    /// image, for the backoffice and maintenance tasks
+    /// # Errors
+    ///
+    /// When db fails or when the item is missing
+    pub async fn find_any_img_slice_by_id(
+        db: &DatabaseConnection,
+        id: u32,
+    ) -> ModelResult<Vec<u8>> {
+        Self::find_img_slice(db, id, Condition::all()).await
+    }
+
+    async fn find_img_slice(
+        db: &DatabaseConnection,
+        id: u32,
+        condition: Condition,
+    ) -> ModelResult<Vec<u8>> {
         let image = match arts::Entity::find()
             .filter(model::query::condition().eq(arts::Column::Id, id).build())
+            .filter(condition)
             .limit(1)
             .select_only()
             .column(arts::Column::Image)
@@ -235,6 +437,69 @@ impl super::_entities::arts::Model {
                 arts::Column::Id,
                 arts::Column::Title,
                 arts::Column::UpdatedAt,
+                arts::Column::Width,
+                arts::Column::Height,
+            ])
+            .order_by_desc(arts::Column::CreatedAt)
+            .into_partial_model::<ArtTitleId>()
+            .all(db)
+            .await?;
+
+        Ok(title_ids)
+    }
+
+    /// finds the art `item` is a variation of and the variations made of it,
+    /// oldest first
+    ///
+    /// # Errors
+    ///
+    /// When DB query error
+    pub async fn find_lineage(db: &DatabaseConnection, item: &Self) -> ModelResult<Lineage> {
+        let title_ids = || {
+            arts::Entity::find().select_only().columns([
+                arts::Column::Id,
+                arts::Column::Title,
+                arts::Column::UpdatedAt,
+                arts::Column::Width,
+                arts::Column::Height,
+            ])
+        };
+
+        let parent = match item.parent_id {
+            Some(parent_id) => {
+                title_ids()
+                    .filter(arts::Column::Id.eq(parent_id))
+                    .into_partial_model::<ArtTitleId>()
+                    .one(db)
+                    .await?
+            }
+            None => None,
+        };
+        let variations = title_ids()
+            .filter(arts::Column::ParentId.eq(item.id))
+            .order_by_asc(arts::Column::Id)
+            .into_partial_model::<ArtTitleId>()
+            .all(db)
+            .await?;
+
+        Ok(Lineage { parent, variations })
+    }
+
+    /// finds the ids and titles of the published arts, for the mix picker
+    ///
+    /// # Errors
+    ///
+    /// When could not find arts or DB query error
+    pub async fn find_published_title_ids(db: &DatabaseConnection) -> ModelResult<Vec<ArtTitleId>> {
+        let title_ids = arts::Entity::find()
+            .filter(published())
+            .select_only()
+            .columns([
+                arts::Column::Id,
+                arts::Column::Title,
+                arts::Column::UpdatedAt,
+                arts::Column::Width,
+                arts::Column::Height,
             ])
             .order_by_desc(arts::Column::CreatedAt)
             .into_partial_model::<ArtTitleId>()
@@ -244,7 +509,7 @@ impl super::_entities::arts::Model {
         Ok(title_ids)
     }
 
-    /// fetches `arts::Model`s before the given id.
+    /// fetches published `arts::Model`s before the given id.
     /// the returned data is paginated.
     ///
     /// # Errors
@@ -255,11 +520,14 @@ impl super::_entities::arts::Model {
         id: i32,
     ) -> Result<Vec<ArtTitleId>, Error> {
         let arts = arts::Entity::find()
+            .filter(published())
             .select_only()
             .columns([
                 arts::Column::Id,
                 arts::Column::Title,
                 arts::Column::UpdatedAt,
+                arts::Column::Width,
+                arts::Column::Height,
             ])
             .cursor_by(arts::Column::Id)
             .into_partial_model::<ArtTitleId>()
@@ -271,7 +539,7 @@ impl super::_entities::arts::Model {
         Ok(arts.into_iter().rev().collect())
     }
 
-    /// fetches `arts::Model`s before the given id.
+    /// fetches published `arts::Model`s after the given id.
     /// the returned data is paginated.
     ///
     /// # Errors
@@ -279,11 +547,14 @@ impl super::_entities::arts::Model {
     /// When could not find arts or DB query error
     pub async fn find_after_id(db: &DatabaseConnection, id: i32) -> Result<Vec<ArtTitleId>, Error> {
         let arts = arts::Entity::find()
+            .filter(published())
             .select_only()
             .columns([
                 arts::Column::Id,
                 arts::Column::Title,
                 arts::Column::UpdatedAt,
+                arts::Column::Width,
+                arts::Column::Height,
             ])
             .cursor_by(arts::Column::Id)
             .into_partial_model::<ArtTitleId>()
@@ -399,6 +670,35 @@ impl super::_entities::arts::Model {
         })
     }
 
+    /// The options the current image was rendered with.
+    #[must_use]
+    pub fn generation_options(&self) -> GenerationOptions {
+        GenerationOptions {
+            aspect_ratio: self.aspect_ratio.clone(),
+            size: self.image_size.clone(),
+            seed: self.seed,
+            negative_prompt: self.negative_prompt.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn provenance(&self) -> Provenance {
+        Provenance {
+            prompt_model: self.prompt_model.clone(),
+            title_model: self.title_model.clone(),
+            prompt_meta_prompt: self.prompt_meta_prompt.clone(),
+            title_meta_prompt: self.title_meta_prompt.clone(),
+            prompt_template_id: self.prompt_template_id,
+            title_template_id: self.title_template_id,
+        }
+    }
+
+    /// Saves a backoffice edit of the title, prompt and model, keeping the
+    /// previous state as a revision when anything changed.
+    ///
+    /// # Errors
+    ///
+    /// When the art is missing or on DB error.
     pub async fn update_details(
         db: &DatabaseConnection,
         id: i32,
@@ -409,13 +709,21 @@ impl super::_entities::arts::Model {
             .await?
             .ok_or_else(|| ModelError::EntityNotFound)?;
 
+        if art.title == params.title && art.prompt == params.prompt && art.model == params.model {
+            return Ok(art);
+        }
+
+        let txn = db.begin().await?;
+        art_revisions::Model::record(&txn, &art, RevisionReason::Edit).await?;
         let mut art_active_model: ActiveModel = art.into();
         art_active_model.title = ActiveValue::set(params.title.clone());
         art_active_model.prompt = ActiveValue::set(params.prompt.clone());
         art_active_model.model = ActiveValue::set(params.model.clone());
         art_active_model.updated_at = ActiveValue::set(chrono::Utc::now().into());
+        let art = art_active_model.update(&txn).await?;
+        txn.commit().await?;
 
-        art_active_model.update(db).await.map_err(Into::into)
+        Ok(art)
     }
 
     pub async fn delete_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<()> {
@@ -428,6 +736,179 @@ impl super::_entities::arts::Model {
         Ok(())
     }
 
+    /// finds the id, title and perceptual hash of every hashed art
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_image_hashes(db: &DatabaseConnection) -> ModelResult<Vec<ArtImageHash>> {
+        Ok(arts::Entity::find()
+            .filter(arts::Column::ImageHash.is_not_null())
+            .order_by_asc(arts::Column::Id)
+            .select_only()
+            .columns([
+                arts::Column::Id,
+                arts::Column::Title,
+                arts::Column::Status,
+                arts::Column::UpdatedAt,
+                arts::Column::ImageHash,
+            ])
+            .into_partial_model::<ArtImageHash>()
+            .all(db)
+            .await?)
+    }
+
+    /// finds the ids of the arts without a perceptual hash, or of every art
+    /// when `all`
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_ids_to_hash(db: &DatabaseConnection, all: bool) -> ModelResult<Vec<i32>> {
+        let mut query = arts::Entity::find();
+        if !all {
+            query = query.filter(arts::Column::ImageHash.is_null());
+        }
+        Ok(query
+            .order_by_asc(arts::Column::Id)
+            .select_only()
+            .column(arts::Column::Id)
+            .into_partial_model::<ArtId>()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|art| art.id)
+            .collect())
+    }
+
+    /// Stores the perceptual hash of an art's image. The art's `updated_at`
+    /// is left alone, as its image did not change.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn set_image_hash(
+        db: &DatabaseConnection,
+        id: i32,
+        hash: Option<i64>,
+    ) -> ModelResult<()> {
+        arts::Entity::update_many()
+            .col_expr(arts::Column::ImageHash, Expr::value(hash))
+            .filter(arts::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// The id of the published art before `id`, for the public navigation.
+    pub async fn find_previous_published_id(
+        db: &DatabaseConnection,
+        id: i32,
+    ) -> ModelResult<Option<i32>> {
+        Ok(arts::Entity::find()
+            .filter(published())
+            .filter(arts::Column::Id.lt(id))
+            .order_by_desc(arts::Column::Id)
+            .select_only()
+            .column(arts::Column::Id)
+            .into_partial_model::<ArtId>()
+            .one(db)
+            .await?
+            .map(|art| art.id))
+    }
+
+    /// The id of the published art after `id`, for the public navigation.
+    pub async fn find_next_published_id(
+        db: &DatabaseConnection,
+        id: i32,
+    ) -> ModelResult<Option<i32>> {
+        Ok(arts::Entity::find()
+            .filter(published())
+            .filter(arts::Column::Id.gt(id))
+            .order_by_asc(arts::Column::Id)
+            .select_only()
+            .column(arts::Column::Id)
+            .into_partial_model::<ArtId>()
+            .one(db)
+            .await?
+            .map(|art| art.id))
+    }
+
+    /// Moves an art in or out of the public gallery.
+    ///
+    /// # Errors
+    ///
+    /// When the art is missing or could not be saved.
+    pub async fn set_status(
+        db: &DatabaseConnection,
+        id: i32,
+        status: PublicationStatus,
+    ) -> ModelResult<Self> {
+        let art = arts::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+
+        let mut art_active_model: ActiveModel = art.into();
+        art_active_model.status = ActiveValue::set(status.to_string());
+        art_active_model.update(db).await.map_err(Into::into)
+    }
+
+    /// Flags an art for the nightly rerender, or clears the flag.
+    ///
+    /// # Errors
+    ///
+    /// When the art is missing or could not be saved.
+    pub async fn set_rerender_flag(
+        db: &DatabaseConnection,
+        id: i32,
+        flagged: bool,
+    ) -> ModelResult<Self> {
+        let art = arts::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+
+        let mut art_active_model: ActiveModel = art.into();
+        art_active_model.rerender_flagged = ActiveValue::set(flagged);
+        art_active_model.update(db).await.map_err(Into::into)
+    }
+
+    /// finds the ids of the arts flagged for a rerender, oldest first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_rerender_flagged_ids(db: &DatabaseConnection) -> ModelResult<Vec<i32>> {
+        Ok(arts::Entity::find()
+            .filter(arts::Column::RerenderFlagged.eq(true))
+            .order_by_asc(arts::Column::Id)
+            .select_only()
+            .column(arts::Column::Id)
+            .into_partial_model::<ArtId>()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|art| art.id)
+            .collect())
+    }
+
+    /// finds the arts with `status`, most recent first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_by_status(
+        db: &DatabaseConnection,
+        status: PublicationStatus,
+    ) -> ModelResult<Vec<Self>> {
+        Ok(arts::Entity::find()
+            .filter(arts::Column::Status.eq(status.as_str()))
+            .order_by_desc(arts::Column::CreatedAt)
+            .all(db)
+            .await?)
+    }
+
     pub async fn find_previous_id(db: &DatabaseConnection, id: i32) -> ModelResult<Option<i32>> {
         Ok(arts::Entity::find()
             .filter(arts::Column::Id.lt(id))
@@ -453,11 +934,42 @@ impl super::_entities::arts::Model {
     }
 }
 
+#[derive(Default)]
 pub struct ArtParams {
     pub image: String,
     pub prompt: String,
     pub title: String,
     pub model: Option<String>,
+    pub provenance: Provenance,
+    pub options: GenerationOptions,
+    pub status: PublicationStatus,
+    /// 1 minus the prompt's similarity to the closest recent prompt.
+    pub diversity_score: Option<f64>,
+    /// The theme the art was asked to be about.
+    pub theme: Option<String>,
+    /// The style it was asked for, a preset name or a style written out, and
+    /// the preset that named it.
+    pub style: Option<String>,
+    pub style_preset_id: Option<i32>,
+    /// The art this one is a variation of, and the instruction it followed.
+    pub parent_id: Option<i32>,
+    pub variation_instruction: Option<String>,
+    /// The request that created or last replaced the art, so a retry finds it
+    /// instead of generating it again.
+    pub request_id: Option<Uuid>,
+}
+
+/// `Provenance` records which text Models wrote the prompt and the title, the
+/// exact meta-prompts they got, and the prompt template versions those were
+/// rendered from (`None` for the built-in templates).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub prompt_model: Option<String>,
+    pub title_model: Option<String>,
+    pub prompt_meta_prompt: Option<String>,
+    pub title_meta_prompt: Option<String>,
+    pub prompt_template_id: Option<i32>,
+    pub title_template_id: Option<i32>,
 }
 
 pub struct ArtUpdateParams {
@@ -522,12 +1034,32 @@ struct ArtUpdatedAt {
     pub updated_at: DateTimeWithTimeZone,
 }
 
+#[derive(DerivePartialModel, FromQueryResult, Serialize, Deserialize, Debug)]
+#[sea_orm(entity = "Entity")]
+pub struct ArtImageHash {
+    pub id: i32,
+    pub title: String,
+    pub status: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub image_hash: Option<i64>,
+}
+
+/// `Lineage` is the art an art is a variation of, when it is one, and the
+/// variations made of it.
+#[derive(Serialize, Debug, Default)]
+pub struct Lineage {
+    pub parent: Option<ArtTitleId>,
+    pub variations: Vec<ArtTitleId>,
+}
+
 #[derive(DerivePartialModel, FromQueryResult, Serialize, Deserialize, Debug)]
 #[sea_orm(entity = "Entity")]
 pub struct ArtTitleId {
     pub id: i32,
     pub title: String,
     pub updated_at: DateTimeWithTimeZone,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
 }
 
 impl From<arts::Model> for ArtTitleId {
@@ -536,6 +1068,8 @@ impl From<arts::Model> for ArtTitleId {
             id: value.id,
             title: value.title,
             updated_at: value.updated_at,
+            width: value.width,
+            height: value.height,
         }
     }
 }
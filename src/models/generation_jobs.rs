@@ -0,0 +1,399 @@
+use std::{fmt, str::FromStr};
+
+pub use super::_entities::generation_jobs::{self, ActiveModel, Entity, Model};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{
+    ActiveValue, Condition, DbBackend, QueryOrder, QuerySelect, entity::prelude::*, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::common::{directives::Directives, generation_options::GenerationOptions};
+pub type GenerationJobs = Entity;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Create,
+    Replace,
+    Rerender,
+    Variation,
+    Mix,
+}
+
+impl JobKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Replace => "replace",
+            Self::Rerender => "rerender",
+            Self::Variation => "variation",
+            Self::Mix => "mix",
+        }
+    }
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(Self::Create),
+            "replace" => Ok(Self::Replace),
+            "rerender" => Ok(Self::Rerender),
+            "variation" => Ok(Self::Variation),
+            "mix" => Ok(Self::Mix),
+            _ => Err(format!("Invalid generation job kind: {s}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `JobPayload` carries the flow-specific inputs a job needs beyond its art id.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobPayload {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub art_ids: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<Uuid>,
+    /// Render options asked for by the caller, over the configured defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<GenerationOptions>,
+    /// Theme and style asked for by the caller.
+    #[serde(default, skip_serializing_if = "Directives::is_empty")]
+    pub directives: Directives,
+    /// Image Model to render with instead of a random pool member.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Prompt to render instead of a generated one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// What a variation changes about the art.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+    /// Whether a variation is stored as a new art rather than over its parent.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub as_new: bool,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let mut this = self;
+        if insert {
+            this.uuid = ActiveValue::Set(Uuid::new_v4());
+        } else if this.updated_at.is_unchanged() {
+            this.updated_at = ActiveValue::Set(chrono::Utc::now().into());
+        }
+        Ok(this)
+    }
+}
+
+impl super::_entities::generation_jobs::Model {
+    /// Persists a new queued job, due immediately.
+    ///
+    /// # Errors
+    ///
+    /// When the payload cannot be serialized or the job could not be saved.
+    pub async fn create(
+        db: &DatabaseConnection,
+        params: &GenerationJobParams,
+    ) -> ModelResult<Self> {
+        let payload =
+            serde_json::to_value(&params.payload).map_err(|e| ModelError::Any(Box::new(e)))?;
+
+        generation_jobs::ActiveModel {
+            kind: ActiveValue::set(params.kind.to_string()),
+            status: ActiveValue::set(JobStatus::Queued.to_string()),
+            art_id: ActiveValue::set(params.art_id),
+            payload: ActiveValue::set(Some(payload)),
+            attempts: ActiveValue::set(0),
+            max_attempts: ActiveValue::set(params.max_attempts.max(1)),
+            run_at: ActiveValue::set(chrono::Utc::now().into()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Atomically moves a queued job to `running` and counts the attempt.
+    /// Returns `None` when the job is not queued anymore, so a job that was
+    /// scheduled twice only runs once.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn claim(db: &DatabaseConnection, id: i32) -> ModelResult<Option<Self>> {
+        let now: DateTimeWithTimeZone = chrono::Utc::now().into();
+        let claimed = generation_jobs::Entity::update_many()
+            .col_expr(
+                generation_jobs::Column::Status,
+                Expr::value(JobStatus::Running.as_str()),
+            )
+            .col_expr(
+                generation_jobs::Column::Attempts,
+                Expr::col(generation_jobs::Column::Attempts).add(1),
+            )
+            .col_expr(generation_jobs::Column::StartedAt, Expr::value(now))
+            .col_expr(generation_jobs::Column::UpdatedAt, Expr::value(now))
+            .filter(generation_jobs::Column::Id.eq(id))
+            .filter(generation_jobs::Column::Status.eq(JobStatus::Queued.as_str()))
+            .exec(db)
+            .await?;
+
+        if claimed.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        Ok(generation_jobs::Entity::find_by_id(id).one(db).await?)
+    }
+
+    /// Marks a job as completed, recording the art or mix it produced.
+    ///
+    /// # Errors
+    ///
+    /// When the job is missing or could not be saved.
+    pub async fn mark_completed(
+        db: &DatabaseConnection,
+        id: i32,
+        art_id: Option<i32>,
+        mix_id: Option<i32>,
+    ) -> ModelResult<Self> {
+        let mut job: ActiveModel = Self::find_required(db, id).await?.into();
+        job.status = ActiveValue::set(JobStatus::Completed.to_string());
+        if art_id.is_some() {
+            job.art_id = ActiveValue::set(art_id);
+        }
+        if mix_id.is_some() {
+            job.mix_id = ActiveValue::set(mix_id);
+        }
+        job.last_error = ActiveValue::set(None);
+        job.finished_at = ActiveValue::set(Some(chrono::Utc::now().into()));
+        job.update(db).await.map_err(Into::into)
+    }
+
+    /// Records a failed attempt. With `retry_at` the job goes back to the
+    /// queue for that time, otherwise it is marked as permanently failed.
+    ///
+    /// # Errors
+    ///
+    /// When the job is missing or could not be saved.
+    pub async fn mark_failed(
+        db: &DatabaseConnection,
+        id: i32,
+        error: &str,
+        retry_at: Option<DateTimeWithTimeZone>,
+    ) -> ModelResult<Self> {
+        let mut job: ActiveModel = Self::find_required(db, id).await?.into();
+        job.last_error = ActiveValue::set(Some(error.to_string()));
+        match retry_at {
+            Some(run_at) => {
+                job.status = ActiveValue::set(JobStatus::Queued.to_string());
+                job.run_at = ActiveValue::set(run_at);
+            }
+            None => {
+                job.status = ActiveValue::set(JobStatus::Failed.to_string());
+                job.finished_at = ActiveValue::set(Some(chrono::Utc::now().into()));
+            }
+        }
+        job.update(db).await.map_err(Into::into)
+    }
+
+    /// Puts jobs still `running` since before `stale_before` back in the
+    /// queue, returning how many were requeued.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn requeue_interrupted(
+        db: &DatabaseConnection,
+        stale_before: DateTimeWithTimeZone,
+    ) -> ModelResult<u64> {
+        let requeued = generation_jobs::Entity::update_many()
+            .col_expr(
+                generation_jobs::Column::Status,
+                Expr::value(JobStatus::Queued.as_str()),
+            )
+            .col_expr(
+                generation_jobs::Column::UpdatedAt,
+                Expr::value(DateTimeWithTimeZone::from(chrono::Utc::now())),
+            )
+            .filter(generation_jobs::Column::Status.eq(JobStatus::Running.as_str()))
+            .filter(generation_jobs::Column::StartedAt.lt(stale_before))
+            .exec(db)
+            .await?;
+
+        Ok(requeued.rows_affected)
+    }
+
+    /// finds every queued job, oldest due first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_queued(db: &DatabaseConnection) -> ModelResult<Vec<Self>> {
+        generation_jobs::Entity::find()
+            .filter(generation_jobs::Column::Status.eq(JobStatus::Queued.as_str()))
+            .order_by_asc(generation_jobs::Column::RunAt)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// counts the jobs of `kind` created since `since`, whatever became of them
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn count_since(
+        db: &DatabaseConnection,
+        kind: JobKind,
+        since: DateTimeWithTimeZone,
+    ) -> ModelResult<u64> {
+        // SQLite compares timestamps as text, and defaults `created_at` to
+        // `YYYY-MM-DD HH:MM:SS`, which an RFC 3339 bound value sorts after
+        // on the same day
+        let since = match db.get_database_backend() {
+            DbBackend::Sqlite => generation_jobs::Column::CreatedAt.gte(since.naive_utc()),
+            _ => generation_jobs::Column::CreatedAt.gte(since),
+        };
+        generation_jobs::Entity::find()
+            .filter(generation_jobs::Column::Kind.eq(kind.as_str()))
+            .filter(since)
+            .count(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// counts the queued or running jobs of `kind`
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn count_active(db: &DatabaseConnection, kind: JobKind) -> ModelResult<u64> {
+        generation_jobs::Entity::find()
+            .filter(generation_jobs::Column::Kind.eq(kind.as_str()))
+            .filter(
+                Condition::any()
+                    .add(generation_jobs::Column::Status.eq(JobStatus::Queued.as_str()))
+                    .add(generation_jobs::Column::Status.eq(JobStatus::Running.as_str())),
+            )
+            .count(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// finds the latest n jobs ordered by creation date (most recent first)
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_n_latest(db: &DatabaseConnection, n: u64) -> ModelResult<Vec<Self>> {
+        generation_jobs::Entity::find()
+            .order_by_desc(generation_jobs::Column::CreatedAt)
+            .order_by_desc(generation_jobs::Column::Id)
+            .limit(n)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// finds the queued or running jobs that target the given art
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_active_for_art(
+        db: &DatabaseConnection,
+        art_id: i32,
+    ) -> ModelResult<Vec<Self>> {
+        generation_jobs::Entity::find()
+            .filter(generation_jobs::Column::ArtId.eq(art_id))
+            .filter(
+                Condition::any()
+                    .add(generation_jobs::Column::Status.eq(JobStatus::Queued.as_str()))
+                    .add(generation_jobs::Column::Status.eq(JobStatus::Running.as_str())),
+            )
+            .order_by_asc(generation_jobs::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn find_required(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        generation_jobs::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// `job_kind` parses the stored kind column.
+    ///
+    /// # Errors
+    ///
+    /// When the stored kind is not a known `JobKind`.
+    pub fn job_kind(&self) -> Result<JobKind, String> {
+        self.kind.parse()
+    }
+
+    /// `job_payload` deserializes the stored payload, defaulting when empty.
+    ///
+    /// # Errors
+    ///
+    /// When the stored payload doesn't match `JobPayload`.
+    pub fn job_payload(&self) -> Result<JobPayload, serde_json::Error> {
+        self.payload
+            .clone()
+            .map_or_else(|| Ok(JobPayload::default()), serde_json::from_value)
+    }
+
+    #[must_use]
+    pub fn has_attempts_left(&self) -> bool {
+        self.attempts < self.max_attempts
+    }
+}
+
+pub struct GenerationJobParams {
+    pub kind: JobKind,
+    pub art_id: Option<i32>,
+    pub payload: JobPayload,
+    pub max_attempts: i32,
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+pub use super::_entities::generation_usage::{self, ActiveModel, Entity, Model};
+use loco_rs::model::ModelResult;
+use sea_orm::{ActiveValue, FromQueryResult, QuerySelect, entity::prelude::*};
+use serde::{Deserialize, Serialize};
+pub type GenerationUsage = Entity;
+
+/// How many days of usage the backoffice spend breakdown covers.
+pub const SPEND_WINDOW_DAYS: i64 = 30;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl super::_entities::generation_usage::Model {
+    /// Records one row per generation call.
+    ///
+    /// # Errors
+    ///
+    /// When the rows could not be saved.
+    pub async fn create_many(db: &DatabaseConnection, params: &[UsageParams]) -> ModelResult<()> {
+        if params.is_empty() {
+            return Ok(());
+        }
+
+        let rows = params.iter().map(|params| generation_usage::ActiveModel {
+            flow: ActiveValue::set(params.flow.clone()),
+            step: ActiveValue::set(params.step.clone()),
+            model: ActiveValue::set(params.model.clone()),
+            prompt_tokens: ActiveValue::set(params.prompt_tokens),
+            completion_tokens: ActiveValue::set(params.completion_tokens),
+            total_tokens: ActiveValue::set(params.total_tokens),
+            cost: ActiveValue::set(params.cost),
+            latency_ms: ActiveValue::set(params.latency_ms),
+            success: ActiveValue::set(params.success),
+            art_id: ActiveValue::set(params.art_id),
+            mix_id: ActiveValue::set(params.mix_id),
+            ..Default::default()
+        });
+        generation_usage::Entity::insert_many(rows).exec(db).await?;
+
+        Ok(())
+    }
+
+    /// Sums the spend of the last [`SPEND_WINDOW_DAYS`] days per day, per
+    /// model and per flow. Grouping happens in Rust so day buckets behave the
+    /// same on `SQLite` and Postgres.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn spend_stats(db: &DatabaseConnection) -> ModelResult<SpendStats> {
+        let since = chrono::Utc::now() - chrono::Duration::days(SPEND_WINDOW_DAYS);
+        let rows = generation_usage::Entity::find()
+            .filter(generation_usage::Column::CreatedAt.gte(since))
+            .select_only()
+            .column(generation_usage::Column::CreatedAt)
+            .column(generation_usage::Column::Flow)
+            .column(generation_usage::Column::Model)
+            .column(generation_usage::Column::TotalTokens)
+            .column(generation_usage::Column::Cost)
+            .column(generation_usage::Column::Success)
+            .into_partial_model::<UsageRow>()
+            .all(db)
+            .await?;
+
+        Ok(SpendStats::from_rows(&rows))
+    }
+}
+
+pub struct UsageParams {
+    pub flow: String,
+    pub step: String,
+    pub model: String,
+    pub prompt_tokens: Option<i32>,
+    pub completion_tokens: Option<i32>,
+    pub total_tokens: Option<i32>,
+    pub cost: Option<f64>,
+    pub latency_ms: i32,
+    pub success: bool,
+    pub art_id: Option<i32>,
+    pub mix_id: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct SpendStat {
+    pub label: String,
+    pub calls: u64,
+    pub failed_calls: u64,
+    pub tokens: i64,
+    pub cost: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct SpendStats {
+    pub window_days: i64,
+    pub total: SpendStat,
+    /// Most recent day first.
+    pub days: Vec<SpendStat>,
+    /// Most expensive first.
+    pub models: Vec<SpendStat>,
+    /// Most expensive first.
+    pub flows: Vec<SpendStat>,
+}
+
+impl SpendStat {
+    fn add(&mut self, row: &UsageRow) {
+        self.calls += 1;
+        if !row.success {
+            self.failed_calls += 1;
+        }
+        self.tokens += i64::from(row.total_tokens.unwrap_or_default());
+        self.cost += row.cost.unwrap_or_default();
+    }
+}
+
+impl SpendStats {
+    fn from_rows(rows: &[UsageRow]) -> Self {
+        let mut total = SpendStat {
+            label: "total".to_string(),
+            ..Default::default()
+        };
+        let mut days = BTreeMap::<String, SpendStat>::new();
+        let mut models = BTreeMap::<String, SpendStat>::new();
+        let mut flows = BTreeMap::<String, SpendStat>::new();
+
+        for row in rows {
+            total.add(row);
+            let day = row.created_at.format("%Y-%m-%d").to_string();
+            for (groups, key) in [
+                (&mut days, day),
+                (&mut models, row.model.clone()),
+                (&mut flows, row.flow.clone()),
+            ] {
+                groups
+                    .entry(key.clone())
+                    .or_insert_with(|| SpendStat {
+                        label: key,
+                        ..Default::default()
+                    })
+                    .add(row);
+            }
+        }
+
+        let by_cost = |groups: BTreeMap<String, SpendStat>| {
+            let mut stats = groups.into_values().collect::<Vec<_>>();
+            stats.sort_by(|a, b| b.cost.total_cmp(&a.cost).then(b.calls.cmp(&a.calls)));
+            stats
+        };
+
+        Self {
+            window_days: SPEND_WINDOW_DAYS,
+            total,
+            days: days.into_values().rev().collect(),
+            models: by_cost(models),
+            flows: by_cost(flows),
+        }
+    }
+}
+
+#[derive(DerivePartialModel, FromQueryResult)]
+#[sea_orm(entity = "Entity")]
+struct UsageRow {
+    pub created_at: DateTimeWithTimeZone,
+    pub flow: String,
+    pub model: String,
+    pub total_tokens: Option<i32>,
+    pub cost: Option<f64>,
+    pub success: bool,
+}
@@ -1,7 +1,9 @@
 pub use super::_entities::mixarts::{self, ActiveModel, Entity, Model};
+use std::collections::HashMap;
+
 use loco_rs::model::ModelResult;
 use sea_orm::FromQueryResult;
-use sea_orm::{ActiveValue, QuerySelect, TransactionTrait, entity::prelude::*};
+use sea_orm::{ActiveValue, QueryOrder, QuerySelect, TransactionTrait, entity::prelude::*};
 pub type Mixarts = Entity;
 
 #[async_trait::async_trait]
@@ -31,22 +33,57 @@ impl super::_entities::mixarts::Model {
     /// When could not save the art into the DB
     pub async fn create(db: &DatabaseConnection, params: &MixArtParams) -> ModelResult<()> {
         let txn = db.begin().await?;
+        Self::link(&txn, params).await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
 
-        for art_id in params.art_ids.clone().into_iter() {
+    /// Links a mix to its source arts on `db`, so it can run inside the
+    /// transaction that saves the mix.
+    ///
+    /// # Errors
+    ///
+    /// When a link could not be saved
+    pub async fn link<C: ConnectionTrait>(db: &C, params: &MixArtParams) -> ModelResult<()> {
+        for art_id in params.art_ids.iter().copied() {
             mixarts::ActiveModel {
                 art_id: ActiveValue::set(art_id),
                 mix_id: ActiveValue::set(params.mix_id),
                 ..Default::default()
             }
-            .insert(&txn)
+            .insert(db)
             .await?;
         }
 
-        txn.commit().await?;
-
         Ok(())
     }
 
+    /// finds the source art ids of each of `mix_ids`, in ascending order
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_art_ids_by_mix(
+        db: &DatabaseConnection,
+        mix_ids: &[i32],
+    ) -> ModelResult<HashMap<i32, Vec<i32>>> {
+        let links = mixarts::Entity::find()
+            .filter(mixarts::Column::MixId.is_in(mix_ids.iter().copied()))
+            .order_by_asc(mixarts::Column::ArtId)
+            .select_only()
+            .columns([mixarts::Column::MixId, mixarts::Column::ArtId])
+            .into_tuple::<(i32, i32)>()
+            .all(db)
+            .await?;
+
+        let mut art_ids: HashMap<i32, Vec<i32>> = HashMap::new();
+        for (mix_id, art_id) in links {
+            art_ids.entry(mix_id).or_default().push(art_id);
+        }
+        Ok(art_ids)
+    }
+
     /// Asynchronously finds all art ids related with a mix
     ///
     /// # Errors
@@ -5,12 +5,18 @@ use loco_rs::Error;
 use loco_rs::model::{self, ModelError, ModelResult};
 use sea_orm::FromQueryResult;
 use sea_orm::{
-    ActiveValue, EntityTrait, PaginatorTrait, QueryOrder, QuerySelect, TransactionTrait,
+    ActiveValue, Condition, EntityTrait, PaginatorTrait, QueryOrder, QuerySelect, TransactionTrait,
     entity::prelude::*,
 };
+
+use super::arts::{PAGE_SIZE, image_version};
+pub use super::arts::{Provenance, PublicationStatus};
+use super::mixarts::{self, MixArtParams};
+use crate::common::image_hash;
 pub type Mixes = Entity;
 
 pub const BACKOFFICE_PAGE_SIZE: u64 = 24;
+pub const INDEX_PAGE_SIZE: u64 = 12;
 
 #[async_trait::async_trait]
 impl ActiveModelBehavior for ActiveModel {
@@ -30,6 +36,15 @@ impl ActiveModelBehavior for ActiveModel {
     }
 }
 
+fn published() -> Condition {
+    Condition::all().add(mixes::Column::Status.eq(PublicationStatus::Published.as_str()))
+}
+
+#[must_use]
+pub fn image_url(id: i32, updated_at: &DateTimeWithTimeZone) -> String {
+    format!("/mix/img/{id}.webp?v={}", image_version(updated_at))
+}
+
 impl super::_entities::mixes::Model {
     /// Asynchronously creates a mix.
     /// database.
@@ -39,29 +54,130 @@ impl super::_entities::mixes::Model {
     /// When could not save the art into the DB
     pub async fn create(db: &DatabaseConnection, params: &MixParams) -> ModelResult<Self> {
         let txn = db.begin().await?;
+        let mix = Self::insert_with_sources(&txn, params, &[]).await?;
+        txn.commit().await?;
+
+        Ok(mix)
+    }
 
-        let art = mixes::ActiveModel {
+    /// Inserts a mix and links it to `art_ids` on `db`. Run it in a
+    /// transaction, so a mix is never saved without its sources.
+    ///
+    /// # Errors
+    ///
+    /// When the mix or one of its links could not be saved
+    pub async fn insert_with_sources<C: ConnectionTrait>(
+        db: &C,
+        params: &MixParams,
+        art_ids: &[i32],
+    ) -> ModelResult<Self> {
+        let mix = mixes::ActiveModel {
             image: ActiveValue::set(params.image.to_string()),
             prompt: ActiveValue::set(params.prompt.to_string()),
             title: ActiveValue::set(params.title.to_string()),
             model: ActiveValue::set(params.model.clone()),
+            prompt_model: ActiveValue::set(params.provenance.prompt_model.clone()),
+            title_model: ActiveValue::set(params.provenance.title_model.clone()),
+            prompt_meta_prompt: ActiveValue::set(params.provenance.prompt_meta_prompt.clone()),
+            title_meta_prompt: ActiveValue::set(params.provenance.title_meta_prompt.clone()),
+            prompt_template_id: ActiveValue::set(params.provenance.prompt_template_id),
+            title_template_id: ActiveValue::set(params.provenance.title_template_id),
+            status: ActiveValue::set(params.status.to_string()),
+            image_hash: ActiveValue::set(image_hash::dhash_base64(&params.image)),
+            request_id: ActiveValue::set(params.request_id),
             ..Default::default()
         }
-        .insert(&txn)
+        .insert(db)
         .await?;
 
-        txn.commit().await?;
+        mixarts::Model::link(
+            db,
+            &MixArtParams {
+                mix_id: mix.id,
+                art_ids: art_ids.to_vec(),
+            },
+        )
+        .await?;
 
-        Ok(art)
+        Ok(mix)
     }
 
-    /// finds an art an returns just its base64 encoded image
+    /// finds the mix saved for a mix request
+    ///
     /// # Errors
     ///
-    /// When db fails or when the item is missing
+    /// On DB query error
+    pub async fn find_by_request_id(
+        db: &DatabaseConnection,
+        request_id: Uuid,
+    ) -> ModelResult<Option<Self>> {
+        Ok(mixes::Entity::find()
+            .filter(mixes::Column::RequestId.eq(request_id))
+            .one(db)
+            .await?)
+    }
+
+    /// finds the mixes linked to no source art, oldest first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_without_sources(db: &DatabaseConnection) -> ModelResult<Vec<MixImageHash>> {
+        Ok(mixes::Entity::find()
+            .filter(
+                mixes::Column::Id.not_in_subquery(
+                    sea_orm::sea_query::Query::select()
+                        .column(mixarts::mixarts::Column::MixId)
+                        .from(mixarts::Entity)
+                        .to_owned(),
+                ),
+            )
+            .order_by_asc(mixes::Column::Id)
+            .into_partial_model::<MixImageHash>()
+            .all(db)
+            .await?)
+    }
+
+    #[must_use]
+    pub fn provenance(&self) -> Provenance {
+        Provenance {
+            prompt_model: self.prompt_model.clone(),
+            title_model: self.title_model.clone(),
+            prompt_meta_prompt: self.prompt_meta_prompt.clone(),
+            title_meta_prompt: self.title_meta_prompt.clone(),
+            prompt_template_id: self.prompt_template_id,
+            title_template_id: self.title_template_id,
+        }
+    }
+
+    /// finds a published mix and returns just its decoded image
+    /// # Errors
+    ///
+    /// When db fails or when the item is missing or not published
     pub async fn find_img_slice_by_id(db: &DatabaseConnection, id: u32) -> ModelResult<Vec<u8>> {
+        Self::find_img_slice(db, id, published()).await
+    }
+
+    /// finds a mix whatever its status and returns just its decoded
+    /// image, for the backoffice and maintenance tasks
+    /// # Errors
+    ///
+    /// When db fails or when the item is missing
+    pub async fn find_any_img_slice_by_id(
+        db: &DatabaseConnection,
+        id: u32,
+    ) -> ModelResult<Vec<u8>> {
+        Self::find_img_slice(db, id, Condition::all()).await
+    }
+
+    async fn find_img_slice(
+        db: &DatabaseConnection,
+        id: u32,
+        condition: Condition,
+    ) -> ModelResult<Vec<u8>> {
         let image = match mixes::Entity::find()
             .filter(model::query::condition().eq(mixes::Column::Id, id).build())
+            .filter(condition)
             .limit(1)
             .select_only()
             .column(mixes::Column::Image)
@@ -85,6 +201,151 @@ impl super::_entities::mixes::Model {
         Ok(img)
     }
 
+    /// finds a published mix by id
+    ///
+    /// # Errors
+    ///
+    /// When the mix is missing or not published, or on DB query error
+    pub async fn find_published(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        mixes::Entity::find_by_id(id)
+            .filter(mixes::Column::Status.eq(PublicationStatus::Published.as_str()))
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// finds the latest published mix
+    ///
+    /// # Errors
+    ///
+    /// When no mix is published, or on DB query error
+    pub async fn find_latest_published(db: &DatabaseConnection) -> ModelResult<MixTitleId> {
+        mixes::Entity::find()
+            .filter(published())
+            .order_by_desc(mixes::Column::Id)
+            .into_partial_model::<MixTitleId>()
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)
+    }
+
+    /// fetches published mixes before the given id, newest first.
+    /// the returned data is paginated.
+    ///
+    /// # Errors
+    ///
+    /// When could not find mixes or DB query error
+    pub async fn find_before_id(
+        db: &DatabaseConnection,
+        id: i32,
+    ) -> Result<Vec<MixTitleId>, Error> {
+        Self::find_page_before(db, id, PAGE_SIZE).await
+    }
+
+    /// fetches published mixes after the given id, oldest first.
+    /// the returned data is paginated.
+    ///
+    /// # Errors
+    ///
+    /// When could not find mixes or DB query error
+    pub async fn find_after_id(db: &DatabaseConnection, id: i32) -> Result<Vec<MixTitleId>, Error> {
+        Self::find_page_after(db, id, PAGE_SIZE).await
+    }
+
+    /// fetches up to `limit` published mixes before the given id, newest
+    /// first.
+    ///
+    /// # Errors
+    ///
+    /// When could not find mixes or DB query error
+    pub async fn find_page_before(
+        db: &DatabaseConnection,
+        id: i32,
+        limit: u64,
+    ) -> Result<Vec<MixTitleId>, Error> {
+        let mixes = mixes::Entity::find()
+            .filter(published())
+            .select_only()
+            .columns([
+                mixes::Column::Id,
+                mixes::Column::Title,
+                mixes::Column::UpdatedAt,
+            ])
+            .cursor_by(mixes::Column::Id)
+            .into_partial_model::<MixTitleId>()
+            .before(id)
+            .last(limit)
+            .all(db)
+            .await?;
+
+        Ok(mixes.into_iter().rev().collect())
+    }
+
+    /// fetches up to `limit` published mixes after the given id, oldest
+    /// first.
+    ///
+    /// # Errors
+    ///
+    /// When could not find mixes or DB query error
+    pub async fn find_page_after(
+        db: &DatabaseConnection,
+        id: i32,
+        limit: u64,
+    ) -> Result<Vec<MixTitleId>, Error> {
+        let mixes = mixes::Entity::find()
+            .filter(published())
+            .select_only()
+            .columns([
+                mixes::Column::Id,
+                mixes::Column::Title,
+                mixes::Column::UpdatedAt,
+            ])
+            .cursor_by(mixes::Column::Id)
+            .into_partial_model::<MixTitleId>()
+            .after(id)
+            .first(limit)
+            .all(db)
+            .await?;
+
+        Ok(mixes)
+    }
+
+    /// finds the mixes with `status`, most recent first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_by_status(
+        db: &DatabaseConnection,
+        status: PublicationStatus,
+    ) -> ModelResult<Vec<Self>> {
+        Ok(mixes::Entity::find()
+            .filter(mixes::Column::Status.eq(status.as_str()))
+            .order_by_desc(mixes::Column::CreatedAt)
+            .all(db)
+            .await?)
+    }
+
+    /// Moves a mix in or out of the public gallery.
+    ///
+    /// # Errors
+    ///
+    /// When the mix is missing or could not be saved.
+    pub async fn set_status(
+        db: &DatabaseConnection,
+        id: i32,
+        status: PublicationStatus,
+    ) -> ModelResult<Self> {
+        let mix = mixes::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)?;
+
+        let mut mix_active_model: ActiveModel = mix.into();
+        mix_active_model.status = ActiveValue::set(status.to_string());
+        mix_active_model.update(db).await.map_err(Into::into)
+    }
+
     pub async fn find_n_latest(db: &DatabaseConnection, n: u64) -> ModelResult<Vec<Self>> {
         mixes::Entity::find()
             .order_by_desc(mixes::Column::CreatedAt)
@@ -120,6 +381,67 @@ impl super::_entities::mixes::Model {
         })
     }
 
+    /// finds the id, title and perceptual hash of every hashed mix
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_image_hashes(db: &DatabaseConnection) -> ModelResult<Vec<MixImageHash>> {
+        Ok(mixes::Entity::find()
+            .filter(mixes::Column::ImageHash.is_not_null())
+            .order_by_asc(mixes::Column::Id)
+            .select_only()
+            .columns([
+                mixes::Column::Id,
+                mixes::Column::Title,
+                mixes::Column::Status,
+                mixes::Column::UpdatedAt,
+                mixes::Column::ImageHash,
+            ])
+            .into_partial_model::<MixImageHash>()
+            .all(db)
+            .await?)
+    }
+
+    /// finds the ids of the mixes without a perceptual hash, or of every mix
+    /// when `all`
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_ids_to_hash(db: &DatabaseConnection, all: bool) -> ModelResult<Vec<i32>> {
+        let mut query = mixes::Entity::find();
+        if !all {
+            query = query.filter(mixes::Column::ImageHash.is_null());
+        }
+        Ok(query
+            .order_by_asc(mixes::Column::Id)
+            .select_only()
+            .column(mixes::Column::Id)
+            .into_tuple::<i32>()
+            .all(db)
+            .await?)
+    }
+
+    /// Stores the perceptual hash of a mix's image, leaving `updated_at`
+    /// alone.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn set_image_hash(
+        db: &DatabaseConnection,
+        id: i32,
+        hash: Option<i64>,
+    ) -> ModelResult<()> {
+        mixes::Entity::update_many()
+            .col_expr(mixes::Column::ImageHash, Expr::value(hash))
+            .filter(mixes::Column::Id.eq(id))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
     pub async fn delete_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<()> {
         let mix = mixes::Entity::find_by_id(id)
             .one(db)
@@ -131,11 +453,33 @@ impl super::_entities::mixes::Model {
     }
 }
 
+#[derive(Default)]
 pub struct MixParams {
     pub image: String,
     pub prompt: String,
     pub title: String,
     pub model: String,
+    pub provenance: Provenance,
+    pub status: PublicationStatus,
+    pub request_id: Option<Uuid>,
+}
+
+#[derive(DerivePartialModel, FromQueryResult, serde::Serialize, serde::Deserialize, Debug)]
+#[sea_orm(entity = "Entity")]
+pub struct MixTitleId {
+    pub id: i32,
+    pub title: String,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+impl From<mixes::Model> for MixTitleId {
+    fn from(value: mixes::Model) -> Self {
+        Self {
+            id: value.id,
+            title: value.title,
+            updated_at: value.updated_at,
+        }
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -148,6 +492,16 @@ pub struct BackofficeMixList {
     pub next_page: Option<u64>,
 }
 
+#[derive(DerivePartialModel, FromQueryResult, serde::Serialize, serde::Deserialize, Debug)]
+#[sea_orm(entity = "Entity")]
+pub struct MixImageHash {
+    pub id: i32,
+    pub title: String,
+    pub status: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub image_hash: Option<i64>,
+}
+
 #[derive(DerivePartialModel, FromQueryResult)]
 #[sea_orm(entity = "Entity")]
 struct MixImage {
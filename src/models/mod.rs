@@ -1,5 +1,13 @@
 pub mod _entities;
+pub mod art_candidates;
+pub mod art_revisions;
 pub mod arts;
+pub mod generation_jobs;
+pub mod generation_usage;
 pub mod mixarts;
 pub mod mixes;
+pub mod prompt_templates;
+pub mod scheduled_runs;
+pub mod staged_mixes;
+pub mod style_presets;
 pub mod users;
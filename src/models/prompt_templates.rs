@@ -0,0 +1,270 @@
+use std::{fmt, str::FromStr};
+
+pub use super::_entities::prompt_templates::{self, ActiveModel, Entity, Model};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{
+    ActiveValue, QueryOrder, QuerySelect, TransactionTrait, entity::prelude::*, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+pub type PromptTemplates = Entity;
+
+/// `TemplateKind` is which of the generation meta-prompts (or the samples fed
+/// into them while the gallery is empty) a template is a version of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    Image,
+    Title,
+    MixImage,
+    Variation,
+    SamplePrompts,
+    SampleTitles,
+}
+
+impl TemplateKind {
+    pub const ALL: [Self; 6] = [
+        Self::Image,
+        Self::Title,
+        Self::MixImage,
+        Self::Variation,
+        Self::SamplePrompts,
+        Self::SampleTitles,
+    ];
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Title => "title",
+            Self::MixImage => "mix_image",
+            Self::Variation => "variation",
+            Self::SamplePrompts => "sample_prompts",
+            Self::SampleTitles => "sample_titles",
+        }
+    }
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Image => "Image prompt",
+            Self::Title => "Title",
+            Self::MixImage => "Mix image prompt",
+            Self::Variation => "Variation prompt",
+            Self::SamplePrompts => "Sample prompts",
+            Self::SampleTitles => "Sample titles",
+        }
+    }
+
+    /// The placeholders every version of the template has to contain.
+    #[must_use]
+    pub const fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            Self::Image | Self::MixImage => &["{{PROMPTS}}"],
+            Self::Title => &["{{TITLES}}", "{{DESCRIPTION}}"],
+            Self::Variation => &["{{PROMPT}}", "{{INSTRUCTION}}"],
+            Self::SamplePrompts | Self::SampleTitles => &[],
+        }
+    }
+
+    /// `missing_placeholders` lists the required placeholders `body` lacks.
+    #[must_use]
+    pub fn missing_placeholders(self, body: &str) -> Vec<&'static str> {
+        self.placeholders()
+            .iter()
+            .copied()
+            .filter(|placeholder| !body.contains(placeholder))
+            .collect()
+    }
+}
+
+impl fmt::Display for TemplateKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TemplateKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("Invalid prompt template kind: {s}"))
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+impl super::_entities::prompt_templates::Model {
+    /// Saves `params.body` as the next version of its kind, and makes it the
+    /// active one when `params.activate` is set. Versions are never edited
+    /// or deleted, so arts keep pointing at the text that produced them.
+    ///
+    /// # Errors
+    ///
+    /// When the body is empty or lacks a required placeholder, or on DB
+    /// query error.
+    pub async fn create_version(
+        db: &DatabaseConnection,
+        params: &TemplateParams,
+    ) -> ModelResult<Self> {
+        if params.body.trim().is_empty() {
+            return Err(ModelError::msg("The template cannot be empty."));
+        }
+        let missing = params.kind.missing_placeholders(&params.body);
+        if !missing.is_empty() {
+            return Err(ModelError::Message(format!(
+                "The template is missing {}.",
+                missing.join(", ")
+            )));
+        }
+
+        let txn = db.begin().await?;
+        let latest: Option<i32> = prompt_templates::Entity::find()
+            .select_only()
+            .column_as(prompt_templates::Column::Version.max(), "version")
+            .filter(prompt_templates::Column::Kind.eq(params.kind.as_str()))
+            .into_tuple::<Option<i32>>()
+            .one(&txn)
+            .await?
+            .flatten();
+
+        if params.activate {
+            deactivate_kind(&txn, params.kind).await?;
+        }
+        let template = prompt_templates::ActiveModel {
+            kind: ActiveValue::set(params.kind.to_string()),
+            version: ActiveValue::set(latest.unwrap_or(0) + 1),
+            body: ActiveValue::set(params.body.clone()),
+            note: ActiveValue::set(params.note.clone()),
+            active: ActiveValue::set(params.activate),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+        Ok(template)
+    }
+
+    /// Makes the version `id` the active one of its kind.
+    ///
+    /// # Errors
+    ///
+    /// When the version doesn't exist or on DB query error.
+    pub async fn activate(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let txn = db.begin().await?;
+        let template = prompt_templates::Entity::find_by_id(id)
+            .one(&txn)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        let kind = template.template_kind().map_err(ModelError::Message)?;
+
+        deactivate_kind(&txn, kind).await?;
+        let mut template: prompt_templates::ActiveModel = template.into();
+        template.active = ActiveValue::set(true);
+        template.updated_at = ActiveValue::set(chrono::Utc::now().into());
+        let template = template.update(&txn).await?;
+
+        txn.commit().await?;
+        Ok(template)
+    }
+
+    /// Deactivates every version of `kind`, so generation falls back to the
+    /// built-in template.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn use_builtin(db: &DatabaseConnection, kind: TemplateKind) -> ModelResult<()> {
+        deactivate_kind(db, kind).await
+    }
+
+    /// finds the active version of `kind`, if any
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn find_active(
+        db: &DatabaseConnection,
+        kind: TemplateKind,
+    ) -> ModelResult<Option<Self>> {
+        prompt_templates::Entity::find()
+            .filter(prompt_templates::Column::Kind.eq(kind.as_str()))
+            .filter(prompt_templates::Column::Active.eq(true))
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// finds every version of `kind`, newest first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn find_versions(
+        db: &DatabaseConnection,
+        kind: TemplateKind,
+    ) -> ModelResult<Vec<Self>> {
+        prompt_templates::Entity::find()
+            .filter(prompt_templates::Column::Kind.eq(kind.as_str()))
+            .order_by_desc(prompt_templates::Column::Version)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// finds every active version
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn find_all_active(db: &DatabaseConnection) -> ModelResult<Vec<Self>> {
+        prompt_templates::Entity::find()
+            .filter(prompt_templates::Column::Active.eq(true))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// finds the versions with the given ids
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn find_in(db: &DatabaseConnection, ids: Vec<i32>) -> ModelResult<Vec<Self>> {
+        prompt_templates::Entity::find()
+            .filter(prompt_templates::Column::Id.is_in(ids))
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// # Errors
+    ///
+    /// When the stored kind is not a known `TemplateKind`.
+    pub fn template_kind(&self) -> Result<TemplateKind, String> {
+        self.kind.parse()
+    }
+}
+
+async fn deactivate_kind<C: ConnectionTrait>(db: &C, kind: TemplateKind) -> ModelResult<()> {
+    prompt_templates::Entity::update_many()
+        .col_expr(prompt_templates::Column::Active, Expr::value(false))
+        .filter(prompt_templates::Column::Kind.eq(kind.as_str()))
+        .filter(prompt_templates::Column::Active.eq(true))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub struct TemplateParams {
+    pub kind: TemplateKind,
+    pub body: String,
+    pub note: Option<String>,
+    pub activate: bool,
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+
+pub use super::_entities::scheduled_runs::{self, ActiveModel, Entity, Model};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{
+    ActiveValue, Condition, QueryOrder, QuerySelect, entity::prelude::*, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+pub type ScheduledRuns = Entity;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// Planned for `scheduled_for`, not started yet.
+    Scheduled,
+    Running,
+    Completed,
+    Failed,
+    /// Not run because the previous run of the same job was still going.
+    Skipped,
+    /// Planned or running when the server stopped.
+    Interrupted,
+}
+
+impl RunStatus {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+            Self::Interrupted => "interrupted",
+        }
+    }
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl super::_entities::scheduled_runs::Model {
+    /// Records a run of `job` planned for `scheduled_for`.
+    ///
+    /// # Errors
+    ///
+    /// When the run could not be saved.
+    pub async fn create(
+        db: &DatabaseConnection,
+        job: &str,
+        kind: &str,
+        status: RunStatus,
+        scheduled_for: DateTimeWithTimeZone,
+    ) -> ModelResult<Self> {
+        let started_at = (status == RunStatus::Running).then(|| chrono::Utc::now().into());
+        scheduled_runs::ActiveModel {
+            job: ActiveValue::set(job.to_string()),
+            kind: ActiveValue::set(kind.to_string()),
+            status: ActiveValue::set(status.to_string()),
+            scheduled_for: ActiveValue::set(scheduled_for),
+            started_at: ActiveValue::set(started_at),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Moves a planned run to `running`.
+    ///
+    /// # Errors
+    ///
+    /// When the run is missing or could not be saved.
+    pub async fn start(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        let mut run: ActiveModel = Self::find_required(db, id).await?.into();
+        run.status = ActiveValue::set(RunStatus::Running.to_string());
+        run.started_at = ActiveValue::set(Some(chrono::Utc::now().into()));
+        run.update(db).await.map_err(Into::into)
+    }
+
+    /// Records how a run ended.
+    ///
+    /// # Errors
+    ///
+    /// When the run is missing or could not be saved.
+    pub async fn finish(
+        db: &DatabaseConnection,
+        id: i32,
+        status: RunStatus,
+        message: &str,
+    ) -> ModelResult<Self> {
+        let mut run: ActiveModel = Self::find_required(db, id).await?.into();
+        run.status = ActiveValue::set(status.to_string());
+        run.message = ActiveValue::set(Some(message.to_string()));
+        run.finished_at = ActiveValue::set(Some(chrono::Utc::now().into()));
+        run.update(db).await.map_err(Into::into)
+    }
+
+    /// Marks runs still running since before `stale_before`, or still planned
+    /// for before it, as interrupted, returning how many there were.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn interrupt_unfinished(
+        db: &DatabaseConnection,
+        stale_before: DateTimeWithTimeZone,
+    ) -> ModelResult<u64> {
+        let interrupted = scheduled_runs::Entity::update_many()
+            .col_expr(
+                scheduled_runs::Column::Status,
+                Expr::value(RunStatus::Interrupted.as_str()),
+            )
+            .col_expr(
+                scheduled_runs::Column::UpdatedAt,
+                Expr::value(DateTimeWithTimeZone::from(chrono::Utc::now())),
+            )
+            .filter(
+                Condition::any()
+                    .add(
+                        scheduled_runs::Column::Status
+                            .eq(RunStatus::Running.as_str())
+                            .and(scheduled_runs::Column::StartedAt.lt(stale_before)),
+                    )
+                    .add(
+                        scheduled_runs::Column::Status
+                            .eq(RunStatus::Scheduled.as_str())
+                            .and(scheduled_runs::Column::ScheduledFor.lt(stale_before)),
+                    ),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(interrupted.rows_affected)
+    }
+
+    /// finds the planned runs, soonest first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_upcoming(db: &DatabaseConnection) -> ModelResult<Vec<Self>> {
+        Ok(scheduled_runs::Entity::find()
+            .filter(scheduled_runs::Column::Status.eq(RunStatus::Scheduled.as_str()))
+            .order_by_asc(scheduled_runs::Column::ScheduledFor)
+            .all(db)
+            .await?)
+    }
+
+    /// finds the latest n runs that were not merely planned
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_n_latest(db: &DatabaseConnection, n: u64) -> ModelResult<Vec<Self>> {
+        Ok(scheduled_runs::Entity::find()
+            .filter(scheduled_runs::Column::Status.ne(RunStatus::Scheduled.as_str()))
+            .order_by_desc(scheduled_runs::Column::Id)
+            .limit(n)
+            .all(db)
+            .await?)
+    }
+
+    async fn find_required(db: &DatabaseConnection, id: i32) -> ModelResult<Self> {
+        scheduled_runs::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or_else(|| ModelError::EntityNotFound)
+    }
+}
@@ -0,0 +1,154 @@
+pub use super::_entities::staged_mixes::{self, ActiveModel, Entity, Model};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{ActiveValue, QueryOrder, TransactionTrait, entity::prelude::*};
+
+use super::mixes::{self, MixParams, Provenance, PublicationStatus};
+pub type StagedMixes = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl super::_entities::staged_mixes::Model {
+    /// Keeps a rendered mix until it is saved, so a failed save is retried
+    /// without paying for the generations again. Staging the same request
+    /// twice keeps the first render.
+    ///
+    /// # Errors
+    ///
+    /// When the render could not be staged.
+    pub async fn stage(
+        db: &DatabaseConnection,
+        request_id: Uuid,
+        params: &MixParams,
+        art_ids: &[i32],
+    ) -> ModelResult<Self> {
+        if let Some(staged) = Self::find_by_request_id(db, request_id).await? {
+            return Ok(staged);
+        }
+
+        let provenance = &params.provenance;
+        Ok(staged_mixes::ActiveModel {
+            request_id: ActiveValue::set(request_id),
+            art_ids: ActiveValue::set(serde_json::json!(art_ids)),
+            image: ActiveValue::set(params.image.clone()),
+            prompt: ActiveValue::set(params.prompt.clone()),
+            title: ActiveValue::set(params.title.clone()),
+            model: ActiveValue::set(params.model.clone()),
+            prompt_model: ActiveValue::set(provenance.prompt_model.clone()),
+            title_model: ActiveValue::set(provenance.title_model.clone()),
+            prompt_meta_prompt: ActiveValue::set(provenance.prompt_meta_prompt.clone()),
+            title_meta_prompt: ActiveValue::set(provenance.title_meta_prompt.clone()),
+            prompt_template_id: ActiveValue::set(provenance.prompt_template_id),
+            title_template_id: ActiveValue::set(provenance.title_template_id),
+            status: ActiveValue::set(params.status.to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?)
+    }
+
+    /// finds the render staged for a mix request
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_by_request_id(
+        db: &DatabaseConnection,
+        request_id: Uuid,
+    ) -> ModelResult<Option<Self>> {
+        Ok(staged_mixes::Entity::find()
+            .filter(staged_mixes::Column::RequestId.eq(request_id))
+            .one(db)
+            .await?)
+    }
+
+    /// finds every staged render, oldest first
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn find_all(db: &DatabaseConnection) -> ModelResult<Vec<Self>> {
+        Ok(staged_mixes::Entity::find()
+            .order_by_asc(staged_mixes::Column::Id)
+            .all(db)
+            .await?)
+    }
+
+    /// Saves the staged render as a mix linked to its sources and drops it
+    /// from the stage, all in one transaction. When the request was already
+    /// saved, by a retry or a repair, that mix is returned instead.
+    ///
+    /// # Errors
+    ///
+    /// When the mix could not be saved; the render stays staged.
+    pub async fn save(&self, db: &DatabaseConnection) -> ModelResult<mixes::Model> {
+        let txn = db.begin().await?;
+
+        let unstaged = staged_mixes::Entity::delete_by_id(self.id)
+            .exec(&txn)
+            .await?;
+        if unstaged.rows_affected == 0 {
+            txn.rollback().await?;
+            return mixes::Model::find_by_request_id(db, self.request_id)
+                .await?
+                .ok_or(ModelError::EntityNotFound);
+        }
+
+        let mix = mixes::Model::insert_with_sources(&txn, &self.params(), &self.art_ids()).await?;
+        txn.commit().await?;
+
+        Ok(mix)
+    }
+
+    /// Records why the staged render could not be saved.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error
+    pub async fn set_last_error(&self, db: &DatabaseConnection, error: &str) -> ModelResult<()> {
+        let mut staged: ActiveModel = self.clone().into();
+        staged.last_error = ActiveValue::set(Some(error.to_string()));
+        staged.update(db).await?;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn art_ids(&self) -> Vec<i32> {
+        serde_json::from_value(self.art_ids.clone()).unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn params(&self) -> MixParams {
+        MixParams {
+            image: self.image.clone(),
+            prompt: self.prompt.clone(),
+            title: self.title.clone(),
+            model: self.model.clone(),
+            provenance: Provenance {
+                prompt_model: self.prompt_model.clone(),
+                title_model: self.title_model.clone(),
+                prompt_meta_prompt: self.prompt_meta_prompt.clone(),
+                title_meta_prompt: self.title_meta_prompt.clone(),
+                prompt_template_id: self.prompt_template_id,
+                title_template_id: self.title_template_id,
+            },
+            status: self.status.parse().unwrap_or(PublicationStatus::Draft),
+            request_id: Some(self.request_id),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+pub use super::_entities::style_presets::{self, ActiveModel, Entity, Model};
+use crate::common::directives::{Directives, Resolved};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{
+    ActiveValue, QueryOrder,
+    entity::prelude::*,
+    sea_query::{Expr, Func},
+};
+pub type StylePresets = Entity;
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+
+    async fn before_save<C>(self, _db: &C, insert: bool) -> std::result::Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if !insert && self.updated_at.is_unchanged() {
+            let mut this = self;
+            this.updated_at = sea_orm::ActiveValue::Set(chrono::Utc::now().into());
+            Ok(this)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl super::_entities::style_presets::Model {
+    /// Saves a preset, replacing the directive of the preset with the same
+    /// name (compared case-insensitively).
+    ///
+    /// # Errors
+    ///
+    /// When the name or directive is empty, or on DB query error.
+    pub async fn save(db: &DatabaseConnection, params: &StylePresetParams) -> ModelResult<Self> {
+        let name = params.name.trim();
+        let directive = params.directive.trim();
+        if name.is_empty() {
+            return Err(ModelError::msg("The preset needs a name."));
+        }
+        if directive.is_empty() {
+            return Err(ModelError::msg("The preset needs a style directive."));
+        }
+
+        match Self::find_by_name(db, name).await? {
+            Some(preset) => {
+                let mut preset: ActiveModel = preset.into();
+                preset.name = ActiveValue::set(name.to_string());
+                preset.directive = ActiveValue::set(directive.to_string());
+                preset.update(db).await.map_err(Into::into)
+            }
+            None => style_presets::ActiveModel {
+                name: ActiveValue::set(name.to_string()),
+                directive: ActiveValue::set(directive.to_string()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await
+            .map_err(Into::into),
+        }
+    }
+
+    /// finds the preset named `name`, compared case-insensitively
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn find_by_name(db: &DatabaseConnection, name: &str) -> ModelResult<Option<Self>> {
+        style_presets::Entity::find()
+            .filter(
+                Expr::expr(Func::lower(Expr::col(style_presets::Column::Name)))
+                    .eq(name.trim().to_lowercase()),
+            )
+            .one(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Looks the style of `directives` up in the preset library; a style
+    /// matching no preset is used as written.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn resolve(
+        db: &DatabaseConnection,
+        directives: &Directives,
+    ) -> ModelResult<Resolved> {
+        let preset = match directives.style.as_deref() {
+            Some(style) => Self::find_by_name(db, style).await?,
+            None => None,
+        };
+
+        Ok(Resolved {
+            theme: directives.theme.clone(),
+            style: directives.style.clone(),
+            style_directive: preset
+                .as_ref()
+                .map(|preset| preset.directive.clone())
+                .or_else(|| directives.style.clone()),
+            style_preset_id: preset.map(|preset| preset.id),
+        })
+    }
+
+    /// finds every preset, by name
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn find_all(db: &DatabaseConnection) -> ModelResult<Vec<Self>> {
+        style_presets::Entity::find()
+            .order_by_asc(style_presets::Column::Name)
+            .all(db)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Deletes a preset. Arts made under it keep its id and name.
+    ///
+    /// # Errors
+    ///
+    /// When the preset doesn't exist or on DB query error.
+    pub async fn delete_by_id(db: &DatabaseConnection, id: i32) -> ModelResult<()> {
+        let preset = style_presets::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        preset.delete(db).await?;
+        Ok(())
+    }
+}
+
+pub struct StylePresetParams {
+    pub name: String,
+    pub directive: String,
+}
@@ -1,8 +1,66 @@
+use axum::http::StatusCode;
+use openrouter_rs::error::OpenRouterError;
+
 use crate::errors::Error;
 
-impl From<openrouter_rs::error::OpenRouterError> for Error {
-    fn from(value: openrouter_rs::error::OpenRouterError) -> Self {
-        Self::AIError(format!("OpenRouter request failed: {value}"))
+/// `ProviderFailure` classifies why a call to the Provider failed, which
+/// decides whether another Model from the pool is worth a try.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProviderFailure {
+    /// The API key is rejected or out of credits; every Model would fail.
+    Account,
+    /// The drawn Model doesn't exist anymore or has no endpoints.
+    ModelUnavailable,
+    /// Rate limits, timeouts and 5xx answers from the Model's upstream.
+    Transient,
+    /// Anything else, like a malformed request or a moderation rejection.
+    Request,
+}
+
+impl ProviderFailure {
+    /// `is_model_level` tells if the failure is tied to the drawn Model, so
+    /// redrawing another member of the Model Pool may succeed.
+    #[must_use]
+    pub const fn is_model_level(self) -> bool {
+        matches!(self, Self::ModelUnavailable | Self::Transient)
+    }
+}
+
+impl From<&OpenRouterError> for ProviderFailure {
+    fn from(value: &OpenRouterError) -> Self {
+        match value {
+            OpenRouterError::KeyNotConfigured => Self::Account,
+            OpenRouterError::HttpRequest(_) => Self::Transient,
+            OpenRouterError::Api(api_error) => match api_error.status {
+                StatusCode::UNAUTHORIZED | StatusCode::PAYMENT_REQUIRED | StatusCode::FORBIDDEN => {
+                    Self::Account
+                }
+                StatusCode::NOT_FOUND | StatusCode::GONE => Self::ModelUnavailable,
+                StatusCode::BAD_REQUEST if mentions_unknown_model(&api_error.message) => {
+                    Self::ModelUnavailable
+                }
+                _ if api_error.is_retryable() => Self::Transient,
+                _ => Self::Request,
+            },
+            _ => Self::Request,
+        }
+    }
+}
+
+fn mentions_unknown_model(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("not a valid model")
+        || message.contains("no endpoints found")
+        || (message.contains("model") && message.contains("not found"))
+        || (message.contains("model") && message.contains("deprecated"))
+}
+
+impl From<OpenRouterError> for Error {
+    fn from(value: OpenRouterError) -> Self {
+        Self::ProviderError {
+            failure: ProviderFailure::from(&value),
+            message: format!("OpenRouter request failed: {value}"),
+        }
     }
 }
 
@@ -17,3 +75,81 @@ impl From<base64::DecodeError> for Error {
         Self::AIError(format!("Base64 decoding error: {}", value))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use openrouter_rs::error::{ApiErrorContext, ApiErrorKind, HttpRequestError};
+
+    use super::*;
+
+    fn api_error(status: StatusCode, message: &str) -> OpenRouterError {
+        OpenRouterError::Api(Box::new(ApiErrorContext {
+            status,
+            api_code: Some(i64::from(status.as_u16())),
+            message: message.to_string(),
+            request_id: None,
+            metadata: None,
+            kind: ApiErrorKind::Generic,
+        }))
+    }
+
+    #[test]
+    fn auth_and_credit_errors_are_account_failures() {
+        for status in [
+            StatusCode::UNAUTHORIZED,
+            StatusCode::PAYMENT_REQUIRED,
+            StatusCode::FORBIDDEN,
+        ] {
+            let failure = ProviderFailure::from(&api_error(status, "Insufficient credits"));
+            assert_eq!(failure, ProviderFailure::Account, "{status}");
+            assert!(!failure.is_model_level());
+        }
+        assert_eq!(
+            ProviderFailure::from(&OpenRouterError::KeyNotConfigured),
+            ProviderFailure::Account
+        );
+    }
+
+    #[test]
+    fn missing_models_are_model_unavailable() {
+        assert_eq!(
+            ProviderFailure::from(&api_error(StatusCode::NOT_FOUND, "No endpoints found")),
+            ProviderFailure::ModelUnavailable
+        );
+        assert_eq!(
+            ProviderFailure::from(&api_error(
+                StatusCode::BAD_REQUEST,
+                "image/gone is not a valid model ID"
+            )),
+            ProviderFailure::ModelUnavailable
+        );
+    }
+
+    #[test]
+    fn rate_limits_server_errors_and_network_errors_are_transient() {
+        for status in [
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            let failure = ProviderFailure::from(&api_error(status, "try later"));
+            assert_eq!(failure, ProviderFailure::Transient, "{status}");
+            assert!(failure.is_model_level());
+        }
+        assert_eq!(
+            ProviderFailure::from(&OpenRouterError::HttpRequest(HttpRequestError::new(
+                "connection reset"
+            ))),
+            ProviderFailure::Transient
+        );
+    }
+
+    #[test]
+    fn other_bad_requests_are_request_failures() {
+        let failure =
+            ProviderFailure::from(&api_error(StatusCode::BAD_REQUEST, "prompt is too long"));
+        assert_eq!(failure, ProviderFailure::Request);
+        assert!(!failure.is_model_level());
+    }
+}
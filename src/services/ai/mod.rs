@@ -1,3 +1,5 @@
 pub mod error;
 pub mod openrouter_service;
+pub mod pool_service;
+pub mod procedural_service;
 pub mod traits;
@@ -1,27 +1,35 @@
-use std::io::Cursor;
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
 
 use async_trait::async_trait;
 use base64::{Engine, engine::general_purpose};
-use image::{ImageFormat, load_from_memory};
 use openrouter_rs::{
     OpenRouterClient,
     api::{
         chat::{ChatCompletionRequest, Message},
-        images::ImageGenerationRequest,
+        images::{ImageGenerationRequest, ImageInputReference, ImageProviderOptions},
     },
     types::Role,
 };
 
-use super::traits::{ImageGenerator, TextGenerator};
-use crate::errors::Error;
+use super::traits::{CallUsage, ImageGenerator, TextGenerator};
+use crate::{
+    common::{generation_options::GenerationOptions, webp::to_webp},
+    errors::Error,
+};
 
-const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api/v1";
+pub(crate) const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api/v1";
 
 /// `OpenRouterService` generates text and images through the single Provider
-/// (OpenRouter), bound to one Model drawn from a Model Pool.
+/// (OpenRouter), bound to one Model drawn from a Model Pool. It keeps the
+/// usage reported for each call until it is taken.
 pub struct OpenRouterService {
     client: OpenRouterClient,
     model: String,
+    image_input: bool,
+    usage: Mutex<Vec<CallUsage>>,
 }
 
 impl OpenRouterService {
@@ -32,7 +40,7 @@ impl OpenRouterService {
         Self::with_base_url(api_key, model, OPENROUTER_BASE_URL)
     }
 
-    fn with_base_url(api_key: &str, model: &str, base_url: &str) -> Result<Self, Error> {
+    pub(crate) fn with_base_url(api_key: &str, model: &str, base_url: &str) -> Result<Self, Error> {
         let client = OpenRouterClient::builder()
             .api_key(api_key)
             .base_url(base_url)
@@ -41,23 +49,49 @@ impl OpenRouterService {
         Ok(Self {
             client,
             model: model.to_string(),
+            image_input: false,
+            usage: Mutex::new(Vec::new()),
         })
     }
 
+    /// `with_image_input` marks the Model as taking reference images.
+    #[must_use]
+    pub const fn with_image_input(mut self, image_input: bool) -> Self {
+        self.image_input = image_input;
+        self
+    }
+
     fn model_label(&self) -> String {
         format!("OpenRouter: {}", self.model)
     }
-}
 
-#[async_trait]
-impl TextGenerator for OpenRouterService {
-    async fn generate(&self, prompt: &str) -> Result<String, Error> {
+    fn record_usage(&self, mut usage: CallUsage, started: Instant, success: bool) {
+        usage.model.clone_from(&self.model);
+        usage.latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        usage.success = success;
+        self.usage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(usage);
+    }
+
+    fn drain_usage(&self) -> Vec<CallUsage> {
+        std::mem::take(&mut *self.usage.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    async fn chat(&self, prompt: &str, usage: &mut CallUsage) -> Result<String, Error> {
         let request = ChatCompletionRequest::builder()
             .model(&self.model)
             .messages(vec![Message::new(Role::User, prompt)])
             .build()?;
 
         let response = self.client.chat().create(&request).await?;
+        if let Some(reported) = &response.usage {
+            usage.prompt_tokens = Some(u64::from(reported.prompt_tokens));
+            usage.completion_tokens = Some(u64::from(reported.completion_tokens));
+            usage.total_tokens = Some(u64::from(reported.total_tokens));
+            usage.cost = reported.cost;
+        }
 
         let text = response
             .choices
@@ -70,21 +104,49 @@ impl TextGenerator for OpenRouterService {
         Ok(text.to_string())
     }
 
-    fn model_name(&self) -> String {
-        self.model_label()
-    }
-}
-
-#[async_trait]
-impl ImageGenerator for OpenRouterService {
-    async fn generate(&self, prompt: &str) -> Result<String, Error> {
-        let request = ImageGenerationRequest::builder()
+    async fn image(
+        &self,
+        prompt: &str,
+        references: &[String],
+        options: &GenerationOptions,
+        usage: &mut CallUsage,
+    ) -> Result<String, Error> {
+        let mut request = ImageGenerationRequest::builder();
+        request
             .model(&self.model)
             .prompt(prompt)
-            .aspect_ratio("1:1")
-            .build()?;
+            .aspect_ratio(options.aspect_ratio());
+        if let Some(size) = &options.size {
+            request.size(size);
+        }
+        if let Some(seed) = options.seed {
+            request.seed(seed);
+        }
+        // there is no common field for it, providers that support one read
+        // it from the passthrough options
+        if let Some(negative_prompt) = &options.negative_prompt {
+            request.provider(ImageProviderOptions::new(
+                [(
+                    "negative_prompt".to_string(),
+                    serde_json::Value::from(negative_prompt.as_str()),
+                )]
+                .into(),
+            ));
+        }
+        if self.image_input && !references.is_empty() {
+            request.input_references(references.iter().map(|image| {
+                ImageInputReference::image_url(format!("data:image/webp;base64,{image}"))
+            }));
+        }
+        let request = request.build()?;
 
         let response = self.client.images().create(&request).await?;
+        if let Some(reported) = &response.usage {
+            usage.prompt_tokens = Some(reported.prompt_tokens);
+            usage.completion_tokens = Some(reported.completion_tokens);
+            usage.total_tokens = Some(reported.total_tokens);
+            usage.cost = reported.cost;
+        }
 
         let b64_json = response
             .data
@@ -98,18 +160,57 @@ impl ImageGenerator for OpenRouterService {
         let webp_bytes = to_webp(&image_bytes)?;
         Ok(general_purpose::STANDARD.encode(&webp_bytes))
     }
+}
+
+#[async_trait]
+impl TextGenerator for OpenRouterService {
+    async fn generate(&self, prompt: &str) -> Result<String, Error> {
+        let started = Instant::now();
+        let mut usage = CallUsage::default();
+        let result = self.chat(prompt, &mut usage).await;
+        self.record_usage(usage, started, result.is_ok());
+        result
+    }
 
     fn model_name(&self) -> String {
         self.model_label()
     }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        self.drain_usage()
+    }
 }
 
-/// `to_webp` takes in a slice of bytes of an image and converts it to `.webp`
-fn to_webp(image_bytes: &[u8]) -> Result<Vec<u8>, Error> {
-    let img = load_from_memory(image_bytes)?;
-    let mut webp_buffer = Cursor::new(Vec::new());
-    img.write_to(&mut webp_buffer, ImageFormat::WebP)?;
-    Ok(webp_buffer.into_inner())
+#[async_trait]
+impl ImageGenerator for OpenRouterService {
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, Error> {
+        self.generate_with_references(prompt, &[], options).await
+    }
+
+    async fn generate_with_references(
+        &self,
+        prompt: &str,
+        references: &[String],
+        options: &GenerationOptions,
+    ) -> Result<String, Error> {
+        let started = Instant::now();
+        let mut usage = CallUsage::default();
+        let result = self.image(prompt, references, options, &mut usage).await;
+        self.record_usage(usage, started, result.is_ok());
+        result
+    }
+
+    fn accepts_references(&self) -> bool {
+        self.image_input
+    }
+
+    fn model_name(&self) -> String {
+        self.model_label()
+    }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        self.drain_usage()
+    }
 }
 
 #[cfg(test)]
@@ -136,7 +237,9 @@ mod tests {
         Mock::given(method("POST"))
             .and(path("/chat/completions"))
             .and(header("authorization", "Bearer sk-or-test-key"))
-            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("a gallery of rust")))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(chat_response("a gallery of rust")),
+            )
             .mount(&server)
             .await;
 
@@ -194,7 +297,7 @@ mod tests {
 
     fn one_pixel_png_b64() -> String {
         use base64::{Engine, engine::general_purpose};
-        let mut buffer = Cursor::new(Vec::new());
+        let mut buffer = std::io::Cursor::new(Vec::new());
         image::RgbImage::from_pixel(1, 1, image::Rgb([255, 0, 0]))
             .write_to(&mut buffer, image::ImageFormat::Png)
             .unwrap();
@@ -216,9 +319,10 @@ mod tests {
 
         let svc = OpenRouterService::with_base_url("sk-or-test-key", "image/model", &server.uri())
             .unwrap();
-        let result = ImageGenerator::generate(&svc, "a rusty canvas")
-            .await
-            .unwrap();
+        let result =
+            ImageGenerator::generate(&svc, "a rusty canvas", &GenerationOptions::default())
+                .await
+                .unwrap();
 
         use base64::{Engine, engine::general_purpose};
         let bytes = general_purpose::STANDARD.decode(&result).unwrap();
@@ -233,6 +337,97 @@ mod tests {
         assert!(body.contains("a rusty canvas"), "{body}");
     }
 
+    #[tokio::test]
+    async fn image_options_are_sent_with_the_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/images"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 1753710000,
+                "data": [{"b64_json": one_pixel_png_b64(), "media_type": "image/png"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let svc = OpenRouterService::with_base_url("sk-or-test-key", "image/model", &server.uri())
+            .unwrap();
+        ImageGenerator::generate(&svc, "a square", &GenerationOptions::default())
+            .await
+            .unwrap();
+        let options = GenerationOptions {
+            aspect_ratio: Some("3:4".to_string()),
+            size: Some("768x1024".to_string()),
+            seed: Some(42),
+            negative_prompt: Some("text, watermark".to_string()),
+        };
+        ImageGenerator::generate(&svc, "a portrait", &options)
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let square: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(square["aspect_ratio"], "1:1");
+        assert!(square.get("seed").is_none(), "{square}");
+        assert!(square.get("provider").is_none(), "{square}");
+
+        let portrait: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(portrait["aspect_ratio"], "3:4");
+        assert_eq!(portrait["size"], "768x1024");
+        assert_eq!(portrait["seed"], 42);
+        assert_eq!(
+            portrait["provider"]["options"]["negative_prompt"],
+            "text, watermark"
+        );
+    }
+
+    #[tokio::test]
+    async fn image_references_are_sent_only_to_models_with_image_input() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/images"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "created": 1753710000,
+                "data": [{"b64_json": one_pixel_png_b64(), "media_type": "image/png"}]
+            })))
+            .mount(&server)
+            .await;
+
+        let references = vec!["UklGRmFrZQ==".to_string()];
+        let blind =
+            OpenRouterService::with_base_url("sk-or-test-key", "image/model", &server.uri())
+                .unwrap();
+        ImageGenerator::generate_with_references(
+            &blind,
+            "a mix",
+            &references,
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap();
+        let multimodal =
+            OpenRouterService::with_base_url("sk-or-test-key", "image/model", &server.uri())
+                .unwrap()
+                .with_image_input(true);
+        ImageGenerator::generate_with_references(
+            &multimodal,
+            "a mix",
+            &references,
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let blind_body = String::from_utf8(requests[0].body.clone()).unwrap();
+        assert!(!blind_body.contains("input_references"), "{blind_body}");
+        let body = String::from_utf8(requests[1].body.clone()).unwrap();
+        assert!(body.contains("input_references"), "{body}");
+        assert!(
+            body.contains("data:image/webp;base64,UklGRmFrZQ=="),
+            "{body}"
+        );
+    }
+
     #[tokio::test]
     async fn image_generate_fails_when_response_has_no_image() {
         let server = MockServer::start().await;
@@ -247,7 +442,70 @@ mod tests {
 
         let svc = OpenRouterService::with_base_url("sk-or-test-key", "image/model", &server.uri())
             .unwrap();
-        let err = ImageGenerator::generate(&svc, "prompt").await.err().unwrap();
+        let err = ImageGenerator::generate(&svc, "prompt", &GenerationOptions::default())
+            .await
+            .err()
+            .unwrap();
         assert!(err.to_string().contains("image"), "{err}");
     }
+
+    #[tokio::test]
+    async fn text_generate_records_reported_usage() {
+        let server = MockServer::start().await;
+        let mut body = chat_response("a title");
+        body["usage"] = serde_json::json!({
+            "prompt_tokens": 12,
+            "completion_tokens": 3,
+            "total_tokens": 15,
+            "cost": 0.0042
+        });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let svc = OpenRouterService::with_base_url("sk-or-test-key", "text/model", &server.uri())
+            .unwrap();
+        TextGenerator::generate(&svc, "prompt").await.unwrap();
+
+        let usage = TextGenerator::take_usage(&svc);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].model, "text/model");
+        assert_eq!(usage[0].prompt_tokens, Some(12));
+        assert_eq!(usage[0].completion_tokens, Some(3));
+        assert_eq!(usage[0].total_tokens, Some(15));
+        assert_eq!(usage[0].cost, Some(0.0042));
+        assert!(usage[0].success);
+        assert!(
+            TextGenerator::take_usage(&svc).is_empty(),
+            "usage is drained"
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_calls_are_recorded_as_unsuccessful() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/images"))
+            .respond_with(ResponseTemplate::new(503).set_body_json(serde_json::json!({
+                "error": {"message": "Upstream unavailable", "code": 503}
+            })))
+            .mount(&server)
+            .await;
+
+        let svc = OpenRouterService::with_base_url("sk-or-test-key", "image/model", &server.uri())
+            .unwrap();
+        assert!(
+            ImageGenerator::generate(&svc, "prompt", &GenerationOptions::default())
+                .await
+                .is_err()
+        );
+
+        let usage = ImageGenerator::take_usage(&svc);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].model, "image/model");
+        assert!(!usage[0].success);
+        assert_eq!(usage[0].cost, None);
+    }
 }
@@ -0,0 +1,565 @@
+use std::{
+    future::Future,
+    sync::{Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use super::{
+    error::ProviderFailure,
+    openrouter_service::{OPENROUTER_BASE_URL, OpenRouterService},
+    procedural_service::{ProceduralService, is_procedural},
+    traits::{CallUsage, ImageGenerator, TextGenerator},
+};
+use crate::{
+    common::{generation_options::GenerationOptions, settings::ModelEntry},
+    errors::Error,
+};
+
+/// `PoolService` generates with a Model drawn from a Model Pool, weighted by
+/// each entry's `weight` and skipping disabled entries. When the
+/// drawn Model fails for a model-level reason (missing, rate limited, down
+/// upstream), it redraws another member of the same pool, up to
+/// `max_attempts` Models per generation. The `local/procedural` entry is
+/// served offline by [`ProceduralService`] instead of OpenRouter.
+pub struct PoolService {
+    api_key: String,
+    base_url: String,
+    pool: Vec<ModelEntry>,
+    max_attempts: usize,
+    current: Mutex<String>,
+    usage: Mutex<Vec<CallUsage>>,
+}
+
+/// `Backend` is what serves a single attempt on the drawn Model.
+enum Backend {
+    OpenRouter(OpenRouterService),
+    Procedural(ProceduralService),
+}
+
+#[async_trait]
+impl TextGenerator for Backend {
+    async fn generate(&self, prompt: &str) -> Result<String, Error> {
+        match self {
+            Self::OpenRouter(service) => TextGenerator::generate(service, prompt).await,
+            Self::Procedural(service) => TextGenerator::generate(service, prompt).await,
+        }
+    }
+
+    fn model_name(&self) -> String {
+        match self {
+            Self::OpenRouter(service) => TextGenerator::model_name(service),
+            Self::Procedural(service) => TextGenerator::model_name(service),
+        }
+    }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        match self {
+            Self::OpenRouter(service) => TextGenerator::take_usage(service),
+            Self::Procedural(service) => TextGenerator::take_usage(service),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageGenerator for Backend {
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, Error> {
+        self.generate_with_references(prompt, &[], options).await
+    }
+
+    async fn generate_with_references(
+        &self,
+        prompt: &str,
+        references: &[String],
+        options: &GenerationOptions,
+    ) -> Result<String, Error> {
+        match self {
+            Self::OpenRouter(service) => {
+                service
+                    .generate_with_references(prompt, references, options)
+                    .await
+            }
+            Self::Procedural(service) => {
+                service
+                    .generate_with_references(prompt, references, options)
+                    .await
+            }
+        }
+    }
+
+    fn accepts_references(&self) -> bool {
+        match self {
+            Self::OpenRouter(service) => service.accepts_references(),
+            Self::Procedural(service) => service.accepts_references(),
+        }
+    }
+
+    fn model_name(&self) -> String {
+        match self {
+            Self::OpenRouter(service) => ImageGenerator::model_name(service),
+            Self::Procedural(service) => ImageGenerator::model_name(service),
+        }
+    }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        match self {
+            Self::OpenRouter(service) => ImageGenerator::take_usage(service),
+            Self::Procedural(service) => ImageGenerator::take_usage(service),
+        }
+    }
+}
+
+fn draw<'a>(pool: &'a [ModelEntry], tried: &[String]) -> Option<&'a str> {
+    let candidates = pool
+        .iter()
+        .filter(|entry| entry.is_drawable() && !tried.contains(&entry.id))
+        .collect::<Vec<_>>();
+    let total = candidates
+        .iter()
+        .map(|entry| u64::from(entry.weight))
+        .sum::<u64>();
+    if total == 0 {
+        return None;
+    }
+
+    let mut ticket = fastrand::u64(..total);
+    for entry in candidates {
+        let weight = u64::from(entry.weight);
+        if ticket < weight {
+            return Some(entry.id.as_str());
+        }
+        ticket -= weight;
+    }
+    None
+}
+
+/// Turns the error of setting up a Model's client into a model-level failure,
+/// so the next member of the pool is drawn.
+fn unavailable(err: Error) -> Error {
+    match err {
+        Error::AIError(message) | Error::ProviderError { message, .. } => Error::ProviderError {
+            failure: ProviderFailure::ModelUnavailable,
+            message,
+        },
+    }
+}
+
+impl PoolService {
+    /// `new` draws the first Model from `pool`, or returns `None` when the
+    /// pool has no enabled entry.
+    #[must_use]
+    pub fn new(api_key: &str, pool: &[ModelEntry], max_attempts: usize) -> Option<Self> {
+        let first = draw(pool, &[])?;
+        Some(Self::with_base_url(
+            api_key,
+            pool,
+            first,
+            max_attempts,
+            OPENROUTER_BASE_URL,
+        ))
+    }
+
+    fn with_base_url(
+        api_key: &str,
+        pool: &[ModelEntry],
+        first: &str,
+        max_attempts: usize,
+        base_url: &str,
+    ) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            base_url: base_url.to_string(),
+            pool: pool.to_vec(),
+            max_attempts: max_attempts.max(1),
+            current: Mutex::new(first.to_string()),
+            usage: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn current_model(&self) -> String {
+        self.current.lock().map_or_else(
+            |poisoned| poisoned.into_inner().clone(),
+            |model| model.clone(),
+        )
+    }
+
+    fn set_current_model(&self, model: &str) {
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        *current = model.to_string();
+    }
+
+    /// Runs `call` against the current Model, redrawing on model-level
+    /// failures. A Model whose client can't be set up counts as unavailable.
+    /// The Model that succeeds stays current, so it is the one reported by
+    /// `model_name` and used first by the next generation. The usage of every
+    /// attempt, failed or not, is kept for `take_usage`.
+    async fn generate_with<F, Fut>(&self, call: F) -> Result<String, Error>
+    where
+        F: Fn(Backend) -> Fut + Sync,
+        Fut: Future<Output = (Result<String, Error>, Vec<CallUsage>)> + Send,
+    {
+        let mut tried: Vec<String> = Vec::new();
+        let mut model = self.current_model();
+
+        loop {
+            let (result, usage) = match self.backend(&model) {
+                Ok(backend) => call(backend).await,
+                Err(err) => (
+                    Err(unavailable(err)),
+                    vec![CallUsage {
+                        model: model.clone(),
+                        ..CallUsage::default()
+                    }],
+                ),
+            };
+            self.usage
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .extend(usage);
+
+            match result {
+                Ok(output) => {
+                    self.set_current_model(&model);
+                    return Ok(output);
+                }
+                Err(err) => {
+                    let failure = err.provider_failure();
+                    warn!(model, ?failure, error = %err, "generation failed on model");
+                    tried.push(model);
+
+                    if !failure.is_some_and(|failure| failure.is_model_level())
+                        || tried.len() >= self.max_attempts
+                    {
+                        return Err(err);
+                    }
+                    match draw(&self.pool, &tried) {
+                        Some(next) => model = next.to_string(),
+                        None => return Err(err),
+                    }
+                }
+            }
+        }
+    }
+
+    fn backend(&self, model: &str) -> Result<Backend, Error> {
+        if is_procedural(model) {
+            return Ok(Backend::Procedural(ProceduralService::new()));
+        }
+        OpenRouterService::with_base_url(&self.api_key, model, &self.base_url)
+            .map(|service| Backend::OpenRouter(service.with_image_input(self.image_input(model))))
+    }
+
+    fn image_input(&self, model: &str) -> bool {
+        is_procedural(model)
+            || self
+                .pool
+                .iter()
+                .any(|entry| entry.id == model && entry.image_input)
+    }
+
+    fn model_label(&self) -> String {
+        let model = self.current_model();
+        if is_procedural(&model) {
+            return TextGenerator::model_name(&ProceduralService::new());
+        }
+        format!("OpenRouter: {model}")
+    }
+
+    fn drain_usage(&self) -> Vec<CallUsage> {
+        std::mem::take(&mut *self.usage.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+#[async_trait]
+impl TextGenerator for PoolService {
+    async fn generate(&self, prompt: &str) -> Result<String, Error> {
+        self.generate_with(|service| async move {
+            let result = TextGenerator::generate(&service, prompt).await;
+            (result, TextGenerator::take_usage(&service))
+        })
+        .await
+    }
+
+    fn model_name(&self) -> String {
+        self.model_label()
+    }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        self.drain_usage()
+    }
+}
+
+#[async_trait]
+impl ImageGenerator for PoolService {
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, Error> {
+        self.generate_with_references(prompt, &[], options).await
+    }
+
+    /// The references go to every Model tried that takes image input; the
+    /// others render from the prompt alone.
+    async fn generate_with_references(
+        &self,
+        prompt: &str,
+        references: &[String],
+        options: &GenerationOptions,
+    ) -> Result<String, Error> {
+        self.generate_with(|service| async move {
+            let result = service
+                .generate_with_references(prompt, references, options)
+                .await;
+            (result, ImageGenerator::take_usage(&service))
+        })
+        .await
+    }
+
+    fn accepts_references(&self) -> bool {
+        self.image_input(&self.current_model())
+    }
+
+    fn model_name(&self) -> String {
+        self.model_label()
+    }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        self.drain_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai::procedural_service::PROCEDURAL_MODEL_ID;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_string_contains, method, path},
+    };
+
+    fn chat_response(content: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": "gen-1",
+            "created": 1753710000,
+            "model": "text/model",
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": content}}]
+        })
+    }
+
+    fn error_response(status: u16, message: &str) -> ResponseTemplate {
+        ResponseTemplate::new(status).set_body_json(serde_json::json!({
+            "error": {"message": message, "code": status}
+        }))
+    }
+
+    fn pool() -> Vec<ModelEntry> {
+        vec![ModelEntry::new("text/gone"), ModelEntry::new("text/alive")]
+    }
+
+    #[tokio::test]
+    async fn redraws_another_model_when_the_drawn_one_is_missing() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_string_contains("text/gone"))
+            .respond_with(error_response(404, "No endpoints found for text/gone"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_string_contains("text/alive"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("a title")))
+            .mount(&server)
+            .await;
+
+        let svc =
+            PoolService::with_base_url("sk-or-test-key", &pool(), "text/gone", 3, &server.uri());
+        let text = TextGenerator::generate(&svc, "prompt").await.unwrap();
+
+        assert_eq!(text, "a title");
+        assert_eq!(TextGenerator::model_name(&svc), "OpenRouter: text/alive");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        let usage = TextGenerator::take_usage(&svc);
+        let attempts = usage
+            .iter()
+            .map(|call| (call.model.as_str(), call.success))
+            .collect::<Vec<_>>();
+        assert_eq!(attempts, vec![("text/gone", false), ("text/alive", true)]);
+    }
+
+    #[tokio::test]
+    async fn sends_references_only_to_models_with_image_input() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/images"))
+            .and(body_string_contains("image/blind"))
+            .respond_with(error_response(404, "No endpoints found for image/blind"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/images"))
+            .and(body_string_contains("image/multimodal"))
+            .respond_with(error_response(503, "Upstream unavailable"))
+            .mount(&server)
+            .await;
+
+        let pool = vec![
+            ModelEntry::new("image/blind"),
+            ModelEntry {
+                image_input: true,
+                ..ModelEntry::new("image/multimodal")
+            },
+        ];
+        let svc =
+            PoolService::with_base_url("sk-or-test-key", &pool, "image/blind", 2, &server.uri());
+        assert!(!svc.accepts_references());
+        let references = vec!["UklGRmFrZQ==".to_string()];
+        assert!(
+            svc.generate_with_references("a mix", &references, &GenerationOptions::default())
+                .await
+                .is_err()
+        );
+
+        let requests = server.received_requests().await.unwrap();
+        let bodies = requests
+            .iter()
+            .map(|request| String::from_utf8(request.body.clone()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies.len(), 2);
+        assert!(!bodies[0].contains("input_references"), "{}", bodies[0]);
+        assert!(bodies[1].contains("input_references"), "{}", bodies[1]);
+    }
+
+    #[tokio::test]
+    async fn redraws_on_transient_upstream_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_string_contains("text/gone"))
+            .respond_with(error_response(429, "Rate limit exceeded"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_string_contains("text/alive"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("a title")))
+            .mount(&server)
+            .await;
+
+        let svc =
+            PoolService::with_base_url("sk-or-test-key", &pool(), "text/gone", 3, &server.uri());
+        TextGenerator::generate(&svc, "prompt").await.unwrap();
+
+        assert_eq!(TextGenerator::model_name(&svc), "OpenRouter: text/alive");
+    }
+
+    #[tokio::test]
+    async fn does_not_redraw_on_account_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(error_response(402, "Insufficient credits"))
+            .mount(&server)
+            .await;
+
+        let svc =
+            PoolService::with_base_url("sk-or-test-key", &pool(), "text/gone", 3, &server.uri());
+        let err = TextGenerator::generate(&svc, "prompt").await.err().unwrap();
+
+        assert_eq!(
+            err.provider_failure(),
+            Some(crate::services::ai::error::ProviderFailure::Account)
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_attempts() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(error_response(503, "Upstream unavailable"))
+            .mount(&server)
+            .await;
+
+        let models = vec![
+            ModelEntry::new("text/a"),
+            ModelEntry::new("text/b"),
+            ModelEntry::new("text/c"),
+        ];
+        let svc = PoolService::with_base_url("sk-or-test-key", &models, "text/a", 2, &server.uri());
+        assert!(TextGenerator::generate(&svc, "prompt").await.is_err());
+
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        assert_eq!(TextGenerator::model_name(&svc), "OpenRouter: text/a");
+    }
+
+    #[tokio::test]
+    async fn serves_the_procedural_model_offline() {
+        let pool = vec![ModelEntry::new(PROCEDURAL_MODEL_ID)];
+        let svc = PoolService::new("", &pool, 3).unwrap();
+
+        let image = ImageGenerator::generate(&svc, "prompt", &GenerationOptions::default())
+            .await
+            .unwrap();
+        assert!(!image.is_empty());
+        assert_eq!(
+            ImageGenerator::model_name(&svc),
+            format!("Procedural: {PROCEDURAL_MODEL_ID}")
+        );
+        let usage = ImageGenerator::take_usage(&svc);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].model, PROCEDURAL_MODEL_ID);
+    }
+
+    #[test]
+    fn draw_skips_tried_models() {
+        let pool = pool();
+        assert_eq!(draw(&pool, &["text/gone".to_string()]), Some("text/alive"));
+        assert_eq!(
+            draw(&pool, &["text/gone".to_string(), "text/alive".to_string()]),
+            None
+        );
+        assert_eq!(draw(&[], &[]), None);
+    }
+
+    #[test]
+    fn draw_skips_disabled_and_zero_weight_entries() {
+        let pool = vec![
+            ModelEntry {
+                enabled: false,
+                ..ModelEntry::new("text/off")
+            },
+            ModelEntry {
+                weight: 0,
+                ..ModelEntry::new("text/zero")
+            },
+            ModelEntry::new("text/on"),
+        ];
+        for _ in 0..50 {
+            assert_eq!(draw(&pool, &[]), Some("text/on"));
+        }
+        assert_eq!(draw(&pool[..2], &[]), None);
+    }
+
+    #[test]
+    fn draw_follows_weights() {
+        let pool = vec![
+            ModelEntry {
+                weight: 1,
+                ..ModelEntry::new("text/rare")
+            },
+            ModelEntry {
+                weight: 9,
+                ..ModelEntry::new("text/common")
+            },
+        ];
+        let rare = (0..2000)
+            .filter(|_| draw(&pool, &[]) == Some("text/rare"))
+            .count();
+        assert!(
+            (100..320).contains(&rare),
+            "drew the 10% model {rare} times"
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    io::Cursor,
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose};
+use image::{ImageFormat, Rgb, RgbImage, imageops::FilterType, load_from_memory};
+use sha2::{Digest, Sha256};
+
+use super::traits::{CallUsage, ImageGenerator, TextGenerator};
+use crate::{
+    common::generation_options::GenerationOptions, errors::Error, services::titles::REJECTED_TITLE,
+};
+
+/// The model ID that selects the procedural backend in a Model Pool.
+pub const PROCEDURAL_MODEL_ID: &str = "local/procedural";
+
+/// The longer side of a render, unless a size is asked for.
+const IMAGE_SIZE: u32 = 512;
+/// The longest side rendered even when a larger size is asked for.
+const MAX_IMAGE_SIZE: u32 = 2048;
+
+const MEDIUMS: &[&str] = &[
+    "Watercolor study",
+    "Impressionistic oil sketch",
+    "Ink and wash drawing",
+    "Long-exposure photograph",
+    "Risograph print",
+    "Digital collage",
+    "Charcoal drawing",
+    "Gouache painting",
+];
+
+const SUBJECTS: &[&str] = &[
+    "a quiet harbor at dawn",
+    "a crowded night market",
+    "an abandoned greenhouse",
+    "a mountain pass in the snow",
+    "a rooftop garden above the city",
+    "a tide pool full of small creatures",
+    "a library lit by a single lamp",
+    "a field of wind turbines",
+];
+
+const MOODS: &[&str] = &[
+    "calm and contemplative",
+    "restless and electric",
+    "warm and nostalgic",
+    "crisp and austere",
+    "playful and bright",
+    "hushed and mysterious",
+];
+
+const PALETTES: &[&str] = &[
+    "slate blue and amber",
+    "moss green and rust",
+    "ivory and charcoal",
+    "coral and teal",
+    "violet and ochre",
+    "sand and cobalt",
+];
+
+const TITLE_ADJECTIVES: &[&str] = &[
+    "Quiet", "Amber", "Hollow", "Drifting", "Electric", "Velvet", "Patient", "Silver",
+];
+
+const TITLE_NOUNS: &[&str] = &[
+    "Harbor", "Echoes", "Meridian", "Lanterns", "Tides", "Orchard", "Static", "Reverie",
+];
+
+/// `is_procedural` tells whether a pool entry selects the procedural backend
+/// rather than an OpenRouter Model.
+#[must_use]
+pub fn is_procedural(model: &str) -> bool {
+    model == PROCEDURAL_MODEL_ID
+}
+
+/// `ProceduralService` generates offline and deterministically: the same
+/// prompt always gives the same image or text. Images are gradients with
+/// noise, texts are assembled from fixed word lists, both seeded from a hash
+/// of the prompt. It needs no API key and costs nothing, so development and
+/// tests can run the whole gallery without network access.
+#[derive(Default)]
+pub struct ProceduralService {
+    usage: Mutex<Vec<CallUsage>>,
+}
+
+/// `seed` hashes the prompt, and the requested seed when there is one, so
+/// the same prompt renders differently per seed but always the same way.
+fn seed(prompt: &str, requested: Option<i64>) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(prompt.as_bytes());
+    if let Some(requested) = requested {
+        hasher.update(requested.to_le_bytes());
+    }
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn pick<'a>(rng: &mut fastrand::Rng, words: &[&'a str]) -> &'a str {
+    words[rng.usize(..words.len())]
+}
+
+fn random_color(rng: &mut fastrand::Rng) -> [f32; 3] {
+    [rng.f32(), rng.f32(), rng.f32()].map(|channel| channel.mul_add(200.0, 30.0))
+}
+
+/// `render_prompt` writes an image prompt from the word lists.
+fn render_prompt(rng: &mut fastrand::Rng) -> String {
+    format!(
+        "{} of {}, {}, in a palette of {}.",
+        pick(rng, MEDIUMS),
+        pick(rng, SUBJECTS),
+        pick(rng, MOODS),
+        pick(rng, PALETTES),
+    )
+}
+
+/// `render_title` writes a two word title from the word lists. The lists
+/// only make a few dozen titles, so a title asked for again after a rejected
+/// one is numbered to tell it apart from the titles already taken.
+fn render_title(rng: &mut fastrand::Rng, numbered: bool) -> String {
+    let title = format!("{} {}", pick(rng, TITLE_ADJECTIVES), pick(rng, TITLE_NOUNS));
+    if numbered {
+        format!("{title} No. {}", rng.u32(2..10_000))
+    } else {
+        title
+    }
+}
+
+/// `average_color` is the mean color of a Base64 encoded image.
+fn average_color(image: &str) -> Result<[f32; 3], Error> {
+    let bytes = general_purpose::STANDARD.decode(image)?;
+    let pixel = load_from_memory(&bytes)?
+        .resize_exact(1, 1, FilterType::Triangle)
+        .to_rgb8();
+    Ok(pixel.get_pixel(0, 0).0.map(f32::from))
+}
+
+/// `render_image` paints a diagonal gradient between two colors, with grain,
+/// at the requested size or aspect ratio, and returns it as WebP bytes. With
+/// references, the gradient runs between the mean colors of the first and
+/// last of them that decode.
+fn render_image(
+    rng: &mut fastrand::Rng,
+    references: &[String],
+    options: &GenerationOptions,
+) -> Result<Vec<u8>, Error> {
+    let colors = references
+        .iter()
+        .filter_map(|image| average_color(image).ok())
+        .collect::<Vec<_>>();
+    let (from, to) = match (colors.first(), colors.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => (random_color(rng), random_color(rng)),
+    };
+    let angle = rng.f32() * std::f32::consts::TAU;
+    let (dx, dy) = (angle.cos(), angle.sin());
+    let grain = rng.f32().mul_add(24.0, 8.0);
+    let (width, height) = options.dimensions(IMAGE_SIZE);
+    let scale = MAX_IMAGE_SIZE as f32 / width.max(height).max(MAX_IMAGE_SIZE) as f32;
+    let (width, height) = (
+        ((width as f32 * scale) as u32).max(1),
+        ((height as f32 * scale) as u32).max(1),
+    );
+    let (w, h) = (width as f32, height as f32);
+
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        let (u, v) = (x as f32 / w - 0.5, y as f32 / h - 0.5);
+        let t = (u.mul_add(dx, v * dy) + 0.5).clamp(0.0, 1.0);
+        let noise = (rng.f32() - 0.5) * grain;
+        Rgb(std::array::from_fn(|channel| {
+            let value = (to[channel] - from[channel]).mul_add(t, from[channel]) + noise;
+            value.clamp(0.0, 255.0) as u8
+        }))
+    });
+
+    let mut buffer = Cursor::new(Vec::new());
+    image.write_to(&mut buffer, ImageFormat::WebP)?;
+    Ok(buffer.into_inner())
+}
+
+impl ProceduralService {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn model_label() -> String {
+        format!("Procedural: {PROCEDURAL_MODEL_ID}")
+    }
+
+    fn record_usage(&self, started: Instant, success: bool) {
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.usage
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(CallUsage {
+                model: PROCEDURAL_MODEL_ID.to_string(),
+                cost: Some(0.0),
+                latency_ms,
+                success,
+                ..Default::default()
+            });
+    }
+
+    fn drain_usage(&self) -> Vec<CallUsage> {
+        std::mem::take(&mut *self.usage.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+#[async_trait]
+impl TextGenerator for ProceduralService {
+    /// Requests for an image prompt (the meta-prompts mention an image
+    /// generator) get a prompt; anything else is answered with a title,
+    /// numbered when a previous one was rejected.
+    async fn generate(&self, prompt: &str) -> Result<String, Error> {
+        let started = Instant::now();
+        let mut rng = fastrand::Rng::with_seed(seed(prompt, None));
+        let text = if prompt.contains("image generator") {
+            render_prompt(&mut rng)
+        } else {
+            render_title(&mut rng, prompt.contains(REJECTED_TITLE))
+        };
+        self.record_usage(started, true);
+        Ok(text)
+    }
+
+    fn model_name(&self) -> String {
+        Self::model_label()
+    }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        self.drain_usage()
+    }
+}
+
+#[async_trait]
+impl ImageGenerator for ProceduralService {
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, Error> {
+        self.generate_with_references(prompt, &[], options).await
+    }
+
+    /// The negative prompt has nothing to steer here and is ignored.
+    async fn generate_with_references(
+        &self,
+        prompt: &str,
+        references: &[String],
+        options: &GenerationOptions,
+    ) -> Result<String, Error> {
+        let started = Instant::now();
+        let mut rng = fastrand::Rng::with_seed(seed(prompt, options.seed));
+        let result = render_image(&mut rng, references, options);
+        self.record_usage(started, result.is_ok());
+        Ok(general_purpose::STANDARD.encode(result?))
+    }
+
+    fn accepts_references(&self) -> bool {
+        true
+    }
+
+    fn model_name(&self) -> String {
+        Self::model_label()
+    }
+
+    fn take_usage(&self) -> Vec<CallUsage> {
+        self.drain_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::art_prompts::{IMAGE_PROMPT, TITLE_PROMPT};
+
+    #[tokio::test]
+    async fn images_are_deterministic_webp() {
+        let svc = ProceduralService::new();
+        let first = ImageGenerator::generate(&svc, "a quiet harbor", &GenerationOptions::default())
+            .await
+            .unwrap();
+        let again = ImageGenerator::generate(&svc, "a quiet harbor", &GenerationOptions::default())
+            .await
+            .unwrap();
+        let other = ImageGenerator::generate(&svc, "a night market", &GenerationOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+
+        let bytes = general_purpose::STANDARD.decode(&first).unwrap();
+        assert_eq!(
+            image::guess_format(&bytes).unwrap(),
+            image::ImageFormat::WebP
+        );
+        let decoded = image::load_from_memory(&bytes).unwrap();
+        assert_eq!(
+            (decoded.width(), decoded.height()),
+            (IMAGE_SIZE, IMAGE_SIZE)
+        );
+    }
+
+    #[tokio::test]
+    async fn options_shape_and_seed_the_render() {
+        let svc = ProceduralService::new();
+        let portrait = GenerationOptions {
+            aspect_ratio: Some("3:4".to_string()),
+            ..Default::default()
+        };
+        let image = ImageGenerator::generate(&svc, "a harbor", &portrait)
+            .await
+            .unwrap();
+        let decoded =
+            image::load_from_memory(&general_purpose::STANDARD.decode(&image).unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (384, 512));
+
+        let seeded = |seed| GenerationOptions {
+            seed: Some(seed),
+            ..Default::default()
+        };
+        let first = ImageGenerator::generate(&svc, "a harbor", &seeded(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            first,
+            ImageGenerator::generate(&svc, "a harbor", &seeded(1))
+                .await
+                .unwrap()
+        );
+        assert_ne!(
+            first,
+            ImageGenerator::generate(&svc, "a harbor", &seeded(2))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn references_color_the_gradient() {
+        let svc = ProceduralService::new();
+        let red = ImageGenerator::generate(&svc, "red", &GenerationOptions::default())
+            .await
+            .unwrap();
+        let mut buffer = Cursor::new(Vec::new());
+        RgbImage::from_pixel(8, 8, Rgb([200, 20, 20]))
+            .write_to(&mut buffer, ImageFormat::WebP)
+            .unwrap();
+        let reference = general_purpose::STANDARD.encode(buffer.into_inner());
+
+        let mixed = ImageGenerator::generate_with_references(
+            &svc,
+            "red",
+            &[reference],
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_ne!(mixed, red);
+
+        let [r, g, b] = average_color(&mixed).unwrap().map(|channel| channel as i32);
+        assert!(r > 150 && g < 80 && b < 80, "mean color {r},{g},{b}");
+    }
+
+    #[tokio::test]
+    async fn answers_prompt_and_title_requests() {
+        let svc = ProceduralService::new();
+        let prompt = TextGenerator::generate(&svc, IMAGE_PROMPT).await.unwrap();
+        assert!(prompt.contains(" of "), "{prompt}");
+        assert_eq!(
+            prompt,
+            TextGenerator::generate(&svc, IMAGE_PROMPT).await.unwrap()
+        );
+
+        let title = TextGenerator::generate(&svc, TITLE_PROMPT).await.unwrap();
+        assert_eq!(title.split_whitespace().count(), 2, "{title}");
+        assert!(title.len() <= 27, "{title}");
+
+        let reasked = format!(
+            "{TITLE_PROMPT}\n\n{REJECTED_TITLE} \"{title}\", which can't be used: another art already has that title."
+        );
+        let numbered = TextGenerator::generate(&svc, &reasked).await.unwrap();
+        assert!(numbered.contains(" No. "), "{numbered}");
+        assert!(numbered.len() <= 27, "{numbered}");
+    }
+
+    #[tokio::test]
+    async fn records_free_successful_calls() {
+        let svc = ProceduralService::new();
+        TextGenerator::generate(&svc, TITLE_PROMPT).await.unwrap();
+        ImageGenerator::generate(&svc, "prompt", &GenerationOptions::default())
+            .await
+            .unwrap();
+
+        let usage = TextGenerator::take_usage(&svc);
+        assert_eq!(usage.len(), 2);
+        assert!(
+            usage
+                .iter()
+                .all(|call| call.success && call.cost == Some(0.0))
+        );
+        assert_eq!(usage[0].model, PROCEDURAL_MODEL_ID);
+        assert!(TextGenerator::take_usage(&svc).is_empty());
+    }
+}
@@ -1,12 +1,44 @@
 use async_trait::async_trait;
 
-use crate::errors::Error;
+use crate::{common::generation_options::GenerationOptions, errors::Error};
+
+/// `CallUsage` is what a single call to the Provider cost, as reported in its
+/// response, with the measured latency. Failed calls are recorded too.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CallUsage {
+    pub model: String,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    pub total_tokens: Option<u64>,
+    pub cost: Option<f64>,
+    pub latency_ms: u64,
+    pub success: bool,
+}
 
 #[async_trait]
 pub trait ImageGenerator: Send {
-    /// generate takes a prompt and returns a Base64 encoding of the image in WebP format.
-    async fn generate(&self, prompt: &str) -> Result<String, Error>;
+    /// generate takes a prompt and the options shaping the render (aspect
+    /// ratio, size, seed, negative prompt) and returns a Base64 encoding of
+    /// the image in WebP format.
+    async fn generate(&self, prompt: &str, options: &GenerationOptions) -> Result<String, Error>;
+    /// generate_with_references is `generate` with reference images (Base64
+    /// WebP) the result should visually inherit from. A Model without image
+    /// input ignores them and renders from the prompt alone.
+    async fn generate_with_references(
+        &self,
+        prompt: &str,
+        references: &[String],
+        options: &GenerationOptions,
+    ) -> Result<String, Error>;
+    /// accepts_references tells whether the current Model takes reference images.
+    fn accepts_references(&self) -> bool {
+        false
+    }
     fn model_name(&self) -> String;
+    /// take_usage drains the usage of the calls made since it was last called.
+    fn take_usage(&self) -> Vec<CallUsage> {
+        Vec::new()
+    }
 }
 
 #[async_trait]
@@ -14,4 +46,8 @@ pub trait TextGenerator: Send {
     /// generate takes a prompt and returns a text response from AI.
     async fn generate(&self, prompt: &str) -> Result<String, Error>;
     fn model_name(&self) -> String;
+    /// take_usage drains the usage of the calls made since it was last called.
+    fn take_usage(&self) -> Vec<CallUsage> {
+        Vec::new()
+    }
 }
@@ -1,11 +1,32 @@
+use base64::{Engine as _, engine::general_purpose};
 use loco_rs::prelude::*;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, TransactionTrait};
+use tracing::warn;
 
 use crate::{
-    common::settings::Settings,
-    models::arts::{self, ArtParams},
-    services::{realtime, service_provider::ServiceProvider},
-    tasks::art_prompts::{IMAGE_PROMPT, SAMPLE_PROMPTS, SAMPLE_TITLES, TITLE_PROMPT},
+    common::{
+        directives::Directives,
+        generation_options::GenerationOptions,
+        image_hash,
+        settings::{DuplicateAction, Flow, ModelEntry, Settings},
+        webp,
+    },
+    models::art_candidates::{self, ArtChanges, CandidateParams},
+    models::art_revisions::{self, RevisionReason},
+    models::arts::{self, ArtParams, Provenance, PublicationStatus},
+    models::prompt_templates::TemplateKind,
+    models::style_presets,
+    services::{
+        ai::traits::ImageGenerator,
+        diversity::{self, DiversePrompt},
+        duplicates::{self, WorkKind},
+        generation_usage::UsageLog,
+        normalize,
+        prompt_templates::PromptTemplates,
+        realtime,
+        service_provider::ServiceProvider,
+        titles,
+    },
 };
 use uuid::Uuid;
 
@@ -18,110 +39,490 @@ fn settings(ctx: &AppContext) -> Result<Settings> {
     )
 }
 
-pub async fn create_art(ctx: &AppContext) -> Result<arts::Model> {
+/// `Rendered` is one image rendered for a prompt and the Model that drew it.
+struct Rendered {
+    image: String,
+    model: String,
+}
+
+/// `render_images` renders `prompt` with `img_gen` and, in candidates mode,
+/// `settings.candidates - 1` more times, each with a Model freshly drawn from
+/// `pool`. A failed candidate is skipped as long as another one
+/// renders.
+///
+/// # Errors
+///
+/// When every render fails.
+async fn render_images(
+    settings: &Settings,
+    pool: &[ModelEntry],
+    img_gen: Box<dyn ImageGenerator + Send>,
+    prompt: &str,
+    options: &GenerationOptions,
+    usage: &mut UsageLog,
+) -> Result<Vec<Rendered>> {
+    let mut rendered = Vec::new();
+    let mut last_error = None;
+    let mut img_gen = Some(img_gen);
+
+    for candidate in 1..=settings.candidates.max(1) {
+        let img_gen = match img_gen.take() {
+            Some(img_gen) => img_gen,
+            None => match ServiceProvider::pool_img_service(settings, pool) {
+                Ok(img_gen) => img_gen,
+                Err(e) => {
+                    last_error = Some(e.to_string());
+                    continue;
+                }
+            },
+        };
+        let image = img_gen.generate(prompt, options).await;
+        usage.collect("image", img_gen.take_usage());
+        match image {
+            Ok(image) => rendered.push(Rendered {
+                image,
+                model: img_gen.model_name(),
+            }),
+            Err(e) => {
+                warn!(candidate, error = %e, "candidate render failed");
+                last_error = Some(e.to_string());
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if rendered.is_empty() => {
+            Err(Error::Message(format!("Unable to generate image: {e}")))
+        }
+        _ => Ok(rendered),
+    }
+}
+
+/// `render_distinct_images` is [`render_images`] checked against the
+/// perceptual hashes of the saved arts and mixes, leaving out the art
+/// `except` that is being re-rendered. Near-duplicates are logged; in the
+/// `reject` duplicate mode they are dropped, and when every render was one
+/// the prompt is rendered again with a freshly drawn Model, up to
+/// `settings.duplicates.attempts` times.
+///
+/// # Errors
+///
+/// When every render fails, or every render of every attempt was rejected as
+/// a near-duplicate.
+#[allow(clippy::too_many_arguments)]
+async fn render_distinct_images(
+    db: &DatabaseConnection,
+    settings: &Settings,
+    pool: &[ModelEntry],
+    img_gen: Box<dyn ImageGenerator + Send>,
+    prompt: &str,
+    options: &GenerationOptions,
+    usage: &mut UsageLog,
+    except: Option<i32>,
+) -> Result<Vec<Rendered>> {
+    let works = duplicates::load(db).await?;
+    let reject = settings.duplicates.action == DuplicateAction::Reject;
+    let attempts = if reject {
+        settings.duplicates.attempts.max(1)
+    } else {
+        1
+    };
+    let mut img_gen = Some(img_gen);
+    let mut last_match = None;
+
+    for attempt in 1..=attempts {
+        let img_gen = match img_gen.take() {
+            Some(img_gen) => img_gen,
+            None => ServiceProvider::pool_img_service(settings, pool)
+                .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?,
+        };
+        let rendered = render_images(settings, pool, img_gen, prompt, options, usage).await?;
+
+        let mut distinct = Vec::new();
+        for rendered in rendered {
+            let found = image_hash::dhash_base64(&rendered.image).and_then(|hash| {
+                duplicates::nearest(
+                    hash,
+                    &works,
+                    settings.duplicates.threshold,
+                    except.map(|id| (WorkKind::Art, id)),
+                )
+            });
+            match found {
+                Some(found) => {
+                    warn!(
+                        attempt,
+                        model = rendered.model,
+                        duplicate_of = %found,
+                        "render is a near-duplicate of an existing work"
+                    );
+                    if !reject {
+                        distinct.push(rendered);
+                    }
+                    last_match = Some(found);
+                }
+                None => distinct.push(rendered),
+            }
+        }
+        if !distinct.is_empty() {
+            return Ok(distinct);
+        }
+    }
+
+    Err(duplicates::all_rejected(last_match.as_ref(), attempts))
+}
+
+/// `save_candidates` keeps every render as a candidate of the art, each
+/// carrying the `changes` it brings along when an editor picks it. The art
+/// itself is left as it is until then.
+async fn save_candidates(
+    ctx: &AppContext,
+    art_id: i32,
+    rendered: Vec<Rendered>,
+    changes: Option<&ArtChanges>,
+) -> Result<()> {
+    let candidates = rendered
+        .into_iter()
+        .map(|rendered| CandidateParams {
+            image: rendered.image,
+            model: Some(rendered.model),
+            changes: changes.cloned(),
+        })
+        .collect::<Vec<_>>();
+    art_candidates::Model::replace_for_art(&ctx.db, art_id, &candidates).await?;
+    Ok(())
+}
+
+/// `CreateArtParams` steers a new art: render `options` over the configured
+/// image options, the theme and style of `directives`, an image `model` of
+/// the create pool to use instead of a random one, and a `prompt` to render
+/// instead of a generated one.
+#[derive(Clone, Debug, Default)]
+pub struct CreateArtParams {
+    pub options: GenerationOptions,
+    pub directives: Directives,
+    pub model: Option<String>,
+    pub prompt: Option<String>,
+}
+
+/// `create_art` writes a prompt and title and renders a new art as `params`
+/// asks.
+///
+/// # Errors
+///
+/// When the generators can't be configured, the image model isn't in the
+/// create pool, any generation step fails, or the art could not be saved.
+pub async fn create_art(ctx: &AppContext, params: &CreateArtParams) -> Result<arts::Model> {
+    create_art_recorded(ctx, params, None).await
+}
+
+/// `create_art_with_progress` creates the art, reporting each stage to the
+/// creation progress room of `request_id` and pointing the last update at the
+/// new art. The art is saved under `request_id`, so a retried request returns
+/// the art it already created. Failures are reported by the generation job
+/// running it.
+pub async fn create_art_with_progress(
+    ctx: &AppContext,
+    params: &CreateArtParams,
+    request_id: Uuid,
+) -> Result<arts::Model> {
+    create_art_recorded(ctx, params, Some(request_id)).await
+}
+
+async fn create_art_recorded(
+    ctx: &AppContext,
+    params: &CreateArtParams,
+    request_id: Option<Uuid>,
+) -> Result<arts::Model> {
+    let mut usage = UsageLog::default();
+    let result = create_art_inner(ctx, params, request_id, &mut usage).await;
+    usage
+        .save(
+            ctx,
+            Flow::Create,
+            result.as_ref().ok().map(|art| art.id),
+            None,
+        )
+        .await;
+    result
+}
+
+async fn emit_create_progress(request_id: Option<Uuid>, update: realtime::ProgressUpdate) {
+    if let Some(request_id) = request_id.as_ref() {
+        realtime::emit_art_create_progress(request_id, &update).await;
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+async fn create_art_inner(
+    ctx: &AppContext,
+    params: &CreateArtParams,
+    request_id: Option<Uuid>,
+    usage: &mut UsageLog,
+) -> Result<arts::Model> {
+    if let Some(request_id) = request_id
+        && let Some(art) = arts::Model::find_by_request_id(&ctx.db, request_id).await?
+    {
+        emit_create_progress(
+            Some(request_id),
+            realtime::ProgressUpdate::done("complete", "The new art is ready. Opening it...")
+                .with_redirect_to(format!("/backoffice/arts/{}", art.id)),
+        )
+        .await;
+        return Ok(art);
+    }
+
+    emit_create_progress(
+        request_id,
+        realtime::ProgressUpdate::new(
+            "preparing",
+            "Gathering gallery context for the new piece...",
+        ),
+    )
+    .await;
+
     let settings = settings(ctx)?;
-    let img_gen = ServiceProvider::random_img_service(&settings)
+    let options = params.options.clone().or(&settings.image_options);
+    options.validate().map_err(Error::Message)?;
+    let pool = match params.model.as_deref() {
+        Some(model) => settings
+            .pinned_image_pool(Flow::Create, model)
+            .ok_or_else(|| {
+                Error::Message(format!("Image model {model} is not in the create pool"))
+            })?,
+        None => settings.image_pool(Flow::Create).to_vec(),
+    };
+    let img_gen = ServiceProvider::pool_img_service(&settings, &pool)
         .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?;
-    let text_gen = ServiceProvider::random_txt_service(&settings)
+    let mut text_gen = ServiceProvider::random_txt_service(&settings, Flow::Create)
         .map_err(|e| Error::Message(format!("Unable to configure text generator: {e}")))?;
 
+    let templates = PromptTemplates::load(&ctx.db).await?;
     let random_arts = arts::Model::find_n_random(&ctx.db, 5).await?;
     let latest_arts = arts::Model::find_n_latest(&ctx.db, 5).await?;
-    let image_generator_prompt = match (random_arts.len(), latest_arts.len()) {
-        (0, 0) => IMAGE_PROMPT.replace("{{PROMPTS}}", SAMPLE_PROMPTS),
-        _ => gen_create_img_prompt(&random_arts, &latest_arts),
-    };
+    let directives = style_presets::Model::resolve(&ctx.db, &params.directives).await?;
+    let recent_prompts =
+        arts::Model::find_n_latest_prompts(&ctx.db, settings.diversity.window, None).await?;
 
-    let prompt = text_gen
-        .generate(&image_generator_prompt)
-        .await
-        .map_err(|e| Error::Message(format!("Unable to generate prompt for image: {e}")))?;
+    emit_create_progress(
+        request_id,
+        realtime::ProgressUpdate::new("prompting", "Writing the prompt for the new piece..."),
+    )
+    .await;
 
-    let title_generator_prompt = match (random_arts.len(), latest_arts.len()) {
-        (0, 0) => TITLE_PROMPT
-            .replace("{{TITLES}}", SAMPLE_TITLES)
-            .replace("{{DESCRIPTION}}", &prompt),
-        _ => gen_create_title_prompt(&prompt, &random_arts, &latest_arts),
+    // a prompt given by hand is taken as written, only scored for diversity
+    let manual_prompt = params
+        .prompt
+        .as_deref()
+        .map(normalize::prompt)
+        .filter(|prompt| !prompt.is_empty());
+    let (prompt, diversity_score, prompt_model, image_generator_prompt) = match manual_prompt {
+        Some(prompt) => {
+            let score = diversity::closest(&prompt, &recent_prompts, settings.diversity.ngram)
+                .map_or(1.0, |closest| 1.0 - closest.score);
+            (prompt, score, None, None)
+        }
+        None => {
+            let image_generator_prompt =
+                directives.meta_prompt(&templates.create_image_prompt(&random_arts, &latest_arts));
+            let DiversePrompt { prompt, score } = diversity::generate_prompt(
+                text_gen.as_mut(),
+                &image_generator_prompt,
+                &settings.diversity,
+                &recent_prompts,
+                usage,
+            )
+            .await?;
+            (
+                prompt,
+                score,
+                Some(text_gen.model_name()),
+                Some(image_generator_prompt),
+            )
+        }
     };
 
-    let title = text_gen
-        .generate(&title_generator_prompt)
-        .await
-        .map_err(|e| Error::Message(format!("Unable to generate title: {e}")))?;
+    emit_create_progress(
+        request_id,
+        realtime::ProgressUpdate::new("titling", "Prompt ready. Writing a title..."),
+    )
+    .await;
 
-    let image = img_gen
-        .generate(&prompt)
-        .await
-        .map_err(|e| Error::Message(format!("Unable to generate image: {e}")))?;
+    let title_generator_prompt = templates.create_title_prompt(&prompt, &random_arts, &latest_arts);
 
-    arts::Model::create(
+    let taken = titles::taken_titles(&ctx.db, None).await?;
+    let title = titles::generate_title(
+        text_gen.as_mut(),
+        &title_generator_prompt,
+        &settings.titles,
+        &taken,
+        usage,
+    )
+    .await?;
+    let title_model = text_gen.model_name();
+
+    emit_create_progress(
+        request_id,
+        realtime::ProgressUpdate::new("rendering", "Title locked in. Rendering the image..."),
+    )
+    .await;
+
+    let prompt = directives.image_prompt(&prompt);
+    let rendered = render_distinct_images(
+        &ctx.db, &settings, &pool, img_gen, &prompt, &options, usage, None,
+    )
+    .await?;
+
+    let candidates_mode = settings.candidates > 1;
+    emit_create_progress(
+        request_id,
+        realtime::ProgressUpdate::new(
+            "saving",
+            if candidates_mode {
+                "Images rendered. Saving them as candidates to pick from..."
+            } else {
+                "Image rendered. Saving the new art..."
+            },
+        ),
+    )
+    .await;
+
+    // In candidates mode the art stays a draft, showing no candidate of its
+    // own, until an editor picks one.
+    let status = if candidates_mode {
+        PublicationStatus::Draft
+    } else {
+        PublicationStatus::for_new_work(settings.require_approval)
+    };
+    let art = arts::Model::create(
         &ctx.db,
         &ArtParams {
-            image,
+            image: rendered[0].image.clone(),
             prompt,
             title,
-            model: Some(img_gen.model_name()),
+            model: Some(rendered[0].model.clone()),
+            provenance: Provenance {
+                prompt_model,
+                title_model: Some(title_model),
+                prompt_template_id: image_generator_prompt
+                    .as_ref()
+                    .and(templates.get(TemplateKind::Image).id),
+                prompt_meta_prompt: image_generator_prompt,
+                title_meta_prompt: Some(title_generator_prompt),
+                title_template_id: templates.get(TemplateKind::Title).id,
+            },
+            options,
+            status,
+            diversity_score: Some(diversity_score),
+            theme: directives.theme,
+            style: directives.style,
+            style_preset_id: directives.style_preset_id,
+            request_id,
+            ..Default::default()
         },
     )
-    .await
-    .map_err(Into::into)
+    .await?;
+    if candidates_mode {
+        save_candidates(ctx, art.id, rendered, None).await?;
+    }
+
+    emit_create_progress(
+        request_id,
+        realtime::ProgressUpdate::done("complete", "The new art is ready. Opening it...")
+            .with_redirect_to(format!("/backoffice/arts/{}", art.id)),
+    )
+    .await;
+
+    Ok(art)
 }
 
-pub async fn replace_art(ctx: &AppContext, art_id: i32) -> Result<arts::Model> {
-    replace_art_inner(ctx, art_id, None).await
+pub async fn replace_art(
+    ctx: &AppContext,
+    art_id: i32,
+    directives: &Directives,
+) -> Result<arts::Model> {
+    replace_art_recorded(ctx, art_id, directives, None, None).await
 }
 
+/// `replace_art_with_progress` replaces the art, reporting each stage to the
+/// art's progress room. Failures are reported by the generation job running it.
+/// The replacement follows `directives` and records them in place of the
+/// replaced art's. The art is marked with `request_id`, so a retried request
+/// returns it instead of replacing it again.
 pub async fn replace_art_with_progress(
     ctx: &AppContext,
     art_id: i32,
     art_uuid: Uuid,
+    directives: &Directives,
+    request_id: Option<Uuid>,
 ) -> Result<arts::Model> {
-    let result = replace_art_inner(ctx, art_id, Some(art_uuid)).await;
-
-    if result.is_err() {
-        realtime::emit_art_replace_progress(
-            &art_uuid,
-            &realtime::ProgressUpdate::failed(
-                "failed",
-                "The regeneration failed before the updated art could be saved.",
-            ),
-        )
-        .await;
-    }
-
-    result
+    replace_art_recorded(ctx, art_id, directives, request_id, Some(art_uuid)).await
 }
 
+/// `rerender_art_image_with_progress` renders a new image for the saved
+/// prompt, reporting each stage to the art's progress room. Failures are
+/// reported by the generation job running it.
 pub async fn rerender_art_image_with_progress(
     ctx: &AppContext,
     art_id: i32,
     art_uuid: Uuid,
 ) -> Result<arts::Model> {
-    let result = rerender_art_image_inner(ctx, art_id, Some(art_uuid)).await;
-
-    if result.is_err() {
-        realtime::emit_art_replace_progress(
-            &art_uuid,
-            &realtime::ProgressUpdate::failed(
-                "failed",
-                "The image-only regeneration failed before the updated art could be saved.",
-            ),
-        )
-        .await;
-    }
+    let mut usage = UsageLog::default();
+    let result = rerender_art_image_inner(ctx, art_id, Some(art_uuid), &mut usage).await;
+    usage.save(ctx, Flow::Rerender, Some(art_id), None).await;
+    result
+}
 
+async fn replace_art_recorded(
+    ctx: &AppContext,
+    art_id: i32,
+    directives: &Directives,
+    request_id: Option<Uuid>,
+    progress_art_uuid: Option<Uuid>,
+) -> Result<arts::Model> {
+    let mut usage = UsageLog::default();
+    let result = replace_art_inner(
+        ctx,
+        art_id,
+        directives,
+        request_id,
+        progress_art_uuid,
+        &mut usage,
+    )
+    .await;
+    usage.save(ctx, Flow::Replace, Some(art_id), None).await;
     result
 }
 
+#[allow(clippy::too_many_lines)]
 async fn replace_art_inner(
     ctx: &AppContext,
     art_id: i32,
+    directives: &Directives,
+    request_id: Option<Uuid>,
     progress_art_uuid: Option<Uuid>,
+    usage: &mut UsageLog,
 ) -> Result<arts::Model> {
+    let art_to_replace = arts::Entity::find_by_id(art_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::string(&format!("Art with ID {art_id} not found")))?;
+    if request_id.is_some() && art_to_replace.request_id == request_id {
+        emit_replace_progress(
+            progress_art_uuid,
+            realtime::ProgressUpdate::done(
+                "complete",
+                "Regeneration finished. Reloading this art with the new result...",
+            ),
+        )
+        .await;
+        return Ok(art_to_replace);
+    }
+
     let settings = settings(ctx)?;
-    let img_gen = ServiceProvider::random_img_service(&settings)
+    let img_gen = ServiceProvider::random_img_service(&settings, Flow::Replace)
         .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?;
-    let text_gen = ServiceProvider::random_txt_service(&settings)
+    let mut text_gen = ServiceProvider::random_txt_service(&settings, Flow::Replace)
         .map_err(|e| Error::Message(format!("Unable to configure text generator: {e}")))?;
 
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
@@ -135,22 +536,30 @@ async fn replace_art_inner(
         .await;
     }
 
-    let art_to_replace = arts::Entity::find_by_id(art_id)
-        .one(&ctx.db)
-        .await?
-        .ok_or_else(|| Error::string(&format!("Art with ID {art_id} not found")))?;
-
+    let options = art_to_replace
+        .generation_options()
+        .or(&settings.image_options);
+    let templates = PromptTemplates::load(&ctx.db).await?;
     let recent_arts = arts::Model::find_n_latest(&ctx.db, 10).await?;
-    let image_generator_prompt = if recent_arts.len() > 1 {
-        gen_replace_img_prompt(&recent_arts)
-    } else {
-        IMAGE_PROMPT.replace("{{PROMPTS}}", SAMPLE_PROMPTS)
-    };
+    let directives = style_presets::Model::resolve(&ctx.db, directives).await?;
+    let image_generator_prompt =
+        directives.meta_prompt(&templates.replace_image_prompt(&recent_arts));
 
-    let prompt = text_gen
-        .generate(&image_generator_prompt)
-        .await
-        .map_err(|e| Error::Message(format!("Failed to generate image prompt: {e}")))?;
+    let recent_prompts =
+        arts::Model::find_n_latest_prompts(&ctx.db, settings.diversity.window, Some(art_id))
+            .await?;
+    let DiversePrompt {
+        prompt,
+        score: diversity_score,
+    } = diversity::generate_prompt(
+        text_gen.as_mut(),
+        &image_generator_prompt,
+        &settings.diversity,
+        &recent_prompts,
+        usage,
+    )
+    .await?;
+    let prompt_model = text_gen.model_name();
 
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
         realtime::emit_art_replace_progress(
@@ -163,10 +572,18 @@ async fn replace_art_inner(
         .await;
     }
 
-    let image = img_gen
-        .generate(&prompt)
-        .await
-        .map_err(|e| Error::Message(format!("Failed to generate image: {e}")))?;
+    let image_prompt = directives.image_prompt(&prompt);
+    let rendered = render_distinct_images(
+        &ctx.db,
+        &settings,
+        settings.image_pool(Flow::Replace),
+        img_gen,
+        &image_prompt,
+        &options,
+        usage,
+        Some(art_id),
+    )
+    .await?;
 
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
         realtime::emit_art_replace_progress(
@@ -179,41 +596,79 @@ async fn replace_art_inner(
         .await;
     }
 
-    let title_generator_prompt = if recent_arts.len() > 1 {
-        gen_replace_title_prompt(&prompt, &recent_arts)
-    } else {
-        TITLE_PROMPT
-            .replace("{{TITLES}}", SAMPLE_TITLES)
-            .replace("{{DESCRIPTION}}", &prompt)
-    };
+    let title_generator_prompt = templates.replace_title_prompt(&prompt, &recent_arts);
 
-    let title = text_gen
-        .generate(&title_generator_prompt)
-        .await
-        .map_err(|e| Error::Message(format!("Failed to generate title: {e}")))?;
+    let taken = titles::taken_titles(&ctx.db, Some(art_id)).await?;
+    let title = titles::generate_title(
+        text_gen.as_mut(),
+        &title_generator_prompt,
+        &settings.titles,
+        &taken,
+        usage,
+    )
+    .await?;
+    let title_model = text_gen.model_name();
 
-    let mut art_active_model: arts::ActiveModel = art_to_replace.into();
-    art_active_model.prompt = Set(prompt);
-    art_active_model.image = Set(image);
-    art_active_model.title = Set(title);
-    art_active_model.model = Set(Some(img_gen.model_name()));
-    art_active_model.updated_at = Set(chrono::Utc::now().into());
+    let changes = ArtChanges {
+        prompt: Some(image_prompt),
+        title: Some(title),
+        prompt_model: Some(Some(prompt_model)),
+        title_model: Some(Some(title_model)),
+        prompt_meta_prompt: Some(Some(image_generator_prompt)),
+        title_meta_prompt: Some(Some(title_generator_prompt)),
+        prompt_template_id: Some(templates.get(TemplateKind::Image).id),
+        title_template_id: Some(templates.get(TemplateKind::Title).id),
+        diversity_score: Some(Some(diversity_score)),
+        theme: Some(directives.theme),
+        style: Some(directives.style),
+        style_preset_id: Some(directives.style_preset_id),
+        variation_instruction: Some(None),
+    };
+    let candidates_mode = settings.candidates > 1;
 
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
         realtime::emit_art_replace_progress(
             art_uuid,
             &realtime::ProgressUpdate::new(
                 "saving",
-                "Saving the regenerated art and refreshing the page...",
+                if candidates_mode {
+                    "Saving the regenerated images as candidates to pick from..."
+                } else {
+                    "Saving the regenerated art and refreshing the page..."
+                },
             ),
         )
         .await;
     }
 
-    let updated_art = art_active_model
-        .update(&ctx.db)
-        .await
-        .map_err(Error::from)?;
+    let updated_art = if candidates_mode {
+        save_candidates(ctx, art_id, rendered, Some(&changes)).await?;
+        if request_id.is_some() {
+            let mut art_active_model = arts::ActiveModel::from(art_to_replace);
+            art_active_model.request_id = Set(request_id);
+            art_active_model
+                .update(&ctx.db)
+                .await
+                .map_err(Error::from)?
+        } else {
+            art_to_replace
+        }
+    } else {
+        let txn = ctx.db.begin().await?;
+        art_revisions::Model::record(&txn, &art_to_replace, RevisionReason::Replace).await?;
+        let mut art_active_model =
+            arts::ActiveModel::from(art_to_replace).with_image(&rendered[0].image, &options);
+        art_active_model.model = Set(Some(rendered[0].model.clone()));
+        changes.apply(&mut art_active_model);
+        if request_id.is_some() {
+            art_active_model.request_id = Set(request_id);
+        }
+        art_active_model.updated_at = Set(chrono::Utc::now().into());
+        let updated_art = art_active_model.update(&txn).await.map_err(Error::from)?;
+        art_candidates::Model::discard_all(&txn, art_id).await?;
+        txn.commit().await?;
+        updated_art
+    };
 
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
         realtime::emit_art_replace_progress(
@@ -233,9 +688,10 @@ async fn rerender_art_image_inner(
     ctx: &AppContext,
     art_id: i32,
     progress_art_uuid: Option<Uuid>,
+    usage: &mut UsageLog,
 ) -> Result<arts::Model> {
     let settings = settings(ctx)?;
-    let img_gen = ServiceProvider::random_img_service(&settings)
+    let img_gen = ServiceProvider::random_img_service(&settings, Flow::Rerender)
         .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?;
 
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
@@ -265,28 +721,60 @@ async fn rerender_art_image_inner(
         .await;
     }
 
-    let image = img_gen
-        .generate(&art_to_replace.prompt)
-        .await
-        .map_err(|e| Error::Message(format!("Failed to generate image: {e}")))?;
-
-    let mut art_active_model: arts::ActiveModel = art_to_replace.into();
-    art_active_model.image = Set(image);
-    art_active_model.model = Set(Some(img_gen.model_name()));
-    art_active_model.updated_at = Set(chrono::Utc::now().into());
+    let options = art_to_replace
+        .generation_options()
+        .or(&settings.image_options);
+    let rendered = render_distinct_images(
+        &ctx.db,
+        &settings,
+        settings.image_pool(Flow::Rerender),
+        img_gen,
+        &art_to_replace.prompt,
+        &options,
+        usage,
+        Some(art_id),
+    )
+    .await?;
 
+    let candidates_mode = settings.candidates > 1;
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
         realtime::emit_art_replace_progress(
             art_uuid,
-            &realtime::ProgressUpdate::new("saving", "Saving the new image and updated model..."),
+            &realtime::ProgressUpdate::new(
+                "saving",
+                if candidates_mode {
+                    "Saving the new images as candidates to pick from..."
+                } else {
+                    "Saving the new image and updated model..."
+                },
+            ),
         )
         .await;
     }
 
-    let updated_art = art_active_model
-        .update(&ctx.db)
-        .await
-        .map_err(Error::from)?;
+    let updated_art = if candidates_mode {
+        save_candidates(ctx, art_id, rendered, None).await?;
+        // The flag is cleared now rather than on pick, so the nightly run
+        // does not render and pay for the art again while it waits.
+        let mut art_active_model = arts::ActiveModel::from(art_to_replace);
+        art_active_model.rerender_flagged = Set(false);
+        art_active_model
+            .update(&ctx.db)
+            .await
+            .map_err(Error::from)?
+    } else {
+        let txn = ctx.db.begin().await?;
+        art_revisions::Model::record(&txn, &art_to_replace, RevisionReason::Rerender).await?;
+        let mut art_active_model =
+            arts::ActiveModel::from(art_to_replace).with_image(&rendered[0].image, &options);
+        art_active_model.model = Set(Some(rendered[0].model.clone()));
+        art_active_model.rerender_flagged = Set(false);
+        art_active_model.updated_at = Set(chrono::Utc::now().into());
+        let updated_art = art_active_model.update(&txn).await.map_err(Error::from)?;
+        art_candidates::Model::discard_all(&txn, art_id).await?;
+        txn.commit().await?;
+        updated_art
+    };
 
     if let Some(art_uuid) = progress_art_uuid.as_ref() {
         realtime::emit_art_replace_progress(
@@ -302,71 +790,318 @@ async fn rerender_art_image_inner(
     Ok(updated_art)
 }
 
-fn gen_create_title_prompt(
-    desc: &str,
-    random_arts: &[arts::Model],
-    latest_arts: &[arts::Model],
-) -> String {
-    let titles = random_arts
-        .iter()
-        .chain(latest_arts.iter())
-        .map(|art| art.title.clone())
-        .collect::<Vec<String>>()
-        .join(", ");
-
-    TITLE_PROMPT
-        .replace("{{TITLES}}", &titles)
-        .replace("{{DESCRIPTION}}", desc)
+/// `vary_art` rewrites the art's prompt as `instruction` tells and renders
+/// it, saving the result over the art, or as a new art linked to it when
+/// `as_new` is set. Returns the art holding the variation.
+///
+/// # Errors
+///
+/// `Error::BadRequest` when the instruction is empty, and other errors when
+/// the generators can't be configured, any generation step fails, or the art
+/// could not be saved.
+pub async fn vary_art(
+    ctx: &AppContext,
+    art_id: i32,
+    instruction: &str,
+    as_new: bool,
+) -> Result<arts::Model> {
+    vary_art_recorded(ctx, art_id, instruction, as_new, None, None).await
+}
+
+/// `vary_art_with_progress` varies the art, reporting each stage to the
+/// art's progress room and pointing the last update at a new variation.
+/// Failures are reported by the generation job running it. A new variation
+/// is saved under `request_id`, so a retried request returns the art it
+/// already saved instead of saving another one.
+pub async fn vary_art_with_progress(
+    ctx: &AppContext,
+    art_id: i32,
+    art_uuid: Uuid,
+    instruction: &str,
+    as_new: bool,
+    request_id: Option<Uuid>,
+) -> Result<arts::Model> {
+    vary_art_recorded(ctx, art_id, instruction, as_new, request_id, Some(art_uuid)).await
 }
 
-fn gen_create_img_prompt(random_arts: &[arts::Model], latest_arts: &[arts::Model]) -> String {
-    let mut prompts = Vec::new();
-
-    if !random_arts.is_empty() {
-        prompts.push("Previous prompts for inspiration (try to vary from these):".to_string());
-        prompts.extend(
-            random_arts
-                .iter()
-                .enumerate()
-                .map(|(i, art)| format!(" - inspiration {}: {}", i + 1, art.prompt.clone())),
-        );
+async fn vary_art_recorded(
+    ctx: &AppContext,
+    art_id: i32,
+    instruction: &str,
+    as_new: bool,
+    request_id: Option<Uuid>,
+    progress_art_uuid: Option<Uuid>,
+) -> Result<arts::Model> {
+    let mut usage = UsageLog::default();
+    let result = vary_art_inner(
+        ctx,
+        art_id,
+        instruction,
+        as_new,
+        request_id,
+        progress_art_uuid,
+        &mut usage,
+    )
+    .await;
+    let usage_art_id = result.as_ref().map_or(art_id, |art| art.id);
+    usage
+        .save(ctx, Flow::Variation, Some(usage_art_id), None)
+        .await;
+    result
+}
+
+async fn emit_replace_progress(art_uuid: Option<Uuid>, update: realtime::ProgressUpdate) {
+    if let Some(art_uuid) = art_uuid.as_ref() {
+        realtime::emit_art_replace_progress(art_uuid, &update).await;
     }
+}
 
-    if !latest_arts.is_empty() {
-        prompts.push(
-            "\nRecent prompts to actively differentiate from (be distinctly different from these):"
-                .to_string(),
-        );
-        prompts.extend(
-            latest_arts
-                .iter()
-                .enumerate()
-                .map(|(i, art)| format!(" - recent {}: {}", i + 1, art.prompt.clone())),
-        );
+#[allow(clippy::too_many_lines)]
+async fn vary_art_inner(
+    ctx: &AppContext,
+    art_id: i32,
+    instruction: &str,
+    as_new: bool,
+    request_id: Option<Uuid>,
+    progress_art_uuid: Option<Uuid>,
+    usage: &mut UsageLog,
+) -> Result<arts::Model> {
+    let instruction = instruction.trim();
+    if instruction.is_empty() {
+        return Err(Error::BadRequest(
+            "Tell what the variation should change.".to_string(),
+        ));
     }
 
-    IMAGE_PROMPT.replace("{{PROMPTS}}", &prompts.join("\n"))
-}
+    if as_new
+        && let Some(request_id) = request_id
+        && let Some(art) = arts::Model::find_by_request_id(&ctx.db, request_id).await?
+    {
+        emit_replace_progress(
+            progress_art_uuid,
+            realtime::ProgressUpdate::done("complete", "The variation is ready. Opening it...")
+                .with_redirect_to(format!("/backoffice/arts/{}", art.id)),
+        )
+        .await;
+        return Ok(art);
+    }
+
+    let settings = settings(ctx)?;
+    let candidates_mode = settings.candidates > 1;
+    let img_gen = ServiceProvider::random_img_service(&settings, Flow::Variation)
+        .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?;
+    let mut text_gen = ServiceProvider::random_txt_service(&settings, Flow::Variation)
+        .map_err(|e| Error::Message(format!("Unable to configure text generator: {e}")))?;
+
+    emit_replace_progress(
+        progress_art_uuid,
+        realtime::ProgressUpdate::new("preparing", "Loading the saved prompt to vary..."),
+    )
+    .await;
 
-fn gen_replace_title_prompt(desc: &str, arts: &[arts::Model]) -> String {
-    let titles = arts
-        .iter()
-        .map(|art| art.title.clone())
-        .collect::<Vec<String>>()
-        .join(", ");
+    let parent = arts::Entity::find_by_id(art_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::string(&format!("Art with ID {art_id} not found")))?;
+
+    let options = parent.generation_options().or(&settings.image_options);
+    let templates = PromptTemplates::load(&ctx.db).await?;
+    let image_generator_prompt = templates.variation_prompt(&parent.prompt, instruction);
+
+    emit_replace_progress(
+        progress_art_uuid,
+        realtime::ProgressUpdate::new("prompting", "Rewriting the prompt as instructed..."),
+    )
+    .await;
 
-    TITLE_PROMPT
-        .replace("{{TITLES}}", &titles)
-        .replace("{{DESCRIPTION}}", desc)
+    let answer = text_gen.generate(&image_generator_prompt).await;
+    usage.collect("prompt", text_gen.take_usage());
+    let prompt = normalize::prompt(
+        &answer.map_err(|e| Error::Message(format!("Unable to generate prompt for image: {e}")))?,
+    );
+    let prompt_model = text_gen.model_name();
+
+    emit_replace_progress(
+        progress_art_uuid,
+        realtime::ProgressUpdate::new("rendering", "Prompt ready. Rendering the variation..."),
+    )
+    .await;
+
+    // a variation saved over its parent may look like the image it replaces
+    let rendered = render_distinct_images(
+        &ctx.db,
+        &settings,
+        settings.image_pool(Flow::Variation),
+        img_gen,
+        &prompt,
+        &options,
+        usage,
+        (!as_new).then_some(art_id),
+    )
+    .await?;
+
+    let art = if as_new {
+        emit_replace_progress(
+            progress_art_uuid,
+            realtime::ProgressUpdate::new("titling", "Image rendered. Writing a title..."),
+        )
+        .await;
+
+        let recent_arts = arts::Model::find_n_latest(&ctx.db, 10).await?;
+        let title_generator_prompt = templates.replace_title_prompt(&prompt, &recent_arts);
+        let taken = titles::taken_titles(&ctx.db, None).await?;
+        let title = titles::generate_title(
+            text_gen.as_mut(),
+            &title_generator_prompt,
+            &settings.titles,
+            &taken,
+            usage,
+        )
+        .await?;
+
+        emit_replace_progress(
+            progress_art_uuid,
+            realtime::ProgressUpdate::new("saving", "Saving the variation as a new art..."),
+        )
+        .await;
+
+        let art = arts::Model::create(
+            &ctx.db,
+            &ArtParams {
+                image: rendered[0].image.clone(),
+                prompt,
+                title,
+                model: Some(rendered[0].model.clone()),
+                provenance: Provenance {
+                    prompt_model: Some(prompt_model),
+                    title_model: Some(text_gen.model_name()),
+                    prompt_meta_prompt: Some(image_generator_prompt),
+                    title_meta_prompt: Some(title_generator_prompt),
+                    prompt_template_id: templates.get(TemplateKind::Variation).id,
+                    title_template_id: templates.get(TemplateKind::Title).id,
+                },
+                options,
+                status: if candidates_mode {
+                    PublicationStatus::Draft
+                } else {
+                    PublicationStatus::for_new_work(settings.require_approval)
+                },
+                diversity_score: None,
+                theme: parent.theme.clone(),
+                style: parent.style.clone(),
+                style_preset_id: parent.style_preset_id,
+                parent_id: Some(parent.id),
+                variation_instruction: Some(instruction.to_string()),
+                request_id,
+            },
+        )
+        .await?;
+        if candidates_mode {
+            save_candidates(ctx, art.id, rendered, None).await?;
+        }
+        art
+    } else {
+        emit_replace_progress(
+            progress_art_uuid,
+            realtime::ProgressUpdate::new(
+                "saving",
+                if candidates_mode {
+                    "Saving the variations as candidates to pick from..."
+                } else {
+                    "Saving the variation over this art..."
+                },
+            ),
+        )
+        .await;
+
+        let changes = ArtChanges {
+            prompt: Some(prompt),
+            prompt_model: Some(Some(prompt_model)),
+            prompt_meta_prompt: Some(Some(image_generator_prompt)),
+            prompt_template_id: Some(templates.get(TemplateKind::Variation).id),
+            variation_instruction: Some(Some(instruction.to_string())),
+            ..Default::default()
+        };
+        if candidates_mode {
+            save_candidates(ctx, parent.id, rendered, Some(&changes)).await?;
+            parent
+        } else {
+            let txn = ctx.db.begin().await?;
+            art_revisions::Model::record(&txn, &parent, RevisionReason::Variation).await?;
+            let parent_id = parent.id;
+            let mut art_active_model =
+                arts::ActiveModel::from(parent).with_image(&rendered[0].image, &options);
+            art_active_model.model = Set(Some(rendered[0].model.clone()));
+            changes.apply(&mut art_active_model);
+            art_active_model.updated_at = Set(chrono::Utc::now().into());
+            let art = art_active_model.update(&txn).await.map_err(Error::from)?;
+            art_candidates::Model::discard_all(&txn, parent_id).await?;
+            txn.commit().await?;
+            art
+        }
+    };
+
+    let done = if as_new {
+        realtime::ProgressUpdate::done("complete", "The variation is ready. Opening it...")
+            .with_redirect_to(format!("/backoffice/arts/{}", art.id))
+    } else {
+        realtime::ProgressUpdate::done(
+            "complete",
+            "Variation finished. Reloading this art with the new result...",
+        )
+    };
+    emit_replace_progress(progress_art_uuid, done).await;
+
+    Ok(art)
 }
 
-fn gen_replace_img_prompt(arts: &[arts::Model]) -> String {
-    let prompts = arts
-        .iter()
-        .enumerate()
-        .map(|(i, art)| format!(" - prompt {}: {}", i + 1, art.prompt.clone()))
-        .collect::<Vec<String>>()
-        .join("\n");
+/// `ArtUpload` is an image made elsewhere, with the details to save it under.
+pub struct ArtUpload {
+    pub image: Vec<u8>,
+    pub title: String,
+    pub prompt: String,
+    /// Label of whatever made the image.
+    pub model: Option<String>,
+}
+
+/// `upload_art` saves an uploaded PNG, JPEG or WebP image as a new art,
+/// converted to WebP like every rendered image.
+///
+/// # Errors
+///
+/// `Error::BadRequest` when the title or prompt is empty or the image isn't
+/// one of those formats or can't be decoded, and other errors when the art
+/// could not be saved.
+pub async fn upload_art(ctx: &AppContext, upload: &ArtUpload) -> Result<arts::Model> {
+    let settings = settings(ctx)?;
+    let title = upload.title.trim();
+    let prompt = upload.prompt.trim();
+    if title.is_empty() || prompt.is_empty() {
+        return Err(Error::BadRequest(
+            "Title and prompt cannot be empty.".to_string(),
+        ));
+    }
+    if upload.image.is_empty() {
+        return Err(Error::BadRequest("Choose an image to upload.".to_string()));
+    }
+    let webp = webp::upload_to_webp(&upload.image).map_err(Error::BadRequest)?;
 
-    IMAGE_PROMPT.replace("{{PROMPTS}}", &prompts)
+    let art = arts::Model::create(
+        &ctx.db,
+        &ArtParams {
+            image: general_purpose::STANDARD.encode(webp),
+            prompt: prompt.to_string(),
+            title: title.to_string(),
+            model: upload
+                .model
+                .as_deref()
+                .map(str::trim)
+                .filter(|model| !model.is_empty())
+                .map(str::to_string),
+            status: PublicationStatus::for_new_work(settings.require_approval),
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(art)
 }
@@ -0,0 +1,267 @@
+//! Keeps new prompts from repeating the gallery. The prompt meta-prompts ask
+//! the text Model to differ from the recent prompts, but nothing verified it
+//! did, and the gallery drifted into the same subjects. A fresh prompt is
+//! compared with the last prompts by the Jaccard similarity of their word
+//! n-grams and, when it is too close to one, the Model is asked again with
+//! the overlap called out.
+
+use std::collections::BTreeSet;
+
+use loco_rs::prelude::*;
+use tracing::warn;
+
+use crate::{
+    common::settings::DiversitySettings,
+    services::{ai::traits::TextGenerator, generation_usage::UsageLog, normalize},
+};
+
+/// Words too common in prompts to tell two subjects apart.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "by", "for", "from", "in", "into", "is", "it", "its",
+    "of", "on", "or", "over", "that", "the", "their", "with", "under", "while", "within",
+];
+
+/// `DiversePrompt` is a generated prompt with its diversity score: 1 minus
+/// its highest similarity to a recent prompt, so 1 shares nothing with them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiversePrompt {
+    pub prompt: String,
+    pub score: f64,
+}
+
+/// `Similarity` is how close a prompt is to its closest recent prompt.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Similarity {
+    /// Jaccard similarity, from 0 (nothing shared) to 1 (the same n-grams).
+    pub score: f64,
+    /// The n-grams both prompts contain.
+    pub shared: Vec<String>,
+}
+
+/// `words` lowercases a prompt into its words, dropping stopwords.
+fn words(prompt: &str) -> Vec<String> {
+    prompt
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        .filter(|word| !word.is_empty() && !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// `ngrams` are the distinct runs of `n` consecutive words of a prompt; a
+/// prompt shorter than `n` words is one n-gram.
+#[must_use]
+pub fn ngrams(prompt: &str, n: usize) -> BTreeSet<String> {
+    let words = words(prompt);
+    let n = n.max(1);
+    if words.len() <= n {
+        return (!words.is_empty())
+            .then(|| words.join(" "))
+            .into_iter()
+            .collect();
+    }
+    words.windows(n).map(|window| window.join(" ")).collect()
+}
+
+/// `similarity` is the Jaccard similarity of the word n-grams of two prompts
+/// and the n-grams they share.
+#[must_use]
+pub fn similarity(prompt: &str, other: &str, n: usize) -> Similarity {
+    let mine = ngrams(prompt, n);
+    let theirs = ngrams(other, n);
+    let shared = mine.intersection(&theirs).cloned().collect::<Vec<_>>();
+    let union = mine.union(&theirs).count();
+
+    #[allow(clippy::cast_precision_loss)]
+    let score = if union == 0 {
+        0.0
+    } else {
+        shared.len() as f64 / union as f64
+    };
+    Similarity { score, shared }
+}
+
+/// `closest` is the similarity of `prompt` to the most similar of `recent`,
+/// `None` when there are no recent prompts.
+#[must_use]
+pub fn closest(prompt: &str, recent: &[String], n: usize) -> Option<Similarity> {
+    recent
+        .iter()
+        .map(|other| similarity(prompt, other, n))
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+}
+
+/// `generate_prompt` asks `text_gen` for an image prompt with `meta_prompt`
+/// and re-asks, naming the overlap, while the answer is more similar than
+/// `settings.threshold` to one of the `recent` prompts, up to
+/// `settings.attempts` answers. The last answer is kept when none is diverse
+/// enough, as a repetitive prompt still beats no art. Every call is collected
+/// into `usage` as a `prompt` step.
+///
+/// # Errors
+///
+/// When the Model fails.
+pub async fn generate_prompt(
+    text_gen: &mut (dyn TextGenerator + Send),
+    meta_prompt: &str,
+    settings: &DiversitySettings,
+    recent: &[String],
+    usage: &mut UsageLog,
+) -> Result<DiversePrompt> {
+    let attempts = settings.attempts.max(1);
+    let mut ask = meta_prompt.to_string();
+    let mut attempt = 1;
+
+    loop {
+        let answer = text_gen.generate(&ask).await;
+        usage.collect("prompt", text_gen.take_usage());
+        let prompt =
+            normalize::prompt(&answer.map_err(|e| {
+                Error::Message(format!("Unable to generate prompt for image: {e}"))
+            })?);
+
+        let Some(closest) = closest(&prompt, recent, settings.ngram) else {
+            return Ok(DiversePrompt { prompt, score: 1.0 });
+        };
+        let score = 1.0 - closest.score;
+        if closest.score <= settings.threshold || attempt == attempts {
+            if closest.score > settings.threshold {
+                warn!(
+                    attempt,
+                    similarity = closest.score,
+                    "keeping a repetitive prompt"
+                );
+            }
+            return Ok(DiversePrompt { prompt, score });
+        }
+
+        warn!(
+            attempt,
+            similarity = closest.score,
+            shared = closest.shared.join(", "),
+            "rejected repetitive prompt"
+        );
+        ask = format!(
+            "{meta_prompt}\n\nYour previous answer was \"{prompt}\", which is too similar to a recent prompt of the gallery: they share {}. Write a prompt about a different subject, and *just* the prompt.",
+            closest.shared.join(", ")
+        );
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::errors::Error as AiError;
+
+    fn settings() -> DiversitySettings {
+        DiversitySettings {
+            window: 10,
+            threshold: 0.5,
+            ngram: 1,
+            attempts: 3,
+        }
+    }
+
+    /// Answers with the queued prompts in order and records what it was asked.
+    struct Scripted {
+        answers: Mutex<Vec<&'static str>>,
+        asked: Mutex<Vec<String>>,
+    }
+
+    impl Scripted {
+        fn new(answers: &[&'static str]) -> Self {
+            Self {
+                answers: Mutex::new(answers.iter().rev().copied().collect()),
+                asked: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TextGenerator for Scripted {
+        async fn generate(&self, prompt: &str) -> Result<String, AiError> {
+            self.asked.lock().unwrap().push(prompt.to_string());
+            Ok(self.answers.lock().unwrap().pop().unwrap().to_string())
+        }
+
+        fn model_name(&self) -> String {
+            "scripted".to_string()
+        }
+    }
+
+    #[test]
+    fn similarity_is_the_jaccard_index_of_ngrams() {
+        let same = similarity("A lighthouse in the fog", "lighthouse, FOG", 1);
+        assert!((same.score - 1.0).abs() < f64::EPSILON);
+        assert_eq!(same.shared, vec!["fog", "lighthouse"]);
+
+        let third = similarity("red fox", "red owl", 1);
+        assert!((third.score - 1.0 / 3.0).abs() < 1e-9);
+
+        let bigrams = similarity("red fox sleeping", "red fox running", 2);
+        assert_eq!(bigrams.shared, vec!["red fox"]);
+        assert!((bigrams.score - 1.0 / 3.0).abs() < 1e-9);
+
+        assert!(similarity("", "", 1).score.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn closest_picks_the_most_similar_recent_prompt() {
+        let recent = vec!["blue whale".to_string(), "red fox".to_string()];
+        let found = closest("red fox at dawn", &recent, 1).unwrap();
+        assert_eq!(found.shared, vec!["fox", "red"]);
+        assert!(closest("anything", &[], 1).is_none());
+    }
+
+    #[tokio::test]
+    async fn re_asks_with_the_overlap_until_diverse() {
+        let mut text_gen = Scripted::new(&[
+            "A lighthouse in the fog at dusk",
+            "A desert caravan under twin moons",
+        ]);
+        let recent = vec!["Lighthouse in fog, dusk".to_string()];
+        let mut usage = UsageLog::default();
+
+        let prompt = generate_prompt(&mut text_gen, "Prompt", &settings(), &recent, &mut usage)
+            .await
+            .unwrap();
+
+        assert_eq!(prompt.prompt, "A desert caravan under twin moons");
+        assert!((prompt.score - 1.0).abs() < f64::EPSILON);
+        let asked = text_gen.asked.lock().unwrap();
+        assert_eq!(asked.len(), 2);
+        assert!(
+            asked[1].contains("they share dusk, fog, lighthouse"),
+            "{}",
+            asked[1]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_the_last_answer_after_the_configured_attempts() {
+        let mut text_gen = Scripted::new(&["red fox", "red fox"]);
+        let recent = vec!["red fox".to_string()];
+        let settings = DiversitySettings {
+            attempts: 2,
+            ..settings()
+        };
+
+        let prompt = generate_prompt(
+            &mut text_gen,
+            "Prompt",
+            &settings,
+            &recent,
+            &mut UsageLog::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(prompt.prompt, "red fox");
+        assert!(prompt.score.abs() < f64::EPSILON);
+        assert_eq!(text_gen.asked.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,241 @@
+//! Finds near-identical images among the arts and mixes by comparing the
+//! perceptual hashes stored with them. Diverse prompts still sometimes render
+//! the same picture; new renders are checked against every existing work and
+//! the backoffice reports the pairs that are already saved.
+
+use std::fmt;
+
+use loco_rs::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    common::{image_hash, settings::Settings},
+    models::{arts, mixes},
+};
+
+/// `WorkKind` is whether a hashed work is an art or a mix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkKind {
+    Art,
+    Mix,
+}
+
+impl WorkKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Art => "art",
+            Self::Mix => "mix",
+        }
+    }
+}
+
+/// `HashedWork` is an art or mix with the perceptual hash of its image.
+#[derive(Clone, Debug, Serialize)]
+pub struct HashedWork {
+    pub kind: WorkKind,
+    pub id: i32,
+    pub title: String,
+    pub status: String,
+    pub updated_at: DateTimeWithTimeZone,
+    pub image_hash: i64,
+}
+
+/// `Match` is the existing work closest to an image.
+#[derive(Clone, Debug, Serialize)]
+pub struct Match {
+    pub work: HashedWork,
+    pub distance: u32,
+}
+
+impl fmt::Display for Match {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} #{} (distance {})",
+            self.work.kind.as_str(),
+            self.work.id,
+            self.distance
+        )
+    }
+}
+
+/// `all_rejected` is the error of a generation whose every render, over
+/// `attempts` attempts, was rejected as a near-duplicate; `last` is the work
+/// the last one matched.
+#[must_use]
+pub fn all_rejected(last: Option<&Match>, attempts: usize) -> Error {
+    let matched = last.map_or_else(String::new, |found| format!(" of {found}"));
+    Error::Message(format!(
+        "Every render was a near-duplicate{matched} after {attempts} attempts"
+    ))
+}
+
+/// `DuplicatePair` is two saved works whose images are near-identical.
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicatePair {
+    pub first: HashedWork,
+    pub second: HashedWork,
+    pub distance: u32,
+}
+
+/// `load` reads every hashed art and mix.
+///
+/// # Errors
+///
+/// On DB query error.
+pub async fn load(db: &DatabaseConnection) -> Result<Vec<HashedWork>> {
+    let arts = arts::Model::find_image_hashes(db).await?;
+    let mixes = mixes::Model::find_image_hashes(db).await?;
+
+    let arts = arts.into_iter().filter_map(|art| {
+        Some(HashedWork {
+            kind: WorkKind::Art,
+            id: art.id,
+            title: art.title,
+            status: art.status,
+            updated_at: art.updated_at,
+            image_hash: art.image_hash?,
+        })
+    });
+    let mixes = mixes.into_iter().filter_map(|mix| {
+        Some(HashedWork {
+            kind: WorkKind::Mix,
+            id: mix.id,
+            title: mix.title,
+            status: mix.status,
+            updated_at: mix.updated_at,
+            image_hash: mix.image_hash?,
+        })
+    });
+    Ok(arts.chain(mixes).collect())
+}
+
+/// `report` is the "possible duplicates" report: every pair of saved works
+/// within the configured threshold, closest first, and the threshold.
+///
+/// # Errors
+///
+/// On invalid settings or DB query error.
+pub async fn report(ctx: &AppContext) -> Result<(Vec<DuplicatePair>, u32)> {
+    let settings = Settings::from_json(
+        &ctx.config
+            .settings
+            .clone()
+            .ok_or(Error::Message("Invalid settings".into()))?,
+    )?;
+    let threshold = settings.duplicates.threshold;
+    let works = load(&ctx.db).await?;
+    Ok((pairs(&works, threshold), threshold))
+}
+
+/// `nearest` is the work in `works` closest to `hash` within `threshold`,
+/// leaving out `except` (the work being re-rendered).
+#[must_use]
+pub fn nearest(
+    hash: i64,
+    works: &[HashedWork],
+    threshold: u32,
+    except: Option<(WorkKind, i32)>,
+) -> Option<Match> {
+    works
+        .iter()
+        .filter(|work| Some((work.kind, work.id)) != except)
+        .map(|work| (work, image_hash::distance(hash, work.image_hash)))
+        .filter(|(_, distance)| *distance <= threshold)
+        .min_by_key(|(work, distance)| (*distance, work.id))
+        .map(|(work, distance)| Match {
+            work: work.clone(),
+            distance,
+        })
+}
+
+/// `pairs` are the pairs of `works` within `threshold` of each other,
+/// closest first.
+#[must_use]
+pub fn pairs(works: &[HashedWork], threshold: u32) -> Vec<DuplicatePair> {
+    let mut pairs = Vec::new();
+    for (index, first) in works.iter().enumerate() {
+        for second in &works[index + 1..] {
+            let distance = image_hash::distance(first.image_hash, second.image_hash);
+            if distance <= threshold {
+                pairs.push(DuplicatePair {
+                    first: first.clone(),
+                    second: second.clone(),
+                    distance,
+                });
+            }
+        }
+    }
+    pairs.sort_by_key(|pair| pair.distance);
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn work(kind: WorkKind, id: i32, image_hash: i64) -> HashedWork {
+        HashedWork {
+            kind,
+            id,
+            title: format!("{} {id}", kind.as_str()),
+            status: "published".to_string(),
+            updated_at: chrono::Utc::now().into(),
+            image_hash,
+        }
+    }
+
+    #[test]
+    fn nearest_finds_the_closest_work_within_the_threshold() {
+        let works = vec![
+            work(WorkKind::Art, 1, 0b1111),
+            work(WorkKind::Art, 2, 0b0111),
+            work(WorkKind::Mix, 1, -1),
+        ];
+
+        let found = nearest(0b0011, &works, 4, None).unwrap();
+        assert_eq!(
+            (found.work.kind, found.work.id, found.distance),
+            (WorkKind::Art, 2, 1)
+        );
+
+        let found = nearest(0b0011, &works, 4, Some((WorkKind::Art, 2))).unwrap();
+        assert_eq!((found.work.id, found.distance), (1, 2));
+
+        assert!(nearest(0b0011, &works, 0, None).is_none());
+    }
+
+    #[test]
+    fn pairs_lists_near_duplicates_closest_first() {
+        let works = vec![
+            work(WorkKind::Art, 1, 0b1111),
+            work(WorkKind::Art, 2, 0b0111),
+            work(WorkKind::Mix, 1, 0b1111),
+            work(WorkKind::Mix, 2, -1),
+        ];
+
+        let pairs = pairs(&works, 2);
+        let found = pairs
+            .iter()
+            .map(|pair| {
+                (
+                    pair.first.kind,
+                    pair.first.id,
+                    pair.second.kind,
+                    pair.second.id,
+                    pair.distance,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (WorkKind::Art, 1, WorkKind::Mix, 1, 0),
+                (WorkKind::Art, 1, WorkKind::Art, 2, 1),
+                (WorkKind::Art, 2, WorkKind::Mix, 1, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use std::time::Duration;
+
+use loco_rs::{bgworker::BackgroundWorker, prelude::*};
+use tracing::{error, info, warn};
+
+use crate::{
+    common::settings::{GenerationJobSettings, Settings},
+    models::{
+        arts,
+        generation_jobs::{self, GenerationJobParams, JobKind, JobPayload, JobStatus},
+    },
+    services::{
+        art_service::{self, CreateArtParams},
+        mix_service, realtime,
+    },
+    workers::generation::{GenerationWorker, GenerationWorkerArgs},
+};
+
+/// Upper bound for the delay between two attempts of the same job.
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+fn job_settings(ctx: &AppContext) -> Result<GenerationJobSettings> {
+    Ok(Settings::from_json(
+        &ctx.config
+            .settings
+            .clone()
+            .ok_or(Error::Message("Invalid settings".into()))?,
+    )?
+    .generation_jobs)
+}
+
+/// `retry_delay` is the exponential backoff after the given number of failed
+/// attempts: `base`, `2 * base`, `4 * base`, ... capped at one hour.
+#[must_use]
+pub fn retry_delay(base_seconds: u64, attempts: i32) -> Duration {
+    let exponent = u32::try_from(attempts.saturating_sub(1))
+        .unwrap_or(0)
+        .min(16);
+    Duration::from_secs(base_seconds.saturating_mul(1 << exponent)).min(MAX_BACKOFF)
+}
+
+/// `enqueue` persists a new generation job and hands it to the background
+/// worker, returning the stored job.
+///
+/// # Errors
+///
+/// When the job could not be saved or handed to the worker.
+pub async fn enqueue(
+    ctx: &AppContext,
+    kind: JobKind,
+    art_id: Option<i32>,
+    payload: JobPayload,
+) -> Result<generation_jobs::Model> {
+    let job = create(ctx, kind, art_id, payload, None).await?;
+    GenerationWorker::perform_later(ctx, GenerationWorkerArgs { job_id: job.id }).await?;
+    Ok(job)
+}
+
+/// `run_now` persists a single-attempt job and runs it in the foreground,
+/// for callers (like CLI tasks) that outlive nothing that could retry it.
+///
+/// # Errors
+///
+/// When the job could not be saved, or it ran and failed.
+pub async fn run_now(
+    ctx: &AppContext,
+    kind: JobKind,
+    art_id: Option<i32>,
+    payload: JobPayload,
+) -> Result<generation_jobs::Model> {
+    let job = create(ctx, kind, art_id, payload, Some(1)).await?;
+    let job = run(ctx, job.id).await?;
+
+    if job.status == JobStatus::Failed.as_str() {
+        return Err(Error::Message(
+            job.last_error
+                .unwrap_or_else(|| format!("Generation job {} failed", job.id)),
+        ));
+    }
+
+    Ok(job)
+}
+
+async fn create(
+    ctx: &AppContext,
+    kind: JobKind,
+    art_id: Option<i32>,
+    payload: JobPayload,
+    max_attempts: Option<i32>,
+) -> Result<generation_jobs::Model> {
+    let max_attempts = match max_attempts {
+        Some(max_attempts) => max_attempts,
+        None => job_settings(ctx)?.max_attempts,
+    };
+
+    let job = generation_jobs::Model::create(
+        &ctx.db,
+        &GenerationJobParams {
+            kind,
+            art_id,
+            payload,
+            max_attempts,
+        },
+    )
+    .await?;
+
+    info!(job_id = job.id, kind = %kind, art_id, "queued generation job");
+    Ok(job)
+}
+
+/// `run` performs one attempt of a queued job and records its outcome. A
+/// failed attempt is requeued with backoff while attempts remain. Returns the
+/// job as stored after the attempt.
+///
+/// # Errors
+///
+/// Only on DB errors; generation failures are recorded on the job instead.
+pub async fn run(ctx: &AppContext, job_id: i32) -> Result<generation_jobs::Model> {
+    let Some(job) = generation_jobs::Model::claim(&ctx.db, job_id).await? else {
+        warn!(job_id, "generation job is not queued, skipping");
+        return generation_jobs::Entity::find_by_id(job_id)
+            .one(&ctx.db)
+            .await?
+            .ok_or_else(|| Error::NotFound);
+    };
+
+    info!(job_id, kind = %job.kind, attempt = job.attempts, "running generation job");
+
+    match perform(ctx, &job).await {
+        Ok((art_id, mix_id)) => {
+            info!(job_id, art_id, mix_id, "generation job completed");
+            Ok(generation_jobs::Model::mark_completed(&ctx.db, job_id, art_id, mix_id).await?)
+        }
+        Err(err) => {
+            let message = err.to_string();
+            if job.has_attempts_left() {
+                let delay = retry_delay(job_settings(ctx)?.backoff_seconds, job.attempts);
+                let retry_at = chrono::Utc::now()
+                    + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero());
+                warn!(job_id, attempt = job.attempts, error = %message, retry_in = ?delay, "generation job failed, retrying");
+
+                let job = generation_jobs::Model::mark_failed(
+                    &ctx.db,
+                    job_id,
+                    &message,
+                    Some(retry_at.into()),
+                )
+                .await?;
+                report_retry(ctx, &job, delay).await;
+                schedule(ctx, &job);
+                Ok(job)
+            } else {
+                error!(job_id, attempt = job.attempts, error = %message, "generation job failed");
+
+                let job =
+                    generation_jobs::Model::mark_failed(&ctx.db, job_id, &message, None).await?;
+                report_failure(ctx, &job).await;
+                Ok(job)
+            }
+        }
+    }
+}
+
+/// `resume_pending` requeues jobs running for longer than the lease, taken
+/// as interrupted by a restart, and schedules every queued job for its due
+/// time, returning how many were scheduled.
+///
+/// # Errors
+///
+/// On DB errors.
+pub async fn resume_pending(ctx: &AppContext, settings: &GenerationJobSettings) -> Result<usize> {
+    let lease =
+        chrono::Duration::seconds(i64::try_from(settings.lease_seconds).unwrap_or(i64::MAX / 1000));
+    let stale_before = chrono::Utc::now()
+        .checked_sub_signed(lease)
+        .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC);
+    let interrupted =
+        generation_jobs::Model::requeue_interrupted(&ctx.db, stale_before.into()).await?;
+    if interrupted > 0 {
+        warn!(
+            interrupted,
+            "requeued generation jobs interrupted by a restart"
+        );
+    }
+
+    let queued = generation_jobs::Model::find_queued(&ctx.db).await?;
+    for job in &queued {
+        schedule(ctx, job);
+    }
+
+    Ok(queued.len())
+}
+
+/// Hands a queued job to the worker once its `run_at` is due.
+fn schedule(ctx: &AppContext, job: &generation_jobs::Model) {
+    let delay = (job.run_at.to_utc() - chrono::Utc::now())
+        .to_std()
+        .unwrap_or_default();
+    let ctx = ctx.clone();
+    let job_id = job.id;
+
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        if let Err(err) =
+            GenerationWorker::perform_later(&ctx, GenerationWorkerArgs { job_id }).await
+        {
+            error!(job_id, error = %err, "could not hand generation job to the worker");
+        }
+    });
+}
+
+async fn perform(
+    ctx: &AppContext,
+    job: &generation_jobs::Model,
+) -> Result<(Option<i32>, Option<i32>)> {
+    let kind = job.job_kind().map_err(Error::Message)?;
+    let payload = job.job_payload()?;
+
+    match kind {
+        JobKind::Create => {
+            let params = CreateArtParams {
+                options: payload.options.unwrap_or_default(),
+                directives: payload.directives,
+                model: payload.model,
+                prompt: payload.prompt,
+            };
+            let art = art_service::create_art_with_progress(
+                ctx,
+                &params,
+                payload.request_id.unwrap_or(job.uuid),
+            )
+            .await?;
+            Ok((Some(art.id), None))
+        }
+        JobKind::Replace => {
+            let art = load_target_art(ctx, job).await?;
+            let art = art_service::replace_art_with_progress(
+                ctx,
+                art.id,
+                art.uuid,
+                &payload.directives,
+                Some(payload.request_id.unwrap_or(job.uuid)),
+            )
+            .await?;
+            Ok((Some(art.id), None))
+        }
+        JobKind::Rerender => {
+            let art = load_target_art(ctx, job).await?;
+            let art = art_service::rerender_art_image_with_progress(ctx, art.id, art.uuid).await?;
+            Ok((Some(art.id), None))
+        }
+        JobKind::Variation => {
+            let art = load_target_art(ctx, job).await?;
+            let instruction = payload.instruction.ok_or_else(|| {
+                Error::Message(format!("Generation job {} has no instruction", job.id))
+            })?;
+            let varied = art_service::vary_art_with_progress(
+                ctx,
+                art.id,
+                art.uuid,
+                &instruction,
+                payload.as_new,
+                Some(payload.request_id.unwrap_or(job.uuid)),
+            )
+            .await?;
+            Ok((Some(varied.id), None))
+        }
+        JobKind::Mix => {
+            let request_id = payload.request_id.unwrap_or(job.uuid);
+            let options = payload.options.unwrap_or_default();
+            let mix = mix_service::create_mix(ctx, &payload.art_ids, &request_id, &options).await?;
+            Ok((None, Some(mix.id)))
+        }
+    }
+}
+
+async fn load_target_art(ctx: &AppContext, job: &generation_jobs::Model) -> Result<arts::Model> {
+    let art_id = job
+        .art_id
+        .ok_or_else(|| Error::Message(format!("Generation job {} has no target art", job.id)))?;
+
+    arts::Entity::find_by_id(art_id)
+        .one(&ctx.db)
+        .await?
+        .ok_or_else(|| Error::string(&format!("Art with ID {art_id} not found")))
+}
+
+async fn report_retry(ctx: &AppContext, job: &generation_jobs::Model, delay: Duration) {
+    let update = realtime::ProgressUpdate::new(
+        "retrying",
+        format!(
+            "Attempt {} of {} failed. Trying again in {} seconds...",
+            job.attempts,
+            job.max_attempts,
+            delay.as_secs()
+        ),
+    );
+    emit_progress(ctx, job, &update).await;
+}
+
+async fn report_failure(ctx: &AppContext, job: &generation_jobs::Model) {
+    let message = match job.job_kind() {
+        Ok(JobKind::Mix) => "The mix could not be completed. Please try again.",
+        Ok(JobKind::Create) => "The new art could not be created.",
+        Ok(JobKind::Rerender) => {
+            "The image-only regeneration failed before the updated art could be saved."
+        }
+        Ok(JobKind::Variation) => "The variation failed before it could be saved.",
+        _ => "The regeneration failed before the updated art could be saved.",
+    };
+    emit_progress(
+        ctx,
+        job,
+        &realtime::ProgressUpdate::failed("failed", message),
+    )
+    .await;
+}
+
+async fn emit_progress(
+    ctx: &AppContext,
+    job: &generation_jobs::Model,
+    update: &realtime::ProgressUpdate,
+) {
+    match job.job_kind() {
+        Ok(JobKind::Replace | JobKind::Rerender | JobKind::Variation) => {
+            if let Ok(art) = load_target_art(ctx, job).await {
+                realtime::emit_art_replace_progress(&art.uuid, update).await;
+            }
+        }
+        Ok(JobKind::Mix) => {
+            let request_id = job
+                .job_payload()
+                .ok()
+                .and_then(|payload| payload.request_id)
+                .unwrap_or(job.uuid);
+            realtime::emit_mix_progress(&request_id, update).await;
+        }
+        Ok(JobKind::Create) => {
+            if let Some(request_id) = job
+                .job_payload()
+                .ok()
+                .and_then(|payload| payload.request_id)
+            {
+                realtime::emit_art_create_progress(&request_id, update).await;
+            }
+        }
+        Err(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_per_attempt() {
+        assert_eq!(retry_delay(30, 1), Duration::from_secs(30));
+        assert_eq!(retry_delay(30, 2), Duration::from_secs(60));
+        assert_eq!(retry_delay(30, 3), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(30, 40), MAX_BACKOFF);
+        assert_eq!(retry_delay(u64::MAX, 2), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn retry_delay_treats_zero_attempts_as_first() {
+        assert_eq!(retry_delay(10, 0), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,62 @@
+use loco_rs::prelude::*;
+use tracing::error;
+
+use crate::{
+    common::settings::Flow,
+    models::generation_usage::{self, UsageParams},
+    services::ai::traits::CallUsage,
+};
+
+/// `UsageLog` collects the Provider calls of one flow run, labelled by step
+/// (`prompt`, `title`, `image`), until they can be saved against the art or
+/// mix the run produced.
+#[derive(Debug, Default)]
+pub struct UsageLog {
+    calls: Vec<(&'static str, CallUsage)>,
+}
+
+impl UsageLog {
+    pub fn collect(&mut self, step: &'static str, usage: Vec<CallUsage>) {
+        self.calls
+            .extend(usage.into_iter().map(|call| (step, call)));
+    }
+
+    /// `save` records the collected calls, linked to the produced art or mix
+    /// when the run succeeded. Accounting never fails a generation, so errors
+    /// are only logged.
+    pub async fn save(
+        self,
+        ctx: &AppContext,
+        flow: Flow,
+        art_id: Option<i32>,
+        mix_id: Option<i32>,
+    ) {
+        let params = self
+            .calls
+            .into_iter()
+            .map(|(step, call)| UsageParams {
+                flow: flow.as_str().to_string(),
+                step: step.to_string(),
+                model: call.model,
+                prompt_tokens: call
+                    .prompt_tokens
+                    .and_then(|tokens| i32::try_from(tokens).ok()),
+                completion_tokens: call
+                    .completion_tokens
+                    .and_then(|tokens| i32::try_from(tokens).ok()),
+                total_tokens: call
+                    .total_tokens
+                    .and_then(|tokens| i32::try_from(tokens).ok()),
+                cost: call.cost,
+                latency_ms: i32::try_from(call.latency_ms).unwrap_or(i32::MAX),
+                success: call.success,
+                art_id,
+                mix_id,
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(err) = generation_usage::Model::create_many(&ctx.db, &params).await {
+            error!(flow = flow.as_str(), error = %err, "could not record generation usage");
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Keeps the public mix page from spending without bounds. Anyone can start a
+//! mix, and each one costs two text and one image generation, so mixes are
+//! refused past a per-visitor rate, a daily budget shared by every visitor,
+//! or a number of mixes in flight at once.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use axum::http::StatusCode;
+use loco_rs::prelude::*;
+use tracing::warn;
+
+use crate::{
+    common::settings::{MixLimitSettings, Settings},
+    models::generation_jobs::{self, JobKind, JobPayload},
+    services::generation_jobs as jobs,
+};
+
+/// `Refusal` is why a mix was not started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    /// The visitor started too many mixes lately.
+    TooMany,
+    /// Too many mixes are queued or rendering.
+    Busy,
+    /// The daily budget is spent.
+    ClosedForToday,
+}
+
+impl Refusal {
+    /// The progress stage reported with the refusal.
+    #[must_use]
+    pub const fn stage(self) -> &'static str {
+        match self {
+            Self::TooMany => "limited",
+            Self::Busy => "busy",
+            Self::ClosedForToday => "closed",
+        }
+    }
+
+    #[must_use]
+    pub const fn message(self) -> &'static str {
+        match self {
+            Self::TooMany => {
+                "You have mixed a lot lately. Give the studio a little while and try again."
+            }
+            Self::Busy => "The studio is busy painting other mixes. Please try again in a minute.",
+            Self::ClosedForToday => {
+                "The studio is closed for today: every mix it could paint has been painted. Come back tomorrow!"
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn status(self) -> StatusCode {
+        match self {
+            Self::TooMany => StatusCode::TOO_MANY_REQUESTS,
+            Self::Busy | Self::ClosedForToday => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+/// `MixLimiter` remembers when each visitor started their recent mixes, and
+/// lets one mix at a time be admitted and queued. It lives in the shared
+/// store of the app context; clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct MixLimiter {
+    starts: Arc<Mutex<HashMap<IpAddr, VecDeque<Instant>>>>,
+    admission: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl MixLimiter {
+    /// `allows` tells whether `ip` started fewer than `limit` mixes within
+    /// `window` before `now`.
+    fn allows(&self, ip: IpAddr, limit: usize, window: Duration, now: Instant) -> bool {
+        let mut starts = self.starts.lock().unwrap_or_else(PoisonError::into_inner);
+        let recent = |start: &Instant| now.saturating_duration_since(*start) < window;
+        starts.retain(|_, times| times.back().is_some_and(recent));
+
+        starts.get_mut(&ip).is_none_or(|times| {
+            while times.front().is_some_and(|start| !recent(start)) {
+                times.pop_front();
+            }
+            times.len() < limit
+        })
+    }
+
+    /// `record` counts a mix started by `ip` at `now` against its rate.
+    fn record(&self, ip: IpAddr, now: Instant) {
+        self.starts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(ip)
+            .or_default()
+            .push_back(now);
+    }
+}
+
+/// `check_budget` refuses a mix when `started_today` mixes spent the daily
+/// budget or `active` ones fill every slot.
+fn check_budget(limits: &MixLimitSettings, started_today: u64, active: u64) -> Option<Refusal> {
+    if limits.daily_budget > 0 && started_today >= limits.daily_budget {
+        return Some(Refusal::ClosedForToday);
+    }
+    if limits.max_concurrent > 0 && active >= limits.max_concurrent {
+        return Some(Refusal::Busy);
+    }
+    None
+}
+
+/// `enqueue` queues the mix job of `payload` for a visitor at `ip`, unless
+/// the visitor, the budget or the concurrency cap refuses it. This process
+/// admits mixes one at a time, so the jobs counted against the budget and the
+/// cap include every mix admitted before, and a mix only counts against the
+/// visitor's rate once its job is queued. A visitor whose IP is unknown is
+/// only held to the budget and the concurrency cap.
+///
+/// # Errors
+///
+/// When the settings are invalid, on DB query error, or when the job could
+/// not be queued.
+pub async fn enqueue(
+    ctx: &AppContext,
+    ip: Option<IpAddr>,
+    payload: JobPayload,
+) -> Result<std::result::Result<generation_jobs::Model, Refusal>> {
+    let settings = Settings::from_json(
+        &ctx.config
+            .settings
+            .clone()
+            .ok_or(Error::Message("Invalid settings".into()))?,
+    )?;
+    let limits = &settings.mix_limits;
+    let limiter = ctx.shared_store.get::<MixLimiter>().unwrap_or_default();
+    let _admission = limiter.admission.lock().await;
+
+    let started_today = if limits.daily_budget > 0 {
+        let midnight = chrono::Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc();
+        generation_jobs::Model::count_since(&ctx.db, JobKind::Mix, midnight.into()).await?
+    } else {
+        0
+    };
+    let active = if limits.max_concurrent > 0 {
+        generation_jobs::Model::count_active(&ctx.db, JobKind::Mix).await?
+    } else {
+        0
+    };
+    if let Some(refusal) = check_budget(limits, started_today, active) {
+        return Ok(Err(refusal));
+    }
+
+    let rate = Duration::from_secs(limits.window_seconds);
+    match ip {
+        Some(ip)
+            if limits.per_ip > 0 && !limiter.allows(ip, limits.per_ip, rate, Instant::now()) =>
+        {
+            return Ok(Err(Refusal::TooMany));
+        }
+        None if limits.per_ip > 0 => {
+            warn!("mix requested from an unknown IP, holding it to the budget only");
+        }
+        _ => {}
+    }
+
+    let job = jobs::enqueue(ctx, JobKind::Mix, None, payload).await?;
+    if let Some(ip) = ip
+        && limits.per_ip > 0
+    {
+        limiter.record(ip, Instant::now());
+    }
+
+    Ok(Ok(job))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visitors_are_held_to_their_own_rate() {
+        let limiter = MixLimiter::default();
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        let first: IpAddr = "203.0.113.7".parse().unwrap();
+        let second: IpAddr = "198.51.100.2".parse().unwrap();
+
+        assert!(limiter.allows(first, 2, window, start));
+        limiter.record(first, start);
+        // only a recorded start counts
+        assert!(limiter.allows(first, 2, window, start));
+        limiter.record(first, start + Duration::from_secs(1));
+        assert!(!limiter.allows(first, 2, window, start + Duration::from_secs(2)));
+        assert!(limiter.allows(second, 2, window, start + Duration::from_secs(2)));
+        // the first start leaves the window
+        assert!(limiter.allows(first, 2, window, start + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn the_budget_closes_the_studio_before_the_cap_makes_it_busy() {
+        let limits = MixLimitSettings {
+            per_ip: 5,
+            window_seconds: 3600,
+            daily_budget: 10,
+            max_concurrent: 2,
+        };
+        assert_eq!(check_budget(&limits, 9, 1), None);
+        assert_eq!(check_budget(&limits, 9, 2), Some(Refusal::Busy));
+        assert_eq!(check_budget(&limits, 10, 0), Some(Refusal::ClosedForToday));
+
+        let unlimited = MixLimitSettings {
+            daily_budget: 0,
+            max_concurrent: 0,
+            ..limits
+        };
+        assert_eq!(check_budget(&unlimited, 1000, 1000), None);
+    }
+}
@@ -0,0 +1,352 @@
+use loco_rs::prelude::*;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    common::{
+        generation_options::GenerationOptions,
+        image_hash,
+        settings::{DuplicateAction, Flow, MixMode, Settings},
+    },
+    models::{
+        arts,
+        mixes::{self, MixParams, Provenance, PublicationStatus},
+        prompt_templates::TemplateKind,
+        staged_mixes,
+    },
+    services::{
+        ai::traits::ImageGenerator, duplicates, generation_usage::UsageLog, normalize,
+        prompt_templates::PromptTemplates, realtime, service_provider::ServiceProvider, titles,
+    },
+};
+
+fn settings(ctx: &AppContext) -> Result<Settings> {
+    Settings::from_json(
+        &ctx.config
+            .settings
+            .clone()
+            .ok_or(Error::Message("Invalid settings".into()))?,
+    )
+}
+
+/// `SourceSelection` is a visitor's choice of arts to mix, without repeats,
+/// and what keeps it from being mixed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SourceSelection {
+    pub art_ids: Vec<i32>,
+    pub errors: Vec<String>,
+}
+
+impl SourceSelection {
+    #[must_use]
+    pub const fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// `count_errors` holds `art_ids` to the configured bounds.
+fn count_errors(art_ids: &[i32], bounds: (usize, usize)) -> Option<String> {
+    let (min, max) = bounds;
+    if art_ids.len() < min {
+        Some(format!("Pick at least {min} different arts to mix."))
+    } else if art_ids.len() > max {
+        Some(format!("Pick at most {max} arts to mix."))
+    } else {
+        None
+    }
+}
+
+/// `check_sources` drops repeated ids from `art_ids`, keeping their order,
+/// and checks there are as many as the settings allow and that each is a
+/// published art.
+///
+/// # Errors
+///
+/// When the settings are invalid or on DB query error.
+pub async fn check_sources(ctx: &AppContext, art_ids: &[i32]) -> Result<SourceSelection> {
+    let settings = settings(ctx)?;
+    let mut unique = Vec::with_capacity(art_ids.len());
+    for id in art_ids {
+        if !unique.contains(id) {
+            unique.push(*id);
+        }
+    }
+
+    let mut errors = Vec::new();
+    if let Some(error) = count_errors(&unique, settings.mix_sources.bounds()) {
+        errors.push(error);
+    } else {
+        let found = arts::Model::find_published_in(&ctx.db, unique.clone()).await?;
+        let missing = unique
+            .iter()
+            .filter(|id| !found.iter().any(|art| art.id == **id))
+            .map(|id| format!("#{id}"))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            errors.push(format!(
+                "These arts can't be mixed: {}.",
+                missing.join(", ")
+            ));
+        }
+    }
+
+    Ok(SourceSelection {
+        art_ids: unique,
+        errors,
+    })
+}
+
+/// `create_mix` writes a shared prompt and title from the given arts, renders
+/// the mixed image and links it to its sources, reporting each stage to the
+/// `request_id` progress room. In the `references` mix mode the source images
+/// go along as references, so Models with image input can inherit from them.
+/// The image is rendered with `options` over the configured image options.
+///
+/// # Errors
+///
+/// When the generators can't be configured, any generation step fails, or the
+/// mix could not be saved.
+pub async fn create_mix(
+    ctx: &AppContext,
+    art_ids: &[i32],
+    request_id: &Uuid,
+    options: &GenerationOptions,
+) -> Result<mixes::Model> {
+    let mut usage = UsageLog::default();
+    let result = create_mix_inner(ctx, art_ids, request_id, options, &mut usage).await;
+    usage
+        .save(ctx, Flow::Mix, None, result.as_ref().ok().map(|mix| mix.id))
+        .await;
+    result
+}
+
+async fn create_mix_inner(
+    ctx: &AppContext,
+    art_ids: &[i32],
+    request_id: &Uuid,
+    options: &GenerationOptions,
+    usage: &mut UsageLog,
+) -> Result<mixes::Model> {
+    realtime::emit_mix_progress(
+        request_id,
+        &realtime::ProgressUpdate::new("preparing", "Collecting the selected source images..."),
+    )
+    .await;
+
+    // a retried request picks up where the last attempt stopped
+    if let Some(mix) = mixes::Model::find_by_request_id(&ctx.db, *request_id).await? {
+        report_saved(request_id, &mix).await;
+        return Ok(mix);
+    }
+    if let Some(staged) = staged_mixes::Model::find_by_request_id(&ctx.db, *request_id).await? {
+        return save_staged(ctx, request_id, &staged).await;
+    }
+
+    let settings = settings(ctx)?;
+    let options = options.clone().or(&settings.image_options);
+    options.validate().map_err(Error::Message)?;
+
+    // a source unpublished or deleted since the mix was asked for is left out
+    let arts = arts::Model::find_published_in(&ctx.db, art_ids.to_vec()).await?;
+    if arts.len() < settings.mix_sources.bounds().0 {
+        return Err(Error::Message(
+            "Too few of the selected arts are still available to mix.".to_string(),
+        ));
+    }
+
+    let img_gen = ServiceProvider::random_img_service(&settings, Flow::Mix)
+        .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?;
+    let mut text_gen = ServiceProvider::random_txt_service(&settings, Flow::Mix)
+        .map_err(|e| Error::Message(format!("Unable to configure text generator: {e}")))?;
+
+    realtime::emit_mix_progress(
+        request_id,
+        &realtime::ProgressUpdate::new(
+            "prompting",
+            "Writing a shared prompt from the chosen artworks...",
+        ),
+    )
+    .await;
+
+    let templates = PromptTemplates::load(&ctx.db).await?;
+    let prompt_meta_prompt = templates.mix_image_prompt(&arts);
+
+    let prompt = text_gen.generate(&prompt_meta_prompt).await;
+    usage.collect("prompt", text_gen.take_usage());
+    let prompt = normalize::prompt(
+        &prompt.map_err(|e| Error::Message(format!("Unable to gen prompt for mix: {e}")))?,
+    );
+    let prompt_model = text_gen.model_name();
+
+    realtime::emit_mix_progress(
+        request_id,
+        &realtime::ProgressUpdate::new(
+            "titling",
+            "Prompt ready. Creating a title for the new mix...",
+        ),
+    )
+    .await;
+
+    let title_prompt = templates.mix_title_prompt(&prompt, &arts);
+
+    let taken = titles::taken_titles(&ctx.db, None).await?;
+    let title = titles::generate_title(
+        text_gen.as_mut(),
+        &title_prompt,
+        &settings.titles,
+        &taken,
+        usage,
+    )
+    .await?;
+    let title_model = text_gen.model_name();
+
+    info!(%title, %prompt, "generating mix");
+
+    realtime::emit_mix_progress(
+        request_id,
+        &realtime::ProgressUpdate::new(
+            "rendering",
+            "Title locked in. Rendering the mixed image now...",
+        ),
+    )
+    .await;
+
+    let (image, model) =
+        render_distinct_image(ctx, &settings, img_gen, &prompt, &arts, &options, usage).await?;
+
+    let status = PublicationStatus::for_new_work(settings.require_approval);
+    let staged = staged_mixes::Model::stage(
+        &ctx.db,
+        *request_id,
+        &MixParams {
+            image,
+            prompt,
+            title,
+            model,
+            provenance: Provenance {
+                prompt_model: Some(prompt_model),
+                title_model: Some(title_model),
+                prompt_meta_prompt: Some(prompt_meta_prompt),
+                title_meta_prompt: Some(title_prompt),
+                prompt_template_id: templates.get(TemplateKind::MixImage).id,
+                title_template_id: templates.get(TemplateKind::Title).id,
+            },
+            status,
+            request_id: Some(*request_id),
+        },
+        &arts.iter().map(|art| art.id).collect::<Vec<_>>(),
+    )
+    .await?;
+
+    save_staged(ctx, request_id, &staged).await
+}
+
+/// `save_staged` saves a staged render as a mix linked to its sources. When
+/// the save fails the render stays staged, for the next attempt of the job or
+/// the `repair_mixes` task to save.
+async fn save_staged(
+    ctx: &AppContext,
+    request_id: &Uuid,
+    staged: &staged_mixes::Model,
+) -> Result<mixes::Model> {
+    realtime::emit_mix_progress(
+        request_id,
+        &realtime::ProgressUpdate::new(
+            "saving",
+            "Image finished. Saving the mix and linking the source art...",
+        ),
+    )
+    .await;
+
+    let mix = match staged.save(&ctx.db).await {
+        Ok(mix) => mix,
+        Err(err) => {
+            if let Err(record_err) = staged.set_last_error(&ctx.db, &err.to_string()).await {
+                error!(error = %record_err, "could not record why a staged mix was not saved");
+            }
+            return Err(err.into());
+        }
+    };
+
+    report_saved(request_id, &mix).await;
+    Ok(mix)
+}
+
+async fn report_saved(request_id: &Uuid, mix: &mixes::Model) {
+    let update = if mix.status == PublicationStatus::Published.as_str() {
+        realtime::ProgressUpdate::done("complete", "Your mix is ready. Opening it now...")
+            .with_redirect_to(format!("/mix/{}", mix.id))
+    } else {
+        realtime::ProgressUpdate::done(
+            "complete",
+            "Your mix is saved. It will appear in the gallery once it has been approved.",
+        )
+    };
+    realtime::emit_mix_progress(request_id, &update).await;
+}
+
+/// `render_distinct_image` renders the mixed image and checks it against the
+/// perceptual hashes of the saved arts and mixes. A near-duplicate is logged;
+/// in the `reject` duplicate mode it is rendered again with a freshly drawn
+/// Model, up to `settings.duplicates.attempts` renders. It returns the image
+/// and the Model that drew it.
+///
+/// # Errors
+///
+/// When a render fails, or every render was rejected as a near-duplicate.
+async fn render_distinct_image(
+    ctx: &AppContext,
+    settings: &Settings,
+    img_gen: Box<dyn ImageGenerator + Send>,
+    prompt: &str,
+    arts: &[arts::Model],
+    options: &GenerationOptions,
+    usage: &mut UsageLog,
+) -> Result<(String, String)> {
+    let works = duplicates::load(&ctx.db).await?;
+    let reject = settings.duplicates.action == DuplicateAction::Reject;
+    let attempts = if reject {
+        settings.duplicates.attempts.max(1)
+    } else {
+        1
+    };
+    let mut img_gen = Some(img_gen);
+    let mut last_match = None;
+
+    for attempt in 1..=attempts {
+        let img_gen = match img_gen.take() {
+            Some(img_gen) => img_gen,
+            None => ServiceProvider::random_img_service(settings, Flow::Mix)
+                .map_err(|e| Error::Message(format!("Unable to configure image generator: {e}")))?,
+        };
+        let image = match settings.mix_mode {
+            MixMode::References => {
+                let references = arts.iter().map(|art| art.image.clone()).collect::<Vec<_>>();
+                img_gen
+                    .generate_with_references(prompt, &references, options)
+                    .await
+            }
+            MixMode::Text => img_gen.generate(prompt, options).await,
+        };
+        usage.collect("image", img_gen.take_usage());
+        let image = image.map_err(|e| Error::Message(format!("Unable to generate image: {e}")))?;
+
+        let found = image_hash::dhash_base64(&image).and_then(|hash| {
+            duplicates::nearest(hash, &works, settings.duplicates.threshold, None)
+        });
+        let Some(found) = found else {
+            return Ok((image, img_gen.model_name()));
+        };
+        warn!(
+            attempt,
+            duplicate_of = %found,
+            "mix render is a near-duplicate of an existing work"
+        );
+        if !reject {
+            return Ok((image, img_gen.model_name()));
+        }
+        last_match = Some(found);
+    }
+
+    Err(duplicates::all_rejected(last_match.as_ref(), attempts))
+}
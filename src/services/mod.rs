@@ -1,5 +1,15 @@
 pub mod ai;
 pub mod art_service;
 pub mod backoffice_auth;
+pub mod diversity;
+pub mod duplicates;
+pub mod generation_jobs;
+pub mod generation_usage;
+pub mod mix_limits;
+pub mod mix_service;
+pub mod normalize;
+pub mod prompt_templates;
 pub mod realtime;
+pub mod scheduler;
 pub mod service_provider;
+pub mod titles;
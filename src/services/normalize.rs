@@ -0,0 +1,173 @@
+//! Cleans up the text Models answer with before it is saved. The meta-prompts
+//! ask for *just* a prompt in single quotes or *just* a title, but answers
+//! still come back quoted, in markdown, labelled ("Title: ...") or followed
+//! by an explanation.
+
+/// Pairs of characters a Model may wrap its whole answer in.
+const WRAPPERS: &[(char, char)] = &[
+    ('\'', '\''),
+    ('"', '"'),
+    ('`', '`'),
+    ('‘', '’'),
+    ('“', '”'),
+    ('«', '»'),
+    ('*', '*'),
+    ('_', '_'),
+];
+
+/// Labels a Model may put in front of its answer.
+const PREFIXES: &[&str] = &["title", "prompt", "image prompt"];
+
+/// Punctuation a title never ends with. `?` and `!` are kept, they can be
+/// part of the title.
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '-', '–', '—'];
+
+/// `title` normalizes a generated title: the first line of the answer,
+/// without markdown, label, wrapping quotes and trailing punctuation, and
+/// with its whitespace collapsed.
+#[must_use]
+pub fn title(raw: &str) -> String {
+    let line = raw
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    let mut text = clean(line);
+    loop {
+        let trimmed = unwrap(text.trim_end_matches(TRAILING_PUNCTUATION).trim_end());
+        if trimmed == text {
+            break;
+        }
+        text = trimmed;
+    }
+    fallback(text, raw)
+}
+
+/// `prompt` normalizes a generated image prompt: without markdown, label
+/// and wrapping quotes, and with its whitespace collapsed.
+#[must_use]
+pub fn prompt(raw: &str) -> String {
+    fallback(clean(raw), raw)
+}
+
+/// `clean` collapses whitespace, drops markdown markers and strips labels
+/// and wrapping quotes until none is left.
+fn clean(raw: &str) -> String {
+    let mut text = collapse_whitespace(&strip_markdown(raw));
+    loop {
+        let stripped = unwrap(strip_prefix(&text));
+        if stripped == text {
+            return text;
+        }
+        text = stripped;
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `strip_markdown` drops bold markers, inline code fences and heading or
+/// list markers at the start of lines.
+fn strip_markdown(text: &str) -> String {
+    text.replace("**", "")
+        .replace("__", "")
+        .replace("```", "")
+        .lines()
+        .map(|line| {
+            line.trim_start()
+                .trim_start_matches('#')
+                .trim_start_matches(['-', '>', '•'])
+                .trim_start()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `strip_prefix` removes a leading "Title:"-style label, in any case.
+fn strip_prefix(text: &str) -> &str {
+    PREFIXES
+        .iter()
+        .find_map(|prefix| {
+            let head = text.get(..prefix.len())?;
+            let rest = text[prefix.len()..].trim_start();
+            (head.eq_ignore_ascii_case(prefix) && rest.starts_with(':'))
+                .then(|| rest[1..].trim_start())
+        })
+        .unwrap_or(text)
+}
+
+/// `unwrap` removes one pair of wrapping quotes (or emphasis markers) around
+/// the whole text.
+fn unwrap(text: &str) -> String {
+    let mut chars = text.chars();
+    let (Some(first), Some(last)) = (chars.next(), chars.next_back()) else {
+        return text.to_string();
+    };
+    if WRAPPERS.contains(&(first, last)) {
+        chars.as_str().trim().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Keeps the collapsed answer when cleaning it leaves nothing.
+fn fallback(text: String, raw: &str) -> String {
+    if text.is_empty() {
+        collapse_whitespace(raw)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_lose_quotes_labels_and_trailing_punctuation() {
+        assert_eq!(title("\"Glowing Grove\""), "Glowing Grove");
+        assert_eq!(title("Title: Glowing Grove."), "Glowing Grove");
+        assert_eq!(title("**Title:** 'Glowing Grove'"), "Glowing Grove");
+        assert_eq!(title("  “Glowing   Grove”  "), "Glowing Grove");
+        assert_eq!(title("# *Glowing Grove*"), "Glowing Grove");
+        assert_eq!(title("'Glowing Grove.'"), "Glowing Grove");
+    }
+
+    #[test]
+    fn titles_keep_inner_quotes_and_expressive_punctuation() {
+        assert_eq!(title("Dali's Dream"), "Dali's Dream");
+        assert_eq!(title("Who Goes There?"), "Who Goes There?");
+        assert_eq!(title("'Tis the Season"), "'Tis the Season");
+    }
+
+    #[test]
+    fn titles_take_the_first_line() {
+        assert_eq!(
+            title("\nLantern Tides\n\nThis title evokes the glow of..."),
+            "Lantern Tides"
+        );
+    }
+
+    #[test]
+    fn prompts_lose_quotes_and_collapse_whitespace() {
+        assert_eq!(
+            prompt("'Ink and wash drawing of a harbor,\n  at dawn.'"),
+            "Ink and wash drawing of a harbor, at dawn."
+        );
+        assert_eq!(
+            prompt("Prompt: `Charcoal drawing of a library.`"),
+            "Charcoal drawing of a library."
+        );
+        assert_eq!(
+            prompt("Risograph print of a night market."),
+            "Risograph print of a night market."
+        );
+    }
+
+    #[test]
+    fn empty_results_fall_back_to_the_answer() {
+        assert_eq!(title("''"), "''");
+        assert_eq!(prompt("  "), "");
+    }
+}
@@ -0,0 +1,328 @@
+use loco_rs::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    models::{
+        arts::{self, ModelVec},
+        prompt_templates::{self, TemplateKind},
+    },
+    tasks::art_prompts::{
+        IMAGE_PROMPT, MIX_IMAGE_PROMPT, SAMPLE_PROMPTS, SAMPLE_TITLES, TITLE_PROMPT,
+        VARIATION_PROMPT,
+    },
+};
+
+/// `builtin` is the compiled-in text of `kind`, used while no version of it
+/// is active.
+#[must_use]
+pub const fn builtin(kind: TemplateKind) -> &'static str {
+    match kind {
+        TemplateKind::Image => IMAGE_PROMPT,
+        TemplateKind::Title => TITLE_PROMPT,
+        TemplateKind::MixImage => MIX_IMAGE_PROMPT,
+        TemplateKind::Variation => VARIATION_PROMPT,
+        TemplateKind::SamplePrompts => SAMPLE_PROMPTS,
+        TemplateKind::SampleTitles => SAMPLE_TITLES,
+    }
+}
+
+/// `ResolvedTemplate` is the text a generation renders for one kind: the
+/// active version, or the built-in one (without id or version).
+#[derive(Clone, Debug, Serialize)]
+pub struct ResolvedTemplate {
+    pub kind: TemplateKind,
+    pub id: Option<i32>,
+    pub version: Option<i32>,
+    pub body: String,
+}
+
+impl ResolvedTemplate {
+    fn builtin(kind: TemplateKind) -> Self {
+        Self {
+            kind,
+            id: None,
+            version: None,
+            body: builtin(kind).to_string(),
+        }
+    }
+}
+
+/// `PromptTemplates` are the templates of one generation run, loaded once so
+/// a run renders all of its meta-prompts from the same versions.
+#[derive(Clone, Debug)]
+pub struct PromptTemplates {
+    image: ResolvedTemplate,
+    title: ResolvedTemplate,
+    mix_image: ResolvedTemplate,
+    variation: ResolvedTemplate,
+    sample_prompts: ResolvedTemplate,
+    sample_titles: ResolvedTemplate,
+}
+
+impl PromptTemplates {
+    fn from_fn(resolve: impl Fn(TemplateKind) -> ResolvedTemplate) -> Self {
+        Self {
+            image: resolve(TemplateKind::Image),
+            title: resolve(TemplateKind::Title),
+            mix_image: resolve(TemplateKind::MixImage),
+            variation: resolve(TemplateKind::Variation),
+            sample_prompts: resolve(TemplateKind::SamplePrompts),
+            sample_titles: resolve(TemplateKind::SampleTitles),
+        }
+    }
+
+    /// `load` resolves every kind to its active version or built-in text.
+    ///
+    /// # Errors
+    ///
+    /// On DB query error.
+    pub async fn load(db: &DatabaseConnection) -> Result<Self> {
+        let active = prompt_templates::Model::find_all_active(db).await?;
+        Ok(Self::from_fn(|kind| {
+            active
+                .iter()
+                .find(|template| template.kind == kind.as_str())
+                .map_or_else(
+                    || ResolvedTemplate::builtin(kind),
+                    |template| ResolvedTemplate {
+                        kind,
+                        id: Some(template.id),
+                        version: Some(template.version),
+                        body: template.body.clone(),
+                    },
+                )
+        }))
+    }
+
+    /// `builtin` is the set of built-in templates.
+    #[must_use]
+    pub fn builtin() -> Self {
+        Self::from_fn(ResolvedTemplate::builtin)
+    }
+
+    #[must_use]
+    pub const fn get(&self, kind: TemplateKind) -> &ResolvedTemplate {
+        match kind {
+            TemplateKind::Image => &self.image,
+            TemplateKind::Title => &self.title,
+            TemplateKind::MixImage => &self.mix_image,
+            TemplateKind::Variation => &self.variation,
+            TemplateKind::SamplePrompts => &self.sample_prompts,
+            TemplateKind::SampleTitles => &self.sample_titles,
+        }
+    }
+
+    fn body(&self, kind: TemplateKind) -> &str {
+        &self.get(kind).body
+    }
+
+    /// `with_draft` swaps in an unsaved `body` for `kind`, for dry runs.
+    #[must_use]
+    pub fn with_draft(mut self, kind: TemplateKind, body: &str) -> Self {
+        let draft = ResolvedTemplate {
+            kind,
+            id: None,
+            version: None,
+            body: body.to_string(),
+        };
+        match kind {
+            TemplateKind::Image => self.image = draft,
+            TemplateKind::Title => self.title = draft,
+            TemplateKind::MixImage => self.mix_image = draft,
+            TemplateKind::Variation => self.variation = draft,
+            TemplateKind::SamplePrompts => self.sample_prompts = draft,
+            TemplateKind::SampleTitles => self.sample_titles = draft,
+        }
+        self
+    }
+
+    /// The meta-prompt asking for a new art's image prompt, varying from a
+    /// random and the latest arts, or from the samples in an empty gallery.
+    #[must_use]
+    pub fn create_image_prompt(
+        &self,
+        random_arts: &[arts::Model],
+        latest_arts: &[arts::Model],
+    ) -> String {
+        if random_arts.is_empty() && latest_arts.is_empty() {
+            return self.sample_image_prompt();
+        }
+
+        let mut prompts = Vec::new();
+
+        if !random_arts.is_empty() {
+            prompts.push("Previous prompts for inspiration (try to vary from these):".to_string());
+            prompts.extend(
+                random_arts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, art)| format!(" - inspiration {}: {}", i + 1, art.prompt.clone())),
+            );
+        }
+
+        if !latest_arts.is_empty() {
+            prompts.push(
+                "\nRecent prompts to actively differentiate from (be distinctly different from these):"
+                    .to_string(),
+            );
+            prompts.extend(
+                latest_arts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, art)| format!(" - recent {}: {}", i + 1, art.prompt.clone())),
+            );
+        }
+
+        self.body(TemplateKind::Image)
+            .replace("{{PROMPTS}}", &prompts.join("\n"))
+    }
+
+    /// The meta-prompt asking for a new art's title.
+    #[must_use]
+    pub fn create_title_prompt(
+        &self,
+        desc: &str,
+        random_arts: &[arts::Model],
+        latest_arts: &[arts::Model],
+    ) -> String {
+        if random_arts.is_empty() && latest_arts.is_empty() {
+            return self.sample_title_prompt(desc);
+        }
+
+        let titles = random_arts
+            .iter()
+            .chain(latest_arts.iter())
+            .map(|art| art.title.clone())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        self.title_prompt(&titles, desc)
+    }
+
+    /// The meta-prompt asking for a replacement image prompt, varying from
+    /// the recent arts (the samples while there is at most one art).
+    #[must_use]
+    pub fn replace_image_prompt(&self, recent_arts: &[arts::Model]) -> String {
+        if recent_arts.len() <= 1 {
+            return self.sample_image_prompt();
+        }
+
+        let prompts = recent_arts
+            .iter()
+            .enumerate()
+            .map(|(i, art)| format!(" - prompt {}: {}", i + 1, art.prompt.clone()))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        self.body(TemplateKind::Image)
+            .replace("{{PROMPTS}}", &prompts)
+    }
+
+    /// The meta-prompt asking for a replacement title.
+    #[must_use]
+    pub fn replace_title_prompt(&self, desc: &str, recent_arts: &[arts::Model]) -> String {
+        if recent_arts.len() <= 1 {
+            return self.sample_title_prompt(desc);
+        }
+
+        let titles = recent_arts
+            .iter()
+            .map(|art| art.title.clone())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        self.title_prompt(&titles, desc)
+    }
+
+    /// The meta-prompt asking for a prompt blending the source arts of a mix.
+    #[must_use]
+    pub fn mix_image_prompt(&self, arts: &[arts::Model]) -> String {
+        self.body(TemplateKind::MixImage)
+            .replace("{{PROMPTS}}", &arts.to_formatted_prompts())
+    }
+
+    /// The meta-prompt asking for a mix title, inspired by its sources' titles.
+    #[must_use]
+    pub fn mix_title_prompt(&self, desc: &str, arts: &[arts::Model]) -> String {
+        self.title_prompt(&arts.to_formatted_titles(), desc)
+    }
+
+    /// The meta-prompt asking to rewrite an art's prompt as `instruction`
+    /// tells.
+    #[must_use]
+    pub fn variation_prompt(&self, prompt: &str, instruction: &str) -> String {
+        self.body(TemplateKind::Variation)
+            .replace("{{PROMPT}}", prompt)
+            .replace("{{INSTRUCTION}}", instruction)
+    }
+
+    fn sample_image_prompt(&self) -> String {
+        self.body(TemplateKind::Image)
+            .replace("{{PROMPTS}}", self.body(TemplateKind::SamplePrompts))
+    }
+
+    fn sample_title_prompt(&self, desc: &str) -> String {
+        self.title_prompt(self.body(TemplateKind::SampleTitles), desc)
+    }
+
+    fn title_prompt(&self, titles: &str, desc: &str) -> String {
+        self.body(TemplateKind::Title)
+            .replace("{{TITLES}}", titles)
+            .replace("{{DESCRIPTION}}", desc)
+    }
+}
+
+/// `Preview` is a draft template rendered the way the next generation would
+/// render it.
+#[derive(Debug, Serialize)]
+pub struct Preview {
+    pub context: &'static str,
+    pub meta_prompt: String,
+}
+
+/// `preview` dry-runs `body` as the `kind` template against the current
+/// gallery, without saving it or calling any Model. The sample kinds are
+/// shown the way an empty gallery would use them.
+///
+/// # Errors
+///
+/// On DB query error.
+pub async fn preview(db: &DatabaseConnection, kind: TemplateKind, body: &str) -> Result<Preview> {
+    let templates = PromptTemplates::load(db).await?.with_draft(kind, body);
+    let random_arts = arts::Model::find_n_random(db, 5).await?;
+    let latest_arts = arts::Model::find_n_latest(db, 5).await?;
+    let description = latest_arts.first().map_or_else(
+        || "(the image prompt written for the new art)".to_string(),
+        |art| art.prompt.clone(),
+    );
+
+    Ok(match kind {
+        TemplateKind::Image => Preview {
+            context: "Creating a new art from 5 random and the 5 latest arts",
+            meta_prompt: templates.create_image_prompt(&random_arts, &latest_arts),
+        },
+        TemplateKind::Title => Preview {
+            context: "Titling a new art described by the latest art's prompt",
+            meta_prompt: templates.create_title_prompt(&description, &random_arts, &latest_arts),
+        },
+        TemplateKind::MixImage => {
+            let sources = latest_arts.iter().take(3).cloned().collect::<Vec<_>>();
+            Preview {
+                context: "Mixing the 3 latest arts",
+                meta_prompt: templates.mix_image_prompt(&sources),
+            }
+        }
+        TemplateKind::Variation => Preview {
+            context: "Varying the latest art as told by a sample instruction",
+            meta_prompt: templates.variation_prompt(&description, "make it autumn"),
+        },
+        TemplateKind::SamplePrompts => Preview {
+            context: "Creating the first art of an empty gallery",
+            meta_prompt: templates.create_image_prompt(&[], &[]),
+        },
+        TemplateKind::SampleTitles => Preview {
+            context: "Titling the first art of an empty gallery",
+            meta_prompt: templates.create_title_prompt(&description, &[], &[]),
+        },
+    })
+}
@@ -78,6 +78,11 @@ pub fn art_replace_room(art_uuid: &Uuid) -> String {
     format!("art-replace:{art_uuid}")
 }
 
+#[must_use]
+pub fn art_create_room(request_id: &Uuid) -> String {
+    format!("art-create:{request_id}")
+}
+
 #[must_use]
 pub fn parse_subscription_id(value: &str) -> Option<Uuid> {
     Uuid::parse_str(value).ok()
@@ -91,6 +96,10 @@ pub async fn emit_art_replace_progress(art_uuid: &Uuid, update: &ProgressUpdate)
     emit("art-replace-progress", art_replace_room(art_uuid), update).await;
 }
 
+pub async fn emit_art_create_progress(request_id: &Uuid, update: &ProgressUpdate) {
+    emit("art-create-progress", art_create_room(request_id), update).await;
+}
+
 async fn emit(event: &str, room: String, update: &ProgressUpdate) {
     let Some(io) = socket_io() else {
         return;
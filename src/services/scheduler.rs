@@ -0,0 +1,490 @@
+//! Runs generation jobs on cron schedules inside the server process, so the
+//! daily art doesn't depend on someone running `cargo loco task create_art`.
+//! Jobs go through the same persisted generation jobs as the tasks, run one
+//! at a time, and every run (including the next planned one) is recorded in
+//! `scheduled_runs` for the backoffice.
+//!
+//! Enable it on a single server process only: overlapping runs are guarded
+//! within the process, not across processes. A starting scheduler marks the
+//! runs left unfinished past the run timeout as interrupted.
+
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::{LazyLock, Mutex, PoisonError},
+};
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use cron::Schedule;
+use loco_rs::prelude::*;
+use tracing::{error, info, warn};
+
+use crate::{
+    common::settings::{QuietHours, ScheduledJob, ScheduledJobKind, SchedulerSettings},
+    models::{
+        arts,
+        generation_jobs::{JobKind, JobPayload},
+        scheduled_runs::{self, RunStatus},
+    },
+    services::generation_jobs,
+};
+
+/// Upper bound for the delay between two attempts to plan a job's next run.
+const MAX_PLAN_BACKOFF: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Names of the jobs running in this process.
+static RUNNING: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Mutex::default);
+
+/// Holds a job's name in [`RUNNING`] for as long as its run lasts.
+struct RunGuard(String);
+
+impl RunGuard {
+    /// `acquire` is `None` when the job is already running.
+    fn acquire(name: &str) -> Option<Self> {
+        let mut running = RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+        running
+            .insert(name.to_string())
+            .then(|| Self(name.to_string()))
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        RUNNING
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.0);
+    }
+}
+
+/// `Window` is a parsed quiet hours window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Window {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Window {
+    /// Whether `time` falls inside the window; a window whose end is before
+    /// its start wraps past midnight.
+    #[must_use]
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// `defer` moves `at` to the end of the window when it falls inside it.
+    #[must_use]
+    pub fn defer(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        if !self.contains(at.time()) {
+            return at;
+        }
+        let end = at.date_naive().and_time(self.end).and_utc();
+        if end > at {
+            end
+        } else {
+            end + Duration::days(1)
+        }
+    }
+}
+
+/// `Plan` is an enabled job with its parsed schedule.
+pub struct Plan {
+    pub job: ScheduledJob,
+    schedule: Schedule,
+}
+
+impl Plan {
+    /// `next_run` is the first cron time after `after`, delayed by `jitter`
+    /// seconds and deferred past the quiet hours. `None` when the schedule
+    /// has no further time.
+    #[must_use]
+    pub fn next_run(
+        &self,
+        after: DateTime<Utc>,
+        jitter: u64,
+        quiet_hours: Option<Window>,
+    ) -> Option<DateTime<Utc>> {
+        let due = self.schedule.after(&after).next()?
+            + Duration::seconds(i64::try_from(jitter).unwrap_or(i64::MAX / 1000));
+        Some(quiet_hours.map_or(due, |window| window.defer(due)))
+    }
+}
+
+/// `parse_cron` reads a cron expression with 5 fields, or 6 or 7 with seconds
+/// (and years).
+///
+/// # Errors
+///
+/// When the expression is malformed.
+pub fn parse_cron(expression: &str) -> std::result::Result<Schedule, String> {
+    let expression = expression.trim();
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {expression}")
+    } else {
+        expression.to_string()
+    };
+    Schedule::from_str(&normalized)
+        .map_err(|e| format!("Invalid cron expression {expression:?}: {e}"))
+}
+
+/// `parse_quiet_hours` reads a `HH:MM` to `HH:MM` window.
+///
+/// # Errors
+///
+/// When either time is malformed.
+pub fn parse_quiet_hours(quiet_hours: &QuietHours) -> std::result::Result<Window, String> {
+    let time = |value: &str| {
+        NaiveTime::parse_from_str(value.trim(), "%H:%M")
+            .map_err(|e| format!("Invalid quiet hours time {value:?}, expected HH:MM: {e}"))
+    };
+    Ok(Window {
+        start: time(&quiet_hours.start)?,
+        end: time(&quiet_hours.end)?,
+    })
+}
+
+/// `plans` parses the enabled jobs of the settings.
+///
+/// # Errors
+///
+/// When a cron expression is malformed or two jobs share a name.
+pub fn plans(settings: &SchedulerSettings) -> std::result::Result<Vec<Plan>, String> {
+    let mut names = HashSet::new();
+    let mut plans = Vec::new();
+    for job in settings.jobs.iter().filter(|job| job.enabled) {
+        if !names.insert(job.name.as_str()) {
+            return Err(format!("Duplicate scheduled job name {:?}", job.name));
+        }
+        plans.push(Plan {
+            schedule: parse_cron(&job.cron)?,
+            job: job.clone(),
+        });
+    }
+    Ok(plans)
+}
+
+/// `start` validates the schedule and spawns the scheduler loop. Runs left
+/// unfinished for longer than the run timeout are marked as interrupted
+/// first; only one process may run the scheduler.
+///
+/// # Errors
+///
+/// When the schedule is invalid, or on DB errors.
+pub async fn start(ctx: &AppContext, settings: &SchedulerSettings) -> Result<()> {
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let timeout =
+        Duration::seconds(i64::try_from(settings.run_timeout_seconds).unwrap_or(i64::MAX / 1000));
+    let stale_before = Utc::now()
+        .checked_sub_signed(timeout)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let interrupted =
+        scheduled_runs::Model::interrupt_unfinished(&ctx.db, stale_before.into()).await?;
+    if interrupted > 0 {
+        warn!(interrupted, "marked stale scheduled runs as interrupted");
+    }
+
+    let plans = plans(settings).map_err(Error::Message)?;
+    let quiet_hours = settings
+        .quiet_hours
+        .as_ref()
+        .map(parse_quiet_hours)
+        .transpose()
+        .map_err(Error::Message)?;
+    info!(jobs = plans.len(), "starting the generation scheduler");
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move { run_loop(&ctx, plans, quiet_hours).await });
+    Ok(())
+}
+
+/// Runs the due jobs one after the other, forever. After each run only that
+/// job is planned again, from the time it finished, so a job never overlaps
+/// itself and a late job runs as soon as the previous one is done.
+async fn run_loop(ctx: &AppContext, plans: Vec<Plan>, quiet_hours: Option<Window>) {
+    let mut planned = Vec::new();
+    for plan in plans {
+        if let Some(next) = plan_next_retrying(ctx, &plan, quiet_hours).await {
+            planned.push((plan, next));
+        }
+    }
+
+    loop {
+        let Some(index) = planned
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, run))| run.scheduled_for)
+            .map(|(index, _)| index)
+        else {
+            info!("no scheduled job has a further run");
+            return;
+        };
+
+        let (plan, run) = planned.swap_remove(index);
+        let delay = (run.scheduled_for.to_utc() - Utc::now())
+            .to_std()
+            .unwrap_or_default();
+        tokio::time::sleep(delay).await;
+
+        if let Err(err) = run_job(ctx, &plan.job.name, plan.job.kind, Some(run.id)).await {
+            error!(job = plan.job.name, error = %err, "could not record scheduled run");
+        }
+        if let Some(next) = plan_next_retrying(ctx, &plan, quiet_hours).await {
+            planned.push((plan, next));
+        }
+    }
+}
+
+/// `plan_next_retrying` records the next run of `plan`, trying again with
+/// backoff for as long as the DB fails, so a passing outage doesn't stop the
+/// scheduler.
+async fn plan_next_retrying(
+    ctx: &AppContext,
+    plan: &Plan,
+    quiet_hours: Option<Window>,
+) -> Option<scheduled_runs::Model> {
+    let mut attempts = 0;
+    loop {
+        match plan_next(ctx, plan, quiet_hours).await {
+            Ok(next) => return next,
+            Err(err) => {
+                attempts += 1;
+                let delay = generation_jobs::retry_delay(5, attempts).min(MAX_PLAN_BACKOFF);
+                error!(job = plan.job.name, attempt = attempts, error = %err, retry_in = ?delay, "could not plan the next scheduled run");
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Records the next run of `plan`.
+async fn plan_next(
+    ctx: &AppContext,
+    plan: &Plan,
+    quiet_hours: Option<Window>,
+) -> Result<Option<scheduled_runs::Model>> {
+    let jitter = fastrand::u64(0..=plan.job.jitter_seconds);
+    let Some(next) = plan.next_run(Utc::now(), jitter, quiet_hours) else {
+        return Ok(None);
+    };
+    info!(job = plan.job.name, next = %next, "scheduled next run");
+
+    Ok(Some(
+        scheduled_runs::Model::create(
+            &ctx.db,
+            &plan.job.name,
+            plan.job.kind.as_str(),
+            RunStatus::Scheduled,
+            next.into(),
+        )
+        .await?,
+    ))
+}
+
+/// `run_job` runs a job now, or its planned run `run_id`, and records the
+/// outcome. A job still running from before is recorded as skipped instead.
+///
+/// # Errors
+///
+/// Only on DB errors; a failed run is recorded instead.
+pub async fn run_job(
+    ctx: &AppContext,
+    name: &str,
+    kind: ScheduledJobKind,
+    run_id: Option<i32>,
+) -> Result<scheduled_runs::Model> {
+    let Some(_guard) = RunGuard::acquire(name) else {
+        warn!(job = name, "previous run still in progress, skipping");
+        let run = match run_id {
+            Some(id) => id,
+            None => {
+                scheduled_runs::Model::create(
+                    &ctx.db,
+                    name,
+                    kind.as_str(),
+                    RunStatus::Scheduled,
+                    Utc::now().into(),
+                )
+                .await?
+                .id
+            }
+        };
+        return Ok(scheduled_runs::Model::finish(
+            &ctx.db,
+            run,
+            RunStatus::Skipped,
+            "The previous run was still in progress",
+        )
+        .await?);
+    };
+
+    let run = match run_id {
+        Some(id) => scheduled_runs::Model::start(&ctx.db, id).await?,
+        None => {
+            scheduled_runs::Model::create(
+                &ctx.db,
+                name,
+                kind.as_str(),
+                RunStatus::Running,
+                Utc::now().into(),
+            )
+            .await?
+        }
+    };
+    info!(job = name, run_id = run.id, "running scheduled job");
+
+    let (status, message) = match perform(ctx, kind).await {
+        Ok(message) => (RunStatus::Completed, message),
+        Err(err) => {
+            error!(job = name, error = %err, "scheduled job failed");
+            (RunStatus::Failed, err.to_string())
+        }
+    };
+    Ok(scheduled_runs::Model::finish(&ctx.db, run.id, status, &message).await?)
+}
+
+/// `perform` does the work of a job kind through single-attempt generation
+/// jobs, like the CLI tasks, and describes the result.
+///
+/// # Errors
+///
+/// When the generation fails; for rerenders, when any flagged art failed.
+pub async fn perform(ctx: &AppContext, kind: ScheduledJobKind) -> Result<String> {
+    match kind {
+        ScheduledJobKind::CreateArt => {
+            let job =
+                generation_jobs::run_now(ctx, JobKind::Create, None, JobPayload::default()).await?;
+            Ok(format!(
+                "Created art #{}",
+                job.art_id
+                    .map_or_else(|| "?".to_string(), |id| id.to_string())
+            ))
+        }
+        ScheduledJobKind::RerenderFlagged => {
+            let ids = arts::Model::find_rerender_flagged_ids(&ctx.db).await?;
+            if ids.is_empty() {
+                return Ok("No arts are flagged for a rerender".to_string());
+            }
+
+            let mut failures = Vec::new();
+            for id in &ids {
+                if let Err(err) = generation_jobs::run_now(
+                    ctx,
+                    JobKind::Rerender,
+                    Some(*id),
+                    JobPayload::default(),
+                )
+                .await
+                {
+                    failures.push(format!("#{id}: {err}"));
+                }
+            }
+
+            let rerendered = ids.len() - failures.len();
+            if failures.is_empty() {
+                Ok(format!("Rerendered {rerendered} flagged arts"))
+            } else {
+                Err(Error::Message(format!(
+                    "Rerendered {rerendered} of {} flagged arts; failed {}",
+                    ids.len(),
+                    failures.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn plan(cron: &str) -> Plan {
+        Plan {
+            schedule: parse_cron(cron).unwrap(),
+            job: ScheduledJob {
+                name: "daily-art".to_string(),
+                kind: ScheduledJobKind::CreateArt,
+                cron: cron.to_string(),
+                jitter_seconds: 0,
+                enabled: true,
+            },
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_five_and_six_field_crons() {
+        assert!(parse_cron("0 9 * * *").is_ok());
+        assert!(parse_cron("30 0 9 * * *").is_ok());
+        assert!(parse_cron("every day").is_err());
+    }
+
+    #[test]
+    fn next_run_follows_the_cron_with_jitter() {
+        let daily = plan("0 9 * * *");
+        assert_eq!(daily.next_run(at(8, 0), 0, None), Some(at(9, 0)));
+        assert_eq!(
+            daily.next_run(at(9, 0), 0, None),
+            Some(at(9, 0) + Duration::days(1))
+        );
+        assert_eq!(
+            daily.next_run(at(8, 0), 90, None),
+            Some(at(9, 1) + Duration::seconds(30))
+        );
+    }
+
+    #[test]
+    fn runs_due_in_quiet_hours_wait_for_their_end() {
+        let overnight = parse_quiet_hours(&QuietHours {
+            start: "22:00".to_string(),
+            end: "02:00".to_string(),
+        })
+        .unwrap();
+        assert!(overnight.contains(NaiveTime::from_hms_opt(23, 0, 0).unwrap()));
+        assert!(overnight.contains(NaiveTime::from_hms_opt(1, 59, 0).unwrap()));
+        assert!(!overnight.contains(NaiveTime::from_hms_opt(2, 0, 0).unwrap()));
+
+        let late = plan("30 23 * * *");
+        assert_eq!(
+            late.next_run(at(12, 0), 0, Some(overnight)),
+            Some(at(2, 0) + Duration::days(1))
+        );
+        let early = plan("0 1 * * *");
+        assert_eq!(early.next_run(at(0, 0), 0, Some(overnight)), Some(at(2, 0)));
+        let daytime = plan("0 9 * * *");
+        assert_eq!(
+            daytime.next_run(at(8, 0), 0, Some(overnight)),
+            Some(at(9, 0))
+        );
+
+        assert!(
+            parse_quiet_hours(&QuietHours {
+                start: "late".to_string(),
+                end: "02:00".to_string(),
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_job_names() {
+        let job = plan("0 9 * * *").job;
+        let settings = SchedulerSettings {
+            enabled: true,
+            jobs: vec![job.clone(), job],
+            ..SchedulerSettings::default()
+        };
+        assert!(plans(&settings).is_err());
+    }
+}
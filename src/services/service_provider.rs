@@ -1,8 +1,12 @@
 use super::ai::{
-    openrouter_service::OpenRouterService,
+    pool_service::PoolService,
+    procedural_service::is_procedural,
     traits::{ImageGenerator, TextGenerator},
 };
-use crate::{common::settings::Settings, errors::Error};
+use crate::{
+    common::settings::{Flow, ModelEntry, Settings},
+    errors::Error,
+};
 
 pub struct ServiceProvider {}
 
@@ -11,50 +15,75 @@ fn is_configured(key: &str) -> bool {
     !key.is_empty() && key != "openrouter_api_key_goes_here"
 }
 
-fn draw(pool: &[String]) -> Option<&str> {
-    if pool.is_empty() {
-        return None;
+fn draw_service(
+    pool: &[ModelEntry],
+    kind: &str,
+    settings: &Settings,
+) -> Result<PoolService, Error> {
+    if !pool.iter().any(ModelEntry::is_drawable) {
+        return Err(Error::AIError(format!("No {kind} models configured")));
     }
-    pool.get(fastrand::usize(..pool.len())).map(String::as_str)
-}
-
-fn draw_service(pool: &[String], kind: &str, api_key: &str) -> Result<OpenRouterService, Error> {
-    let model = draw(pool)
-        .ok_or_else(|| Error::AIError(format!("No {kind} models configured")))?;
-    if !is_configured(api_key) {
+    // a pool of only `local/procedural` runs offline, without a key
+    let needs_key = pool
+        .iter()
+        .any(|entry| entry.is_drawable() && !is_procedural(&entry.id));
+    if needs_key && !is_configured(&settings.openrouter_api_key) {
         return Err(Error::AIError(
             "OpenRouter API key is not configured".to_string(),
         ));
     }
-    OpenRouterService::new(api_key, model)
+    PoolService::new(&settings.openrouter_api_key, pool, settings.model_attempts)
+        .ok_or_else(|| Error::AIError(format!("No {kind} models configured")))
 }
 
 impl ServiceProvider {
-    /// `random_img_service` draws a Model from the image Model Pool and returns
-    /// a generator bound to it.
+    /// `random_img_service` draws a Model from the image Model Pool of `flow`
+    /// (or the global one when the flow has none) and returns
+    /// a generator bound to it, which falls back to other pool members when
+    /// the drawn Model fails.
     ///
     /// # Errors
     ///
-    /// If the image Model Pool is empty, or the OpenRouter key is not configured.
-    pub fn random_img_service(settings: &Settings) -> Result<Box<dyn ImageGenerator + Send>, Error> {
-        Ok(Box::new(draw_service(
-            &settings.image_models,
-            "image",
-            &settings.openrouter_api_key,
-        )?))
+    /// If the image Model Pool has no enabled Model, or it has OpenRouter
+    /// Models and the OpenRouter key is not configured.
+    pub fn random_img_service(
+        settings: &Settings,
+        flow: Flow,
+    ) -> Result<Box<dyn ImageGenerator + Send>, Error> {
+        Self::pool_img_service(settings, settings.image_pool(flow))
+    }
+
+    /// `pool_img_service` is [`Self::random_img_service`] drawing from `pool`
+    /// instead of a flow's pool.
+    ///
+    /// # Errors
+    ///
+    /// If `pool` has no enabled Model, or it has OpenRouter Models and the
+    /// OpenRouter key is not configured.
+    pub fn pool_img_service(
+        settings: &Settings,
+        pool: &[ModelEntry],
+    ) -> Result<Box<dyn ImageGenerator + Send>, Error> {
+        Ok(Box::new(draw_service(pool, "image", settings)?))
     }
 
-    /// `random_txt_service` draws a Model from the text Model Pool and returns
-    /// a generator bound to it.
+    /// `random_txt_service` draws a Model from the text Model Pool of `flow`
+    /// (or the global one when the flow has none) and returns
+    /// a generator bound to it, which falls back to other pool members when
+    /// the drawn Model fails.
     ///
     /// # Errors
     ///
-    /// If the text Model Pool is empty, or the OpenRouter key is not configured.
-    pub fn random_txt_service(settings: &Settings) -> Result<Box<dyn TextGenerator + Send>, Error> {
+    /// If the text Model Pool has no enabled Model, or it has OpenRouter
+    /// Models and the OpenRouter key is not configured.
+    pub fn random_txt_service(
+        settings: &Settings,
+        flow: Flow,
+    ) -> Result<Box<dyn TextGenerator + Send>, Error> {
         Ok(Box::new(draw_service(
-            &settings.text_models,
+            settings.text_pool(flow),
             "text",
-            &settings.openrouter_api_key,
+            settings,
         )?))
     }
 }
@@ -62,12 +91,17 @@ impl ServiceProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::settings::FlowPool;
+
+    fn entries(models: Vec<String>) -> Vec<ModelEntry> {
+        models.iter().map(|model| ModelEntry::new(model)).collect()
+    }
 
     fn settings(text_models: Vec<String>, image_models: Vec<String>) -> Settings {
         Settings {
             openrouter_api_key: "sk-or-test-key".to_string(),
-            text_models,
-            image_models,
+            text_models: entries(text_models),
+            image_models: entries(image_models),
             ..Default::default()
         }
     }
@@ -78,7 +112,7 @@ mod tests {
             vec!["text/only-model".to_string()],
             vec!["image/only-model".to_string()],
         );
-        let generator = ServiceProvider::random_img_service(&s).unwrap();
+        let generator = ServiceProvider::random_img_service(&s, Flow::Create).unwrap();
         assert_eq!(generator.model_name(), "OpenRouter: image/only-model");
     }
 
@@ -88,7 +122,7 @@ mod tests {
             vec!["text/only-model".to_string()],
             vec!["image/only-model".to_string()],
         );
-        let generator = ServiceProvider::random_txt_service(&s).unwrap();
+        let generator = ServiceProvider::random_txt_service(&s, Flow::Create).unwrap();
         assert_eq!(generator.model_name(), "OpenRouter: text/only-model");
     }
 
@@ -100,7 +134,7 @@ mod tests {
             "image/c".to_string(),
         ];
         let s = settings(vec!["text/x".to_string()], pool.clone());
-        let generator = ServiceProvider::random_img_service(&s).unwrap();
+        let generator = ServiceProvider::random_img_service(&s, Flow::Create).unwrap();
         let name = generator.model_name();
         assert!(
             pool.iter().any(|m| name == format!("OpenRouter: {m}")),
@@ -111,14 +145,18 @@ mod tests {
     #[test]
     fn random_img_service_fails_when_image_pool_empty() {
         let s = settings(vec!["text/x".to_string()], vec![]);
-        let err = ServiceProvider::random_img_service(&s).err().unwrap();
+        let err = ServiceProvider::random_img_service(&s, Flow::Create)
+            .err()
+            .unwrap();
         assert!(err.to_string().contains("image"), "{err}");
     }
 
     #[test]
     fn random_txt_service_fails_when_text_pool_empty() {
         let s = settings(vec![], vec!["image/x".to_string()]);
-        let err = ServiceProvider::random_txt_service(&s).err().unwrap();
+        let err = ServiceProvider::random_txt_service(&s, Flow::Create)
+            .err()
+            .unwrap();
         assert!(err.to_string().contains("text"), "{err}");
     }
 
@@ -128,13 +166,55 @@ mod tests {
             let mut s = settings(vec!["text/x".to_string()], vec!["image/x".to_string()]);
             s.openrouter_api_key = bad_key.to_string();
             assert!(
-                ServiceProvider::random_img_service(&s).is_err(),
+                ServiceProvider::random_img_service(&s, Flow::Create).is_err(),
                 "img factory accepted key {bad_key:?}"
             );
             assert!(
-                ServiceProvider::random_txt_service(&s).is_err(),
+                ServiceProvider::random_txt_service(&s, Flow::Create).is_err(),
                 "txt factory accepted key {bad_key:?}"
             );
         }
     }
+
+    #[test]
+    fn flow_pools_override_the_global_pool() {
+        let mut s = settings(vec!["text/x".to_string()], vec!["image/cheap".to_string()]);
+        s.flows.mix = Some(FlowPool {
+            image_models: Some(entries(vec!["image/expensive".to_string()])),
+            ..Default::default()
+        });
+
+        let mix = ServiceProvider::random_img_service(&s, Flow::Mix).unwrap();
+        assert_eq!(mix.model_name(), "OpenRouter: image/expensive");
+        let create = ServiceProvider::random_img_service(&s, Flow::Create).unwrap();
+        assert_eq!(create.model_name(), "OpenRouter: image/cheap");
+        let text = ServiceProvider::random_txt_service(&s, Flow::Mix).unwrap();
+        assert_eq!(text.model_name(), "OpenRouter: text/x");
+    }
+
+    #[test]
+    fn procedural_pools_need_no_api_key() {
+        let mut s = settings(
+            vec!["local/procedural".to_string()],
+            vec!["local/procedural".to_string()],
+        );
+        s.openrouter_api_key = String::new();
+
+        let image = ServiceProvider::random_img_service(&s, Flow::Create).unwrap();
+        assert_eq!(image.model_name(), "Procedural: local/procedural");
+        assert!(ServiceProvider::random_txt_service(&s, Flow::Create).is_ok());
+
+        s.image_models.push(ModelEntry::new("image/remote"));
+        assert!(ServiceProvider::random_img_service(&s, Flow::Create).is_err());
+    }
+
+    #[test]
+    fn factories_fail_when_every_model_is_disabled() {
+        let mut s = settings(vec!["text/x".to_string()], vec!["image/x".to_string()]);
+        s.image_models[0].enabled = false;
+        let err = ServiceProvider::random_img_service(&s, Flow::Create)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("image"), "{err}");
+    }
 }
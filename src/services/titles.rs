@@ -0,0 +1,282 @@
+//! Generates titles that can actually be saved. Text Models don't always
+//! follow the title meta-prompt: answers come back too long, repeat an
+//! existing art's title, or are an apology instead of a title. Each answer
+//! is validated and, on a violation, the Model is asked again with feedback.
+
+use std::fmt;
+
+use loco_rs::prelude::*;
+use tracing::warn;
+
+use crate::{
+    common::settings::TitleSettings,
+    models::arts,
+    services::{ai::traits::TextGenerator, generation_usage::UsageLog, normalize},
+};
+
+/// Phrases that give an answer away as a refusal or apology.
+const REFUSALS: &[&str] = &[
+    "i'm sorry",
+    "i am sorry",
+    "sorry, ",
+    "i apologize",
+    "i can't",
+    "i cannot",
+    "i can not",
+    "i'm unable",
+    "i am unable",
+    "i'm not able",
+    "i am not able",
+    "i won't",
+    "as an ai",
+    "language model",
+];
+
+/// Opens the feedback a Model gets when its title was rejected, so generators
+/// can tell a re-ask from a first ask.
+pub const REJECTED_TITLE: &str = "Your previous answer was";
+
+/// `TitleViolation` is why a generated title can't be saved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TitleViolation {
+    Empty,
+    TooLong { length: usize, max_length: usize },
+    Duplicate,
+    Refusal,
+}
+
+impl fmt::Display for TitleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("it is empty"),
+            Self::TooLong { length, max_length } => write!(
+                f,
+                "it is {length} characters long, titles have at most {max_length}"
+            ),
+            Self::Duplicate => f.write_str("another art already has that title"),
+            Self::Refusal => f.write_str("it is a refusal, not a title"),
+        }
+    }
+}
+
+/// `is_refusal` tells whether an answer apologizes or declines instead of
+/// giving a title.
+#[must_use]
+pub fn is_refusal(title: &str) -> bool {
+    let title = title.to_lowercase().replace('’', "'");
+    REFUSALS.iter().any(|phrase| title.contains(phrase))
+}
+
+/// `check` validates a normalized title against the length limit, the
+/// `taken` titles (compared case-insensitively) and refusal phrases.
+///
+/// # Errors
+///
+/// The first violation found.
+pub fn check(
+    title: &str,
+    settings: &TitleSettings,
+    taken: &[String],
+) -> Result<(), TitleViolation> {
+    if title.trim().is_empty() {
+        return Err(TitleViolation::Empty);
+    }
+    if is_refusal(title) {
+        return Err(TitleViolation::Refusal);
+    }
+    let length = title.chars().count();
+    if length > settings.max_length {
+        return Err(TitleViolation::TooLong {
+            length,
+            max_length: settings.max_length,
+        });
+    }
+    if taken
+        .iter()
+        .any(|other| other.trim().eq_ignore_ascii_case(title))
+    {
+        return Err(TitleViolation::Duplicate);
+    }
+    Ok(())
+}
+
+/// `taken_titles` are the titles of every art a new title must differ from,
+/// leaving out the art `except` that is being retitled.
+///
+/// # Errors
+///
+/// On DB query error.
+pub async fn taken_titles(db: &DatabaseConnection, except: Option<i32>) -> Result<Vec<String>> {
+    Ok(arts::Model::find_all_title_ids(db)
+        .await?
+        .into_iter()
+        .filter(|art| Some(art.id) != except)
+        .map(|art| art.title)
+        .collect())
+}
+
+/// `generate_title` asks `text_gen` for a title with `meta_prompt` and
+/// re-asks, telling it what was wrong, until an answer passes `check` or
+/// `settings.attempts` answers were rejected. Every call is collected into
+/// `usage` as a `title` step. The generator is borrowed mutably only to keep
+/// the future `Send`, as generators aren't `Sync`.
+///
+/// # Errors
+///
+/// When the Model fails, or none of its answers is a valid title.
+pub async fn generate_title(
+    text_gen: &mut (dyn TextGenerator + Send),
+    meta_prompt: &str,
+    settings: &TitleSettings,
+    taken: &[String],
+    usage: &mut UsageLog,
+) -> Result<String> {
+    let attempts = settings.attempts.max(1);
+    let mut prompt = meta_prompt.to_string();
+    let mut rejected = None;
+
+    for attempt in 1..=attempts {
+        let answer = text_gen.generate(&prompt).await;
+        usage.collect("title", text_gen.take_usage());
+        let title = normalize::title(
+            &answer.map_err(|e| Error::Message(format!("Unable to generate title: {e}")))?,
+        );
+
+        match check(&title, settings, taken) {
+            Ok(()) => return Ok(title),
+            Err(violation) => {
+                warn!(attempt, title, %violation, "rejected generated title");
+                prompt = format!(
+                    "{meta_prompt}\n\n{REJECTED_TITLE} \"{title}\", which can't be used: {violation}. Give me a different title, and *just* the title."
+                );
+                rejected = Some((title, violation));
+            }
+        }
+    }
+
+    let (title, violation) = rejected.unwrap_or((String::new(), TitleViolation::Empty));
+    Err(Error::Message(format!(
+        "Unable to generate a valid title after {attempts} attempts: the last answer \"{title}\" was rejected because {violation}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::errors::Error as AiError;
+
+    fn settings() -> TitleSettings {
+        TitleSettings {
+            max_length: 27,
+            attempts: 3,
+        }
+    }
+
+    /// Answers with the queued titles in order and records the prompts.
+    struct Scripted {
+        answers: Mutex<Vec<&'static str>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl Scripted {
+        fn new(answers: &[&'static str]) -> Self {
+            Self {
+                answers: Mutex::new(answers.iter().rev().copied().collect()),
+                prompts: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TextGenerator for Scripted {
+        async fn generate(&self, prompt: &str) -> Result<String, AiError> {
+            self.prompts.lock().unwrap().push(prompt.to_string());
+            Ok(self.answers.lock().unwrap().pop().unwrap().to_string())
+        }
+
+        fn model_name(&self) -> String {
+            "scripted".to_string()
+        }
+    }
+
+    #[test]
+    fn checks_length_duplicates_and_refusals() {
+        let taken = vec!["Glowing Grove".to_string()];
+        assert_eq!(check("Lantern Tides", &settings(), &taken), Ok(()));
+        assert_eq!(check("", &settings(), &taken), Err(TitleViolation::Empty));
+        assert_eq!(
+            check("glowing grove", &settings(), &taken),
+            Err(TitleViolation::Duplicate)
+        );
+        assert_eq!(
+            check("A Very Long Title About Many Things", &settings(), &taken),
+            Err(TitleViolation::TooLong {
+                length: 35,
+                max_length: 27
+            })
+        );
+        assert_eq!(
+            check("I’m sorry, I can't help", &settings(), &taken),
+            Err(TitleViolation::Refusal)
+        );
+    }
+
+    #[test]
+    fn detects_refusals() {
+        assert!(is_refusal("I cannot create that title."));
+        assert!(is_refusal("As an AI, I do not have preferences"));
+        assert!(!is_refusal("Sorrow in Silver"));
+        assert!(!is_refusal("Cannonball Sky"));
+    }
+
+    #[tokio::test]
+    async fn re_asks_with_feedback_until_valid() {
+        let mut text_gen = Scripted::new(&[
+            "I'm sorry, I can't do that.",
+            "Glowing Grove",
+            "Lantern Tides",
+        ]);
+        let taken = vec!["Glowing Grove".to_string()];
+        let mut usage = UsageLog::default();
+
+        let title = generate_title(&mut text_gen, "Title this", &settings(), &taken, &mut usage)
+            .await
+            .unwrap();
+
+        assert_eq!(title, "Lantern Tides");
+        let prompts = text_gen.prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 3);
+        assert_eq!(prompts[0], "Title this");
+        assert!(prompts[1].contains("it is a refusal"), "{}", prompts[1]);
+        assert!(prompts[2].contains("another art already has that title"));
+    }
+
+    #[tokio::test]
+    async fn fails_after_the_configured_attempts() {
+        let mut text_gen =
+            Scripted::new(&["A Title Far Too Long To Ever Be Used", "Glowing Grove"]);
+        let taken = vec!["Glowing Grove".to_string()];
+        let settings = TitleSettings {
+            attempts: 2,
+            ..settings()
+        };
+
+        let err = generate_title(
+            &mut text_gen,
+            "Title this",
+            &settings,
+            &taken,
+            &mut UsageLog::default(),
+        )
+        .await
+        .unwrap_err();
+
+        let message = err.to_string();
+        assert!(message.contains("after 2 attempts"), "{message}");
+        assert!(message.contains("Glowing Grove"), "{message}");
+    }
+}
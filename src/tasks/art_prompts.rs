@@ -57,3 +57,13 @@ Your primary goal is to create a beautiful, compelling, and artistic image promp
 Analyze the above prompts and create a single artwork that harmoniously blends their subjects, styles, moods, colors, and artistic approaches. Look for connecting themes - whether visual, emotional, or stylistic - that can unite disparate elements into one compelling composition.
 
 Please give me *just* the prompt surrounded by single quotes and nothing more before or after it. This is EXTREMELY important. The prompt should be a concise yet descriptive instruction for an image generation AI.";
+
+pub const VARIATION_PROMPT: &str = "Here is a prompt for an AI image generator (e.g., DALL-E 2, MidJourney, StableDiffusion) that produced an artwork:
+
+{{PROMPT}}
+
+Rewrite this prompt to produce a variation of the artwork following this instruction: {{INSTRUCTION}}
+
+Keep everything the instruction doesn't ask to change: the subject, composition, medium, mood and palette should stay as close to the original as the instruction allows, so the new image reads as a variation of the first rather than a different artwork.
+
+Please give me *just* the prompt surrounded by single quotes and nothing more before or after it. This is EXTREMELY important. The prompt should be a concise yet descriptive instruction for an image generation AI.";
@@ -1,4 +1,10 @@
 use loco_rs::prelude::*;
+use sea_orm::{QueryOrder, QuerySelect, sea_query::Expr};
+
+use crate::{
+    models::_entities::{arts, mixes},
+    services::normalize,
+};
 
 pub struct CleanTitles;
 #[async_trait]
@@ -6,11 +12,90 @@ impl Task for CleanTitles {
     fn task(&self) -> TaskInfo {
         TaskInfo {
             name: "clean_titles".to_string(),
-            detail: "Task generator".to_string(),
+            detail: "Normalizes the titles and prompts of existing arts and mixes. Usage: cargo loco task clean_titles [dry_run:true]"
+                .to_string(),
         }
     }
-    async fn run(&self, _app_context: &AppContext, _vars: &task::Vars) -> Result<()> {
-        println!("Task CleanTitles generated");
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let dry_run = vars
+            .cli_arg("dry_run")
+            .is_ok_and(|dry_run| dry_run == "true");
+
+        let art_rows = arts::Entity::find()
+            .select_only()
+            .columns([arts::Column::Id, arts::Column::Title, arts::Column::Prompt])
+            .order_by_asc(arts::Column::Id)
+            .into_tuple::<(i32, String, String)>()
+            .all(&ctx.db)
+            .await?;
+        let mut changed = 0;
+        for (id, title, prompt) in art_rows {
+            let Some((title, prompt)) = cleaned("art", id, &title, &prompt) else {
+                continue;
+            };
+            changed += 1;
+            if !dry_run {
+                arts::Entity::update_many()
+                    .col_expr(arts::Column::Title, Expr::value(title))
+                    .col_expr(arts::Column::Prompt, Expr::value(prompt))
+                    .filter(arts::Column::Id.eq(id))
+                    .exec(&ctx.db)
+                    .await?;
+            }
+        }
+
+        let mix_rows = mixes::Entity::find()
+            .select_only()
+            .columns([
+                mixes::Column::Id,
+                mixes::Column::Title,
+                mixes::Column::Prompt,
+            ])
+            .order_by_asc(mixes::Column::Id)
+            .into_tuple::<(i32, String, String)>()
+            .all(&ctx.db)
+            .await?;
+        for (id, title, prompt) in mix_rows {
+            let Some((title, prompt)) = cleaned("mix", id, &title, &prompt) else {
+                continue;
+            };
+            changed += 1;
+            if !dry_run {
+                mixes::Entity::update_many()
+                    .col_expr(mixes::Column::Title, Expr::value(title))
+                    .col_expr(mixes::Column::Prompt, Expr::value(prompt))
+                    .filter(mixes::Column::Id.eq(id))
+                    .exec(&ctx.db)
+                    .await?;
+            }
+        }
+
+        if dry_run {
+            println!("{changed} arts and mixes would change (dry run, nothing saved)");
+        } else {
+            println!("Cleaned {changed} arts and mixes");
+        }
         Ok(())
     }
 }
+
+/// `cleaned` normalizes a title and prompt, printing a diff of what changes.
+/// It is `None` when both are already clean.
+fn cleaned(kind: &str, id: i32, title: &str, prompt: &str) -> Option<(String, String)> {
+    let clean_title = normalize::title(title);
+    let clean_prompt = normalize::prompt(prompt);
+    if clean_title == title && clean_prompt == prompt {
+        return None;
+    }
+
+    println!("{kind} {id}:");
+    if clean_title != title {
+        println!("  - title:  {title:?}");
+        println!("  + title:  {clean_title:?}");
+    }
+    if clean_prompt != prompt {
+        println!("  - prompt: {prompt:?}");
+        println!("  + prompt: {clean_prompt:?}");
+    }
+    Some((clean_title, clean_prompt))
+}
@@ -1,7 +1,14 @@
 use loco_rs::errors::Error;
 use loco_rs::prelude::*;
 
-use crate::services::art_service;
+use crate::{
+    common::{directives::Directives, generation_options::GenerationOptions},
+    models::{
+        arts,
+        generation_jobs::{JobKind, JobPayload},
+    },
+    services::generation_jobs,
+};
 
 pub struct CreateArt;
 #[async_trait]
@@ -9,15 +16,52 @@ impl Task for CreateArt {
     fn task(&self) -> TaskInfo {
         TaskInfo {
             name: "create_art".to_string(),
-            detail: "Task generator".to_string(),
+            detail: "Creates a new AI-generated art. Usage: cargo loco task create_art [aspect_ratio:3:4] [size:1024x1536] [seed:42] [negative_prompt:text] [theme:winter] [style:linocut]"
+                .to_string(),
         }
     }
-    async fn run(&self, ctx: &AppContext, _vars: &task::Vars) -> Result<()> {
-        let art = art_service::create_art(ctx)
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let options = options(vars)?;
+        let payload = JobPayload {
+            options: (options != GenerationOptions::default()).then_some(options),
+            directives: directives(vars),
+            ..Default::default()
+        };
+        let job = generation_jobs::run_now(ctx, JobKind::Create, None, payload)
             .await
             .map_err(|e| Error::Message(format!("Unable to create art: {e}")))?;
+        let art = arts::Entity::find_by_id(job.art_id.unwrap_or_default())
+            .one(&ctx.db)
+            .await?
+            .ok_or_else(|| Error::Message(format!("Job {} did not produce an art", job.id)))?;
 
         println!("Created art: {} - {}", art.id, art.title);
         Ok(())
     }
 }
+
+/// `options` reads the render options given as task vars.
+fn options(vars: &task::Vars) -> Result<GenerationOptions> {
+    let var = |name: &str| vars.cli_arg(name).ok().cloned();
+    let seed = var("seed")
+        .map(|seed| {
+            seed.parse::<i64>()
+                .map_err(|e| Error::Message(format!("Invalid 'seed': {e}. Must be an integer.")))
+        })
+        .transpose()?;
+    let options = GenerationOptions {
+        aspect_ratio: var("aspect_ratio"),
+        size: var("size"),
+        seed,
+        negative_prompt: var("negative_prompt"),
+    };
+    options.validate().map_err(Error::Message)?;
+    Ok(options)
+}
+
+/// `directives` reads the theme and style given as task vars. The style is the
+/// name of a style preset or a style written out.
+fn directives(vars: &task::Vars) -> Directives {
+    let var = |name: &str| vars.cli_arg(name).ok().map(String::as_str);
+    Directives::new(var("theme"), var("style"))
+}
@@ -0,0 +1,47 @@
+use loco_rs::prelude::*;
+
+use crate::{
+    common::image_hash,
+    models::{arts, mixes},
+};
+
+pub struct HashImages;
+#[async_trait]
+impl Task for HashImages {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "hash_images".to_string(),
+            detail: "Stores the perceptual hash of arts and mixes saved without one, or of all of them with force. Usage: cargo loco task hash_images [force:true]"
+                .to_string(),
+        }
+    }
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let force = vars.cli_arg("force").is_ok_and(|force| force == "true");
+
+        let mut hashed = 0;
+        let mut undecodable = 0;
+        for id in arts::Model::find_ids_to_hash(&ctx.db, force).await? {
+            let image = arts::Model::find_any_img_slice_by_id(&ctx.db, id.unsigned_abs()).await?;
+            let hash = image_hash::dhash(&image);
+            if hash.is_none() {
+                println!("art {id}: image could not be decoded");
+                undecodable += 1;
+            }
+            arts::Model::set_image_hash(&ctx.db, id, hash).await?;
+            hashed += 1;
+        }
+        for id in mixes::Model::find_ids_to_hash(&ctx.db, force).await? {
+            let image = mixes::Model::find_any_img_slice_by_id(&ctx.db, id.unsigned_abs()).await?;
+            let hash = image_hash::dhash(&image);
+            if hash.is_none() {
+                println!("mix {id}: image could not be decoded");
+                undecodable += 1;
+            }
+            mixes::Model::set_image_hash(&ctx.db, id, hash).await?;
+            hashed += 1;
+        }
+
+        println!("Hashed {hashed} arts and mixes ({undecodable} could not be decoded)");
+        Ok(())
+    }
+}
@@ -37,6 +37,7 @@ impl Task for ImportArts {
                     image: a.image,
                     prompt: a.prompt,
                     model: a.model,
+                    ..Default::default()
                 },
             )
             .await?;
@@ -0,0 +1,64 @@
+use loco_rs::prelude::*;
+
+use crate::models::{
+    mixes::{self, PublicationStatus},
+    staged_mixes,
+};
+
+pub struct RepairMixes;
+#[async_trait]
+impl Task for RepairMixes {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "repair_mixes".to_string(),
+            detail: "Lists rendered mixes left unsaved and mixes linked to no source art. With apply, saves the former and takes the latter out of the gallery. Usage: cargo loco task repair_mixes [apply:true]"
+                .to_string(),
+        }
+    }
+    async fn run(&self, ctx: &AppContext, vars: &task::Vars) -> Result<()> {
+        let apply = vars.cli_arg("apply").is_ok_and(|apply| apply == "true");
+
+        let staged = staged_mixes::Model::find_all(&ctx.db).await?;
+        let mut failed = 0;
+        for render in &staged {
+            let error = render.last_error.as_deref().unwrap_or("never saved");
+            println!(
+                "staged mix \"{}\" for request {}: {error}",
+                render.title, render.request_id
+            );
+            if apply {
+                match render.save(&ctx.db).await {
+                    Ok(mix) => println!("  saved as mix {}", mix.id),
+                    Err(err) => {
+                        failed += 1;
+                        println!("  could not save: {err}");
+                        render.set_last_error(&ctx.db, &err.to_string()).await?;
+                    }
+                }
+            }
+        }
+
+        let sourceless = mixes::Model::find_without_sources(&ctx.db).await?;
+        for mix in &sourceless {
+            println!(
+                "mix {} \"{}\" ({}) has no source arts",
+                mix.id, mix.title, mix.status
+            );
+            if apply && mix.status == PublicationStatus::Published.as_str() {
+                mixes::Model::set_status(&ctx.db, mix.id, PublicationStatus::Draft).await?;
+                println!("  moved to drafts");
+            }
+        }
+
+        let action = if apply { "Repaired" } else { "Found" };
+        println!(
+            "{action} {} staged mixes and {} mixes without sources",
+            staged.len() - failed,
+            sourceless.len()
+        );
+        if failed > 0 {
+            println!("{failed} staged mixes could not be saved and stay staged");
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 use loco_rs::prelude::*;
 
-use crate::services::art_service;
+use crate::{
+    models::{
+        arts,
+        generation_jobs::{JobKind, JobPayload},
+    },
+    services::generation_jobs,
+};
 
 pub struct ReplaceArt;
 #[async_trait]
@@ -24,7 +30,20 @@ impl Task for ReplaceArt {
         })?;
 
         println!("Attempting to replace art with ID: {}", art_id);
-        let updated_art = art_service::replace_art(ctx, art_id).await?;
+        arts::Entity::find_by_id(art_id)
+            .one(&ctx.db)
+            .await?
+            .ok_or_else(|| {
+                loco_rs::errors::Error::string(&format!("Art with ID {art_id} not found"))
+            })?;
+        generation_jobs::run_now(ctx, JobKind::Replace, Some(art_id), JobPayload::default())
+            .await?;
+        let updated_art = arts::Entity::find_by_id(art_id)
+            .one(&ctx.db)
+            .await?
+            .ok_or_else(|| {
+                loco_rs::errors::Error::string(&format!("Art with ID {art_id} not found"))
+            })?;
 
         println!(
             "Successfully replaced art: {} - {}",
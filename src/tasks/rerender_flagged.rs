@@ -0,0 +1,20 @@
+use loco_rs::prelude::*;
+
+use crate::{common::settings::ScheduledJobKind, services::scheduler};
+
+pub struct RerenderFlagged;
+#[async_trait]
+impl Task for RerenderFlagged {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "rerender_flagged".to_string(),
+            detail: "Rerenders the arts flagged for a rerender in the backoffice. Usage: cargo loco task rerender_flagged"
+                .to_string(),
+        }
+    }
+    async fn run(&self, ctx: &AppContext, _vars: &task::Vars) -> Result<()> {
+        let message = scheduler::perform(ctx, ScheduledJobKind::RerenderFlagged).await?;
+        println!("{message}");
+        Ok(())
+    }
+}
@@ -11,11 +11,16 @@ use crate::models::{
 /// # Errors
 ///
 /// When there is an issue with rendering the view.
-pub fn show(v: &impl ViewRenderer, item: &art_entity::Model, latest: bool) -> Result<Response> {
+pub fn show(
+    v: &impl ViewRenderer,
+    item: &art_entity::Model,
+    previous_id: Option<i32>,
+    next_id: Option<i32>,
+) -> Result<Response> {
     format::render().view(
         v,
         "arts/show.html",
-        serde_json::json!({"item": item, "latest": latest}),
+        serde_json::json!({"item": item, "previous_id": previous_id, "next_id": next_id}),
     )
 }
 
@@ -37,6 +42,8 @@ pub struct ListResponse {
     id: i32,
     title: String,
     image_url: String,
+    width: Option<i32>,
+    height: Option<i32>,
 }
 
 impl From<&ArtTitleId> for ListResponse {
@@ -45,6 +52,8 @@ impl From<&ArtTitleId> for ListResponse {
             id: art.id,
             title: art.title.clone(),
             image_url: image_url(art.id, &art.updated_at),
+            width: art.width,
+            height: art.height,
         }
     }
 }
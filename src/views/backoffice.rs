@@ -1,8 +1,19 @@
 use loco_rs::prelude::*;
 
-use crate::models::{
-    arts::{self, BackofficeArtList, BackofficeStats},
-    mixes::{self, BackofficeMixList},
+use crate::{
+    models::{
+        art_candidates, art_revisions,
+        arts::{self, BackofficeArtList, BackofficeStats},
+        generation_jobs,
+        generation_usage::SpendStats,
+        mixes::{self, BackofficeMixList},
+        prompt_templates::{self, TemplateKind},
+        scheduled_runs, style_presets,
+    },
+    services::{
+        duplicates::DuplicatePair,
+        prompt_templates::{Preview, ResolvedTemplate},
+    },
 };
 
 pub fn login(v: &impl ViewRenderer, error: Option<&str>) -> Result<Response> {
@@ -13,19 +24,32 @@ pub fn login(v: &impl ViewRenderer, error: Option<&str>) -> Result<Response> {
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn dashboard(
     v: &impl ViewRenderer,
     stats: &BackofficeStats,
+    spend: &SpendStats,
     recent_arts: &[arts::Model],
     recent_mixes: &[mixes::Model],
+    recent_jobs: &[generation_jobs::Model],
+    upcoming_runs: &[scheduled_runs::Model],
+    recent_runs: &[scheduled_runs::Model],
+    presets: &[style_presets::Model],
+    image_models: &[String],
 ) -> Result<Response> {
     format::render().view(
         v,
         "backoffice/dashboard.html",
         serde_json::json!({
             "stats": stats,
+            "spend": spend,
             "recent_arts": recent_arts,
             "recent_mixes": recent_mixes,
+            "recent_jobs": recent_jobs,
+            "upcoming_runs": upcoming_runs,
+            "recent_runs": recent_runs,
+            "presets": presets,
+            "image_models": image_models,
         }),
     )
 }
@@ -34,11 +58,46 @@ pub fn art_index(v: &impl ViewRenderer, page: &BackofficeArtList) -> Result<Resp
     format::render().view(v, "backoffice/arts.html", serde_json::json!({"page": page}))
 }
 
+/// `approvals` is the queue of draft arts and mixes waiting to be published
+/// or rejected.
+pub fn approvals(
+    v: &impl ViewRenderer,
+    arts: &[arts::Model],
+    mixes: &[mixes::Model],
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "backoffice/approvals.html",
+        serde_json::json!({"arts": arts, "mixes": mixes}),
+    )
+}
+
+/// `duplicates` is the report of saved arts and mixes whose images are
+/// near-identical.
+pub fn duplicates(
+    v: &impl ViewRenderer,
+    pairs: &[DuplicatePair],
+    threshold: u32,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "backoffice/duplicates.html",
+        serde_json::json!({"pairs": pairs, "threshold": threshold}),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn art_detail(
     v: &impl ViewRenderer,
     item: &arts::Model,
     previous_id: Option<i32>,
     next_id: Option<i32>,
+    active_jobs: &[generation_jobs::Model],
+    templates: &[prompt_templates::Model],
+    candidates: &[art_candidates::CandidateSummary],
+    presets: &[style_presets::Model],
+    lineage: &arts::Lineage,
+    revisions: &[art_revisions::RevisionSummary],
     notice: Option<&str>,
     error: Option<&str>,
 ) -> Result<Response> {
@@ -49,12 +108,43 @@ pub fn art_detail(
             "item": item,
             "previous_id": previous_id,
             "next_id": next_id,
+            "active_jobs": active_jobs,
+            "templates": templates,
+            "candidates": candidates,
+            "presets": presets,
+            "lineage": lineage,
+            "revisions": revisions,
             "notice": notice,
             "error": error,
         }),
     )
 }
 
+/// `art_export` is the JSON export of an art: its metadata and full
+/// provenance, with a link to the image instead of the image itself.
+pub fn art_export(item: &arts::Model) -> Result<Response> {
+    format::json(serde_json::json!({
+        "id": item.id,
+        "uuid": item.uuid,
+        "title": item.title,
+        "prompt": item.prompt,
+        "image_model": item.model,
+        "image_url": arts::image_url(item.id, &item.updated_at),
+        "created_at": item.created_at,
+        "updated_at": item.updated_at,
+        "provenance": item.provenance(),
+        "options": item.generation_options(),
+        "width": item.width,
+        "height": item.height,
+        "diversity_score": item.diversity_score,
+        "theme": item.theme,
+        "style": item.style,
+        "style_preset_id": item.style_preset_id,
+        "parent_id": item.parent_id,
+        "variation_instruction": item.variation_instruction,
+    }))
+}
+
 pub fn mix_index(v: &impl ViewRenderer, page: &BackofficeMixList) -> Result<Response> {
     format::render().view(
         v,
@@ -63,10 +153,111 @@ pub fn mix_index(v: &impl ViewRenderer, page: &BackofficeMixList) -> Result<Resp
     )
 }
 
-pub fn mix_detail(v: &impl ViewRenderer, item: &mixes::Model, art_ids: &[i32]) -> Result<Response> {
+pub fn mix_detail(
+    v: &impl ViewRenderer,
+    item: &mixes::Model,
+    art_ids: &[i32],
+    templates: &[prompt_templates::Model],
+) -> Result<Response> {
     format::render().view(
         v,
         "backoffice/mix.html",
-        serde_json::json!({"item": item, "art_ids": art_ids}),
+        serde_json::json!({"item": item, "art_ids": art_ids, "templates": templates}),
+    )
+}
+
+/// `mix_export` is the JSON export of a mix: its metadata, source arts and
+/// full provenance, with a link to the image instead of the image itself.
+pub fn mix_export(item: &mixes::Model, art_ids: &[i32]) -> Result<Response> {
+    format::json(serde_json::json!({
+        "id": item.id,
+        "uuid": item.uuid,
+        "title": item.title,
+        "prompt": item.prompt,
+        "image_model": item.model,
+        "image_url": format!("/mix/img/{}.webp", item.id),
+        "art_ids": art_ids,
+        "created_at": item.created_at,
+        "updated_at": item.updated_at,
+        "provenance": item.provenance(),
+    }))
+}
+
+fn template_kind_json(kind: TemplateKind) -> serde_json::Value {
+    serde_json::json!({
+        "value": kind.as_str(),
+        "label": kind.label(),
+        "placeholders": kind.placeholders(),
+    })
+}
+
+/// `upload` is the form saving an image made elsewhere as a new art.
+pub fn upload(
+    v: &impl ViewRenderer,
+    values: &impl serde::Serialize,
+    error: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "backoffice/upload.html",
+        serde_json::json!({"values": values, "error": error}),
+    )
+}
+
+/// `style_index` is the library of named style presets, with the form adding
+/// or replacing one.
+pub fn style_index(
+    v: &impl ViewRenderer,
+    presets: &[style_presets::Model],
+    error: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "backoffice/styles.html",
+        serde_json::json!({"presets": presets, "error": error}),
+    )
+}
+
+pub fn template_index(
+    v: &impl ViewRenderer,
+    kinds: &[(TemplateKind, ResolvedTemplate, usize)],
+) -> Result<Response> {
+    let kinds = kinds
+        .iter()
+        .map(|(kind, current, versions)| {
+            serde_json::json!({
+                "kind": template_kind_json(*kind),
+                "current": current,
+                "versions": versions,
+            })
+        })
+        .collect::<Vec<_>>();
+    format::render().view(
+        v,
+        "backoffice/templates.html",
+        serde_json::json!({"kinds": kinds}),
+    )
+}
+
+pub fn template_editor(
+    v: &impl ViewRenderer,
+    kind: TemplateKind,
+    current: &ResolvedTemplate,
+    versions: &[prompt_templates::Model],
+    body: &str,
+    preview: Option<&Preview>,
+    error: Option<&str>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "backoffice/template.html",
+        serde_json::json!({
+            "kind": template_kind_json(kind),
+            "current": current,
+            "versions": versions,
+            "body": body,
+            "preview": preview,
+            "error": error,
+        }),
     )
 }
@@ -1,6 +1,13 @@
+use std::collections::HashMap;
+
 use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::models::{_entities::mixes, arts::ArtTitleId};
+use crate::models::{
+    _entities::mixes,
+    arts::ArtTitleId,
+    mixes::{MixTitleId, image_url},
+};
 
 /// Render a single arts view.
 ///
@@ -20,10 +27,69 @@ pub fn show(v: &impl ViewRenderer, item: &mixes::Model, art_ids: &[i32]) -> Resu
 /// # Errors
 ///
 /// When there is an issue with rendering the view.
-pub fn show_form(v: &impl ViewRenderer, title_ids: &[ArtTitleId]) -> Result<Response> {
+pub fn show_form(
+    v: &impl ViewRenderer,
+    title_ids: &[ArtTitleId],
+    bounds: (usize, usize),
+) -> Result<Response> {
+    let (min_arts, max_arts) = bounds;
     format::render().view(
         v,
         "mixes/form.html",
-        serde_json::json!({"title_ids": title_ids}),
+        serde_json::json!({"title_ids": title_ids, "min_arts": min_arts, "max_arts": max_arts}),
+    )
+}
+
+/// Renders a page of the public mix index, with the ids to page to newer and
+/// older mixes from.
+///
+/// # Errors
+///
+/// When there is an issue with rendering the view.
+pub fn show_index(
+    v: &impl ViewRenderer,
+    items: &[ListResponse],
+    newer_id: Option<i32>,
+    older_id: Option<i32>,
+) -> Result<Response> {
+    format::render().view(
+        v,
+        "mixes/index.html",
+        serde_json::json!({"items": items, "newer_id": newer_id, "older_id": older_id}),
     )
 }
+
+/// Renders a snap-scrolling, infinitely lazy-loaded view of the mixes.
+///
+/// # Errors
+///
+/// When there is an issue with rendering the view.
+pub fn show_infinite(v: &impl ViewRenderer, items: &[ListResponse]) -> Result<Response> {
+    format::render().view(
+        v,
+        "mixes/infinite.html",
+        serde_json::json!({"items": items}),
+    )
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListResponse {
+    id: i32,
+    title: String,
+    image_url: String,
+    art_ids: Vec<i32>,
+}
+
+/// `list_response` pairs each mix with its source arts from `art_ids`.
+#[must_use]
+pub fn list_response(items: &[MixTitleId], art_ids: &HashMap<i32, Vec<i32>>) -> Vec<ListResponse> {
+    items
+        .iter()
+        .map(|mix| ListResponse {
+            id: mix.id,
+            title: mix.title.clone(),
+            image_url: image_url(mix.id, &mix.updated_at),
+            art_ids: art_ids.get(&mix.id).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
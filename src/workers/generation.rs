@@ -0,0 +1,27 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::services::generation_jobs;
+
+/// `GenerationWorker` runs one attempt of a persisted generation job. The job
+/// row is the source of truth; the worker args only point at it.
+pub struct GenerationWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GenerationWorkerArgs {
+    pub job_id: i32,
+}
+
+#[async_trait]
+impl BackgroundWorker<GenerationWorkerArgs> for GenerationWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    async fn perform(&self, args: GenerationWorkerArgs) -> Result<()> {
+        generation_jobs::run(&self.ctx, args.job_id).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::testing::request::boot_test;
+use oxidized_canvas::{
+    app::App,
+    models::{
+        art_candidates::{self, CandidateParams},
+        art_revisions::{self, RevisionReason},
+        arts::{self, ArtParams},
+    },
+};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+fn candidate(image: &str, model: &str) -> CandidateParams {
+    CandidateParams {
+        image: STANDARD.encode(image),
+        model: Some(model.to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn picking_a_candidate_publishes_it_and_discards_the_rest() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("first-render"),
+            prompt: "Gouache study of a lighthouse".to_string(),
+            title: "Keeper's Light".to_string(),
+            model: Some("first-model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    art_candidates::Model::replace_for_art(
+        db,
+        art.id,
+        &[
+            candidate("first-render", "first-model"),
+            candidate("second-render", "second-model"),
+        ],
+    )
+    .await
+    .unwrap();
+    let candidates = art_candidates::Model::find_by_art(db, art.id)
+        .await
+        .unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[1].model.as_deref(), Some("second-model"));
+    assert_eq!(
+        art_candidates::Model::find_img_slice(db, art.id, candidates[1].id)
+            .await
+            .unwrap(),
+        b"second-render"
+    );
+
+    let published = art_candidates::Model::pick(db, art.id, candidates[1].id)
+        .await
+        .unwrap();
+    assert_eq!(published.image, STANDARD.encode("second-render"));
+    assert_eq!(published.model.as_deref(), Some("second-model"));
+    assert_eq!(published.title, art.title);
+    assert!(
+        art_candidates::Model::find_by_art(db, art.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let history = art_revisions::Model::find_history(db, &published)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].reason, RevisionReason::Pick.as_str());
+    let fields = history[0]
+        .changes
+        .iter()
+        .map(|change| change.field)
+        .collect::<Vec<_>>();
+    assert_eq!(fields, ["Image", "Image model"]);
+    assert_eq!(
+        art_revisions::Model::find_img_slice(db, art.id, history[0].id)
+            .await
+            .unwrap(),
+        b"first-render"
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn new_candidates_replace_stale_ones_and_can_be_discarded() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("kept-render"),
+            prompt: "Linocut of a fox".to_string(),
+            title: "Fox in Relief".to_string(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    for batch in ["old", "new"] {
+        art_candidates::Model::replace_for_art(
+            db,
+            art.id,
+            &[
+                candidate(&format!("{batch}-a"), "model-a"),
+                candidate(&format!("{batch}-b"), "model-b"),
+            ],
+        )
+        .await
+        .unwrap();
+    }
+    let candidates = art_candidates::Model::find_by_art(db, art.id)
+        .await
+        .unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(
+        art_candidates::Model::find_img_slice(db, art.id, candidates[0].id)
+            .await
+            .unwrap(),
+        b"new-a"
+    );
+    // a candidate is only reachable through its own art
+    assert!(
+        art_candidates::Model::find_img_slice(db, art.id + 1, candidates[0].id)
+            .await
+            .is_err()
+    );
+
+    assert_eq!(
+        art_candidates::Model::discard_all(db, art.id)
+            .await
+            .unwrap(),
+        2
+    );
+    let kept = arts::Entity::find_by_id(art.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(kept.image, STANDARD.encode("kept-render"));
+}
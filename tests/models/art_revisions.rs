@@ -0,0 +1,93 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::testing::request::boot_test;
+use oxidized_canvas::{
+    app::App,
+    models::{
+        art_revisions::{self, FieldChange},
+        arts::{self, ArtParams, ArtUpdateParams},
+    },
+};
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn edits_keep_the_previous_state_and_can_be_restored() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("first-render"),
+            prompt: "Ink study of a heron".to_string(),
+            title: "Grey Wader".to_string(),
+            model: Some("first-model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // an edit changing nothing keeps no revision
+    let unchanged = ArtUpdateParams {
+        title: art.title.clone(),
+        prompt: art.prompt.clone(),
+        model: art.model.clone(),
+    };
+    arts::Model::update_details(db, art.id, &unchanged)
+        .await
+        .unwrap();
+    assert!(
+        art_revisions::Model::find_history(db, &art)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let edited = arts::Model::update_details(
+        db,
+        art.id,
+        &ArtUpdateParams {
+            title: "Patient Heron".to_string(),
+            prompt: art.prompt.clone(),
+            model: art.model.clone(),
+        },
+    )
+    .await
+    .unwrap();
+    let history = art_revisions::Model::find_history(db, &edited)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].reason, "edit");
+    assert_eq!(
+        history[0].changes,
+        vec![FieldChange {
+            field: "Title",
+            before: Some("Grey Wader".to_string()),
+            after: Some("Patient Heron".to_string()),
+        }]
+    );
+    assert_eq!(
+        art_revisions::Model::find_img_slice(db, art.id, history[0].id)
+            .await
+            .unwrap(),
+        b"first-render"
+    );
+
+    let restored = art_revisions::Model::restore(db, art.id, history[0].id)
+        .await
+        .unwrap();
+    assert_eq!(restored.title, "Grey Wader");
+    let history = art_revisions::Model::find_history(db, &restored)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].reason, "restore");
+    assert_eq!(history[0].title, "Patient Heron");
+
+    assert!(
+        art_revisions::Model::restore(db, art.id + 1, history[0].id)
+            .await
+            .is_err()
+    );
+}
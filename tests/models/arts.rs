@@ -1,6 +1,11 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
 use loco_rs::testing::{db::seed, request::boot_test};
-use oxidized_canvas::app::App;
+use oxidized_canvas::{
+    app::App,
+    models::arts::{self, ArtParams, PublicationStatus},
+};
 use serial_test::serial;
+use uuid::Uuid;
 
 macro_rules! configure_insta {
     ($($expr:expr),*) => {
@@ -29,3 +34,105 @@ async fn test_model() {
     // snapshot the result:
     // assert_debug_snapshot!(item);
 }
+
+#[tokio::test]
+#[serial]
+async fn public_queries_only_see_published_arts() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let published = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("published-image"),
+            prompt: "Watercolor of a tram depot".to_string(),
+            title: "Depot at Dusk".to_string(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let draft = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("draft-image"),
+            prompt: "Pastel of a quarry".to_string(),
+            title: "Quarry Draft".to_string(),
+            status: PublicationStatus::Draft,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(draft.status, "draft");
+
+    assert_ne!(arts::Model::find_latest(db).await.unwrap().id, draft.id);
+    assert_ne!(arts::Model::find_latest_id(db).await.unwrap(), draft.id);
+    assert!(arts::Model::find_published(db, draft.id).await.is_err());
+    assert!(!arts::Model::find_ids(db).await.unwrap().contains(&draft.id));
+    let before = arts::Model::find_before_id(db, draft.id + 1).await.unwrap();
+    assert!(before.iter().all(|art| art.id != draft.id));
+    assert_eq!(before.first().map(|art| art.id), Some(published.id));
+    let after = arts::Model::find_after_id(db, published.id).await.unwrap();
+    assert!(after.is_empty());
+    assert!(
+        arts::Model::find_published_title_ids(db)
+            .await
+            .unwrap()
+            .iter()
+            .all(|art| art.id != draft.id)
+    );
+    assert_eq!(
+        arts::Model::find_next_published_id(db, published.id)
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(
+        arts::Model::find_by_status(db, PublicationStatus::Draft)
+            .await
+            .unwrap()
+            .iter()
+            .any(|art| art.id == draft.id)
+    );
+
+    arts::Model::set_status(db, draft.id, PublicationStatus::Published)
+        .await
+        .unwrap();
+    assert!(arts::Model::find_published(db, draft.id).await.is_ok());
+    assert_eq!(
+        arts::Model::find_next_published_id(db, published.id)
+            .await
+            .unwrap(),
+        Some(draft.id)
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn latest_prompts_leave_out_the_art_being_replaced() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let prompt = format!("Charcoal of windmill {}", Uuid::new_v4());
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("diverse-image"),
+            prompt: prompt.clone(),
+            title: "Windmill".to_string(),
+            diversity_score: Some(0.75),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(art.diversity_score, Some(0.75));
+
+    let prompts = arts::Model::find_n_latest_prompts(db, 100, None)
+        .await
+        .unwrap();
+    assert!(prompts.contains(&prompt));
+    let prompts = arts::Model::find_n_latest_prompts(db, 100, Some(art.id))
+        .await
+        .unwrap();
+    assert!(!prompts.contains(&prompt));
+}
@@ -0,0 +1,154 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::testing::request::boot_test;
+use oxidized_canvas::{
+    app::App,
+    models::{
+        arts::{self, ArtParams},
+        generation_jobs::{self, GenerationJobParams, JobKind, JobPayload, JobStatus},
+    },
+};
+use serial_test::serial;
+
+async fn create_art(db: &sea_orm::DatabaseConnection) -> arts::Model {
+    arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "Job prompt".to_string(),
+            title: "Job art".to_string(),
+            model: Some("seed-model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap()
+}
+
+fn params(kind: JobKind, art_id: Option<i32>) -> GenerationJobParams {
+    GenerationJobParams {
+        kind,
+        art_id,
+        payload: JobPayload::default(),
+        max_attempts: 2,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn can_create_and_claim_a_job_once() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = create_art(db).await;
+
+    let job = generation_jobs::Model::create(db, &params(JobKind::Replace, Some(art.id)))
+        .await
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Queued.as_str());
+    assert_eq!(job.attempts, 0);
+    assert_eq!(job.job_kind().unwrap(), JobKind::Replace);
+
+    let claimed = generation_jobs::Model::claim(db, job.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(claimed.status, JobStatus::Running.as_str());
+    assert_eq!(claimed.attempts, 1);
+    assert!(claimed.started_at.is_some());
+
+    let again = generation_jobs::Model::claim(db, job.id).await.unwrap();
+    assert!(again.is_none(), "a running job must not be claimed twice");
+
+    let active = generation_jobs::Model::find_active_for_art(db, art.id)
+        .await
+        .unwrap();
+    assert!(active.iter().any(|active| active.id == job.id));
+}
+
+#[tokio::test]
+#[serial]
+async fn failed_attempts_are_requeued_until_attempts_run_out() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    let job = generation_jobs::Model::create(db, &params(JobKind::Create, None))
+        .await
+        .unwrap();
+
+    let claimed = generation_jobs::Model::claim(db, job.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(claimed.has_attempts_left());
+    let retry_at = chrono::Utc::now() + chrono::Duration::seconds(30);
+    let retried =
+        generation_jobs::Model::mark_failed(db, job.id, "model is down", Some(retry_at.into()))
+            .await
+            .unwrap();
+    assert_eq!(retried.status, JobStatus::Queued.as_str());
+    assert_eq!(retried.last_error.as_deref(), Some("model is down"));
+    assert!(retried.finished_at.is_none());
+
+    let claimed = generation_jobs::Model::claim(db, job.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(claimed.attempts, 2);
+    assert!(!claimed.has_attempts_left());
+    let failed = generation_jobs::Model::mark_failed(db, job.id, "still down", None)
+        .await
+        .unwrap();
+    assert_eq!(failed.status, JobStatus::Failed.as_str());
+    assert!(failed.finished_at.is_some());
+}
+
+#[tokio::test]
+#[serial]
+async fn jobs_running_past_the_lease_are_requeued() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    let job = generation_jobs::Model::create(db, &params(JobKind::Create, None))
+        .await
+        .unwrap();
+    generation_jobs::Model::claim(db, job.id).await.unwrap();
+
+    generation_jobs::Model::requeue_interrupted(
+        db,
+        (chrono::Utc::now() - chrono::Duration::hours(1)).into(),
+    )
+    .await
+    .unwrap();
+    let queued = generation_jobs::Model::find_queued(db).await.unwrap();
+    assert!(!queued.iter().any(|queued| queued.id == job.id));
+
+    let requeued = generation_jobs::Model::requeue_interrupted(
+        db,
+        (chrono::Utc::now() + chrono::Duration::seconds(1)).into(),
+    )
+    .await
+    .unwrap();
+    assert!(requeued >= 1);
+
+    let queued = generation_jobs::Model::find_queued(db).await.unwrap();
+    assert!(queued.iter().any(|queued| queued.id == job.id));
+}
+
+#[tokio::test]
+#[serial]
+async fn can_complete_a_job_with_its_result() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = create_art(db).await;
+
+    let job = generation_jobs::Model::create(db, &params(JobKind::Create, None))
+        .await
+        .unwrap();
+    generation_jobs::Model::claim(db, job.id).await.unwrap();
+    let completed = generation_jobs::Model::mark_completed(db, job.id, Some(art.id), None)
+        .await
+        .unwrap();
+
+    assert_eq!(completed.status, JobStatus::Completed.as_str());
+    assert_eq!(completed.art_id, Some(art.id));
+    assert!(completed.finished_at.is_some());
+}
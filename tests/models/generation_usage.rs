@@ -0,0 +1,103 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::testing::request::boot_test;
+use oxidized_canvas::{
+    app::App,
+    models::{
+        arts::{self, ArtParams},
+        generation_usage::{self, UsageParams},
+    },
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serial_test::serial;
+use uuid::Uuid;
+
+fn usage(flow: &str, step: &str, model: &str, cost: f64, success: bool) -> UsageParams {
+    UsageParams {
+        flow: flow.to_string(),
+        step: step.to_string(),
+        model: model.to_string(),
+        prompt_tokens: Some(10),
+        completion_tokens: Some(5),
+        total_tokens: Some(15),
+        cost: Some(cost),
+        latency_ms: 120,
+        success,
+        art_id: None,
+        mix_id: None,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn can_record_usage_linked_to_an_art() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "Usage prompt".to_string(),
+            title: "Usage art".to_string(),
+            model: Some("image/model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut prompt = usage("create", "prompt", "text/model", 0.001, true);
+    prompt.art_id = Some(art.id);
+    let mut image = usage("create", "image", "image/model", 0.04, true);
+    image.art_id = Some(art.id);
+    generation_usage::Model::create_many(db, &[prompt, image])
+        .await
+        .unwrap();
+
+    let rows = generation_usage::Entity::find()
+        .filter(generation_usage::generation_usage::Column::ArtId.eq(art.id))
+        .all(db)
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row.flow == "create" && row.success));
+}
+
+#[tokio::test]
+#[serial]
+async fn spend_stats_group_by_day_model_and_flow() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let image_model = format!("image/stats-{}", Uuid::new_v4());
+
+    generation_usage::Model::create_many(
+        db,
+        &[
+            usage("create", "prompt", "text/stats", 0.25, true),
+            usage("create", "image", &image_model, 1.5, true),
+            usage("mix", "image", &image_model, 0.5, false),
+        ],
+    )
+    .await
+    .unwrap();
+
+    let stats = generation_usage::Model::spend_stats(db).await.unwrap();
+    assert!(stats.total.calls >= 3);
+    assert!(stats.total.cost >= 2.25);
+
+    let image_model = stats
+        .models
+        .iter()
+        .find(|stat| stat.label == image_model)
+        .unwrap();
+    assert_eq!(image_model.calls, 2);
+    assert_eq!(image_model.failed_calls, 1);
+    assert!((image_model.cost - 2.0).abs() < f64::EPSILON);
+    assert_eq!(image_model.tokens, 30);
+
+    assert!(stats.flows.iter().any(|stat| stat.label == "mix"));
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    assert_eq!(
+        stats.days.first().map(|day| day.label.as_str()),
+        Some(today.as_str())
+    );
+}
@@ -1,4 +1,11 @@
+mod art_candidates;
+mod art_revisions;
 mod arts;
+mod generation_jobs;
+mod generation_usage;
 
 mod mixarts;
 mod mixes;
+mod prompt_templates;
+mod staged_mixes;
+mod style_presets;
@@ -0,0 +1,144 @@
+use loco_rs::testing::request::boot_test;
+use oxidized_canvas::{
+    app::App,
+    models::prompt_templates::{self, TemplateKind, TemplateParams},
+    services::prompt_templates::{PromptTemplates, builtin},
+};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+async fn clear_templates(db: &sea_orm::DatabaseConnection) {
+    prompt_templates::Entity::delete_many()
+        .exec(db)
+        .await
+        .unwrap();
+}
+
+fn params(kind: TemplateKind, body: &str, activate: bool) -> TemplateParams {
+    TemplateParams {
+        kind,
+        body: body.to_string(),
+        note: None,
+        activate,
+    }
+}
+
+#[test]
+fn kinds_round_trip_through_their_names() {
+    for kind in TemplateKind::ALL {
+        assert_eq!(kind.as_str().parse::<TemplateKind>(), Ok(kind));
+    }
+    assert!("prompt".parse::<TemplateKind>().is_err());
+}
+
+#[test]
+fn reports_missing_placeholders() {
+    assert_eq!(
+        TemplateKind::Title.missing_placeholders("Name this: {{DESCRIPTION}}"),
+        vec!["{{TITLES}}"]
+    );
+    assert!(
+        TemplateKind::Image
+            .missing_placeholders("Vary from {{PROMPTS}}")
+            .is_empty()
+    );
+    assert!(
+        TemplateKind::SampleTitles
+            .missing_placeholders("anything")
+            .is_empty()
+    );
+    for kind in TemplateKind::ALL {
+        assert!(kind.missing_placeholders(builtin(kind)).is_empty());
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn versions_are_numbered_per_kind_and_one_is_active() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    clear_templates(db).await;
+
+    let first = prompt_templates::Model::create_version(
+        db,
+        &params(TemplateKind::Image, "First {{PROMPTS}}", true),
+    )
+    .await
+    .unwrap();
+    let second = prompt_templates::Model::create_version(
+        db,
+        &params(TemplateKind::Image, "Second {{PROMPTS}}", true),
+    )
+    .await
+    .unwrap();
+    let draft = prompt_templates::Model::create_version(
+        db,
+        &params(TemplateKind::Image, "Draft {{PROMPTS}}", false),
+    )
+    .await
+    .unwrap();
+    let title = prompt_templates::Model::create_version(
+        db,
+        &params(TemplateKind::Title, "{{TITLES}} {{DESCRIPTION}}", false),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!((first.version, second.version, draft.version), (1, 2, 3));
+    assert_eq!(title.version, 1);
+    let active = prompt_templates::Model::find_active(db, TemplateKind::Image)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(active.id, second.id);
+
+    prompt_templates::Model::activate(db, first.id)
+        .await
+        .unwrap();
+    let versions = prompt_templates::Model::find_versions(db, TemplateKind::Image)
+        .await
+        .unwrap();
+    let active = versions
+        .iter()
+        .filter(|version| version.active)
+        .map(|version| version.id)
+        .collect::<Vec<_>>();
+    assert_eq!(active, vec![first.id]);
+
+    let templates = PromptTemplates::load(db).await.unwrap();
+    assert_eq!(templates.get(TemplateKind::Image).id, Some(first.id));
+    assert_eq!(templates.get(TemplateKind::Title).id, None);
+    assert_eq!(
+        templates.get(TemplateKind::Title).body,
+        builtin(TemplateKind::Title)
+    );
+
+    prompt_templates::Model::use_builtin(db, TemplateKind::Image)
+        .await
+        .unwrap();
+    let templates = PromptTemplates::load(db).await.unwrap();
+    assert_eq!(templates.get(TemplateKind::Image).version, None);
+}
+
+#[tokio::test]
+#[serial]
+async fn rejects_versions_missing_placeholders() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    clear_templates(db).await;
+
+    let err = prompt_templates::Model::create_version(
+        db,
+        &params(TemplateKind::Title, "Just a {{DESCRIPTION}}", true),
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(err.to_string().contains("{{TITLES}}"), "{err}");
+    assert!(
+        prompt_templates::Model::find_versions(db, TemplateKind::Title)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,77 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::testing::request::boot_test;
+use oxidized_canvas::{
+    app::App,
+    models::{
+        arts::{self, ArtParams},
+        mixarts,
+        mixes::{self, MixParams},
+        staged_mixes,
+    },
+};
+use serial_test::serial;
+use uuid::Uuid;
+
+#[tokio::test]
+#[serial]
+async fn staged_renders_are_saved_once_with_their_sources() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let mut art_ids = Vec::new();
+    for title in ["Staged source one", "Staged source two"] {
+        let art = arts::Model::create(
+            db,
+            &ArtParams {
+                image: STANDARD.encode(title),
+                prompt: format!("Prompt for {title}"),
+                title: title.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        art_ids.push(art.id);
+    }
+    let request_id = Uuid::new_v4();
+    let params = |title: &str| MixParams {
+        image: STANDARD.encode("staged-mix-render"),
+        prompt: "Two sources, one mix".to_string(),
+        title: title.to_string(),
+        model: "mix-model".to_string(),
+        ..Default::default()
+    };
+
+    let staged = staged_mixes::Model::stage(db, request_id, &params("First render"), &art_ids)
+        .await
+        .unwrap();
+    // staging the request again keeps the render already paid for
+    let again = staged_mixes::Model::stage(db, request_id, &params("Second render"), &art_ids)
+        .await
+        .unwrap();
+    assert_eq!(again.id, staged.id);
+    assert_eq!(again.art_ids(), art_ids);
+
+    let mix = staged.save(db).await.unwrap();
+    assert_eq!(mix.title, "First render");
+    assert_eq!(mix.request_id, Some(request_id));
+    let mut linked = mixarts::Model::find_art_ids(db, mix.id).await.unwrap();
+    linked.sort_unstable();
+    assert_eq!(linked, art_ids);
+    assert!(
+        staged_mixes::Model::find_by_request_id(db, request_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // saving it again finds the mix instead of saving a second one
+    let resaved = staged.save(db).await.unwrap();
+    assert_eq!(resaved.id, mix.id);
+    assert_eq!(
+        mixes::Model::find_by_request_id(db, request_id)
+            .await
+            .unwrap()
+            .map(|found| found.id),
+        Some(mix.id)
+    );
+}
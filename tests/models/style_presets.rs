@@ -0,0 +1,69 @@
+use loco_rs::{model::ModelError, testing::request::boot_test};
+use oxidized_canvas::{
+    app::App,
+    models::style_presets::{self, StylePresetParams},
+};
+use serial_test::serial;
+
+fn params(name: &str, directive: &str) -> StylePresetParams {
+    StylePresetParams {
+        name: name.to_string(),
+        directive: directive.to_string(),
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn saving_under_an_existing_name_replaces_the_directive() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    let first = style_presets::Model::save(db, &params(" Ukiyo-e ", "woodblock print"))
+        .await
+        .unwrap();
+    assert_eq!(first.name, "Ukiyo-e");
+
+    let second =
+        style_presets::Model::save(db, &params("UKIYO-E", "ukiyo-e woodblock, flat colour"))
+            .await
+            .unwrap();
+    assert_eq!(second.id, first.id);
+    assert_eq!(second.name, "UKIYO-E");
+    assert_eq!(second.directive, "ukiyo-e woodblock, flat colour");
+
+    let found = style_presets::Model::find_by_name(db, "ukiyo-e")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, first.id);
+
+    style_presets::Model::delete_by_id(db, first.id)
+        .await
+        .unwrap();
+    assert!(
+        style_presets::Model::find_by_name(db, "ukiyo-e")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(matches!(
+        style_presets::Model::delete_by_id(db, first.id).await,
+        Err(ModelError::EntityNotFound)
+    ));
+}
+
+#[tokio::test]
+#[serial]
+async fn presets_need_a_name_and_a_directive() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+
+    assert!(matches!(
+        style_presets::Model::save(db, &params("  ", "ink wash")).await,
+        Err(ModelError::Message(_))
+    ));
+    assert!(matches!(
+        style_presets::Model::save(db, &params("Ink wash", "")).await,
+        Err(ModelError::Message(_))
+    ));
+}
@@ -1,20 +1,31 @@
-use std::net::SocketAddr;
+use std::io::Cursor;
 
-use axum_test::{TestServer, TestServerConfig};
+use axum_test::multipart::{MultipartForm, Part};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
-use loco_rs::testing::request::boot_test;
+use image::{ImageFormat, Rgb, RgbImage};
 use oxidized_canvas::{
-    app::App,
+    common::settings::ScheduledJobKind,
     models::{
-        arts::{self, ArtParams},
+        art_candidates, art_revisions,
+        arts::{self, ArtParams, Provenance, PublicationStatus},
+        generation_jobs::{self, JobStatus},
         mixarts::{self, MixArtParams},
         mixes::{self, MixParams},
+        prompt_templates::{self, TemplateKind},
+        scheduled_runs::{self, RunStatus},
+        style_presets,
+    },
+    services::{
+        art_service::{self, CreateArtParams},
+        backoffice_auth, scheduler,
     },
-    services::backoffice_auth,
 };
 use sea_orm::EntityTrait;
 use serde::Serialize;
 use serial_test::serial;
+use uuid::Uuid;
+
+use super::helpers::boot_server;
 
 #[derive(Serialize)]
 struct LoginBody<'a> {
@@ -28,6 +39,52 @@ struct EditArtBody<'a> {
     model: &'a str,
 }
 
+#[derive(Serialize)]
+struct StatusBody<'a> {
+    status: &'a str,
+    from: &'a str,
+}
+
+#[derive(Serialize)]
+struct DirectivesBody<'a> {
+    theme: &'a str,
+    style: &'a str,
+}
+
+#[derive(Serialize)]
+struct VariationBody<'a> {
+    instruction: &'a str,
+    mode: &'a str,
+}
+
+#[derive(Serialize)]
+struct CreateArtBody<'a> {
+    request_id: &'a str,
+    theme: &'a str,
+    style: &'a str,
+    model: &'a str,
+    prompt: &'a str,
+}
+
+#[derive(Serialize)]
+struct StylePresetBody<'a> {
+    name: &'a str,
+    directive: &'a str,
+}
+
+#[derive(Serialize)]
+struct RerenderFlagBody {
+    flagged: bool,
+}
+
+#[derive(Serialize)]
+struct TemplateBody<'a> {
+    body: &'a str,
+    note: &'a str,
+    activate: &'a str,
+    action: &'a str,
+}
+
 #[tokio::test]
 #[serial]
 async fn redirects_unauthenticated_backoffice_requests_to_login() {
@@ -63,6 +120,7 @@ async fn can_log_in_and_edit_art_metadata() {
             prompt: "Original prompt".to_string(),
             title: "Original title".to_string(),
             model: Some("seed-model".to_string()),
+            ..Default::default()
         },
     )
     .await
@@ -120,6 +178,7 @@ async fn can_delete_art_from_backoffice() {
             prompt: "Prompt to delete".to_string(),
             title: "Delete me".to_string(),
             model: Some("seed-model".to_string()),
+            ..Default::default()
         },
     )
     .await
@@ -154,6 +213,7 @@ async fn can_view_and_delete_mix_from_backoffice() {
             prompt: "Prompt for source art".to_string(),
             title: "Source art".to_string(),
             model: Some("seed-model".to_string()),
+            ..Default::default()
         },
     )
     .await
@@ -165,6 +225,7 @@ async fn can_view_and_delete_mix_from_backoffice() {
             prompt: "Mix prompt".to_string(),
             title: "Mix title".to_string(),
             model: "mix-model".to_string(),
+            ..Default::default()
         },
     )
     .await
@@ -187,6 +248,14 @@ async fn can_view_and_delete_mix_from_backoffice() {
     assert!(body.contains("Mix title"), "{body}");
     assert!(body.contains("Art #"), "{body}");
 
+    let export = server
+        .get(&format!("/backoffice/mixes/{}/export.json", mix.id))
+        .await;
+    assert_eq!(export.status_code(), 200, "{}", export.text());
+    let json: serde_json::Value = export.json();
+    assert_eq!(json["art_ids"], serde_json::json!([art.id]));
+    assert!(json["provenance"].is_object());
+
     let delete = server
         .post(&format!("/backoffice/mixes/{}/delete", mix.id))
         .await;
@@ -206,20 +275,1010 @@ async fn can_view_and_delete_mix_from_backoffice() {
     assert!(deleted.is_none());
 }
 
-async fn boot_server() -> (loco_rs::app::AppContext, TestServer) {
-    let boot = boot_test::<App>().await.unwrap();
-    let config = TestServerConfig {
-        default_content_type: Some("application/json".to_string()),
-        save_cookies: true,
-        ..Default::default()
-    };
-    let server = TestServer::new_with_config(
-        boot.router
+#[tokio::test]
+#[serial]
+async fn replace_queues_a_persisted_generation_job() {
+    let (ctx, mut server) = boot_server().await;
+    let art = arts::Model::create(
+        &ctx.db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "Prompt to replace".to_string(),
+            title: "Replace me".to_string(),
+            model: Some("seed-model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let replace = server
+        .post(&format!("/backoffice/arts/{}/replace", art.id))
+        .form(&DirectivesBody {
+            theme: "",
+            style: "",
+        })
+        .await;
+    assert_eq!(replace.status_code(), 303, "{}", replace.text());
+    replace.assert_header("location", format!("/backoffice/arts/{}?queued=1", art.id));
+
+    // the test config generates offline, so the job runs to completion inline
+    let jobs = generation_jobs::Model::find_n_latest(&ctx.db, 1)
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+    let job = &jobs[0];
+    assert_eq!(job.kind, "replace");
+    assert_eq!(job.art_id, Some(art.id));
+    assert_eq!(
+        job.status,
+        JobStatus::Completed.as_str(),
+        "{:?}",
+        job.last_error
+    );
+    assert_eq!(job.attempts, 1);
+
+    let replaced = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(replaced.title, "Replace me");
+    assert_eq!(
+        replaced.model.as_deref(),
+        Some("Procedural: local/procedural")
+    );
+    assert_eq!(
+        replaced.prompt_model.as_deref(),
+        Some("Procedural: local/procedural")
+    );
+
+    let history = art_revisions::Model::find_history(&ctx.db, &replaced)
+        .await
+        .unwrap();
+    let retried = art_service::replace_art_with_progress(
+        &ctx,
+        art.id,
+        art.uuid,
+        &Default::default(),
+        Some(job.job_payload().unwrap().request_id.unwrap_or(job.uuid)),
+    )
+    .await
+    .unwrap();
+    assert_eq!(retried.title, replaced.title);
+    assert_eq!(
+        art_revisions::Model::find_history(&ctx.db, &replaced)
+            .await
+            .unwrap()
+            .len(),
+        history.len()
+    );
+
+    let dashboard = server.get("/backoffice").await;
+    let body = dashboard.text();
+    assert!(body.contains("Generation queue"), "{body}");
+    assert!(body.contains("Generation spend"), "{body}");
+}
+
+#[tokio::test]
+#[serial]
+async fn creates_a_new_art_from_the_dashboard() {
+    let (ctx, mut server) = boot_server().await;
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let dashboard = server.get("/backoffice").await.text();
+    assert!(dashboard.contains("Create new art"), "{dashboard}");
+    assert!(dashboard.contains("procedural</option>"), "{dashboard}");
+
+    let request_id = Uuid::new_v4().to_string();
+    let created = server
+        .post("/backoffice/arts")
+        .form(&CreateArtBody {
+            request_id: &request_id,
+            theme: "tide pools",
+            style: "",
+            model: "local/procedural",
+            prompt: " A hermit crab trying on a thimble for a shell. ",
+        })
+        .await;
+    assert_eq!(created.status_code(), 303, "{}", created.text());
+
+    // the test config generates offline, so the job runs to completion inline
+    let job = &generation_jobs::Model::find_n_latest(&ctx.db, 1)
+        .await
+        .unwrap()[0];
+    assert_eq!(job.kind, "create");
+    assert_eq!(
+        job.status,
+        JobStatus::Completed.as_str(),
+        "{:?}",
+        job.last_error
+    );
+    let payload = job.job_payload().unwrap();
+    assert_eq!(payload.request_id.unwrap().to_string(), request_id);
+
+    let art = arts::Entity::find_by_id(job.art_id.unwrap())
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(art.prompt, "A hermit crab trying on a thimble for a shell.");
+    assert_eq!(art.theme.as_deref(), Some("tide pools"));
+    assert_eq!(art.model.as_deref(), Some("Procedural: local/procedural"));
+    assert_eq!(art.prompt_model, None);
+    assert_eq!(art.prompt_meta_prompt, None);
+    assert!(art.diversity_score.is_some());
+
+    let retried = art_service::create_art_with_progress(
+        &ctx,
+        &CreateArtParams::default(),
+        payload.request_id.unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(retried.id, art.id);
+
+    let unknown = server
+        .post("/backoffice/arts")
+        .form(&CreateArtBody {
+            request_id: "",
+            theme: "",
+            style: "",
+            model: "image/not-configured",
+            prompt: "",
+        })
+        .await;
+    assert_eq!(unknown.status_code(), 400, "{}", unknown.text());
+}
+
+#[tokio::test]
+#[serial]
+async fn style_presets_direct_a_replacement_from_the_backoffice() {
+    let (ctx, mut server) = boot_server().await;
+    let art = arts::Model::create(
+        &ctx.db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "Prompt to restyle".to_string(),
+            title: "Restyle me".to_string(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let saved = server
+        .post("/backoffice/styles")
+        .form(&StylePresetBody {
+            name: "Cyanotype",
+            directive: "cyanotype print, prussian blue",
+        })
+        .await;
+    assert_eq!(saved.status_code(), 303, "{}", saved.text());
+    let rejected = server
+        .post("/backoffice/styles")
+        .form(&StylePresetBody {
+            name: "Empty",
+            directive: " ",
+        })
+        .await;
+    assert!(
+        rejected
+            .text()
+            .contains("The preset needs a style directive."),
+        "{}",
+        rejected.text()
+    );
+    let styles = server.get("/backoffice/styles").await.text();
+    assert!(
+        styles.contains("cyanotype print, prussian blue"),
+        "{styles}"
+    );
+
+    let replace = server
+        .post(&format!("/backoffice/arts/{}/replace", art.id))
+        .form(&DirectivesBody {
+            theme: " harbour at night ",
+            style: "cyanotype",
+        })
+        .await;
+    assert_eq!(replace.status_code(), 303, "{}", replace.text());
+
+    let preset = style_presets::Model::find_by_name(&ctx.db, "Cyanotype")
+        .await
+        .unwrap()
+        .unwrap();
+    let replaced = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(replaced.theme.as_deref(), Some("harbour at night"));
+    assert_eq!(replaced.style.as_deref(), Some("cyanotype"));
+    assert_eq!(replaced.style_preset_id, Some(preset.id));
+    assert!(
+        replaced
+            .prompt
+            .ends_with("Style: cyanotype print, prussian blue"),
+        "{}",
+        replaced.prompt
+    );
+
+    let detail = server
+        .get(&format!("/backoffice/arts/{}", art.id))
+        .await
+        .text();
+    assert!(detail.contains("harbour at night"), "{detail}");
+    let export = server
+        .get(&format!("/backoffice/arts/{}/export.json", art.id))
+        .await
+        .json::<serde_json::Value>();
+    assert_eq!(export["style_preset_id"], preset.id);
+}
+
+#[tokio::test]
+#[serial]
+async fn varies_an_art_over_itself_or_as_a_linked_new_art() {
+    let (ctx, mut server) = boot_server().await;
+    let art = arts::Model::create(
+        &ctx.db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "A harbor at dawn, in watercolor.".to_string(),
+            title: "Vary me".to_string(),
+            theme: Some("harbors".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let blank = server
+        .post(&format!("/backoffice/arts/{}/variation", art.id))
+        .form(&VariationBody {
+            instruction: "  ",
+            mode: "new",
+        })
+        .await;
+    assert!(
+        blank
+            .text()
+            .contains("Tell what the variation should change."),
+        "{}",
+        blank.text()
+    );
+
+    let as_new = server
+        .post(&format!("/backoffice/arts/{}/variation", art.id))
+        .form(&VariationBody {
+            instruction: "make it autumn",
+            mode: "new",
+        })
+        .await;
+    assert_eq!(as_new.status_code(), 303, "{}", as_new.text());
+    as_new.assert_header("location", format!("/backoffice/arts/{}?queued=1", art.id));
+
+    let job = &generation_jobs::Model::find_n_latest(&ctx.db, 1)
+        .await
+        .unwrap()[0];
+    assert_eq!(job.kind, "variation");
+    assert_eq!(
+        job.status,
+        JobStatus::Completed.as_str(),
+        "{:?}",
+        job.last_error
+    );
+
+    let parent = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(parent.prompt, "A harbor at dawn, in watercolor.");
+    let lineage = arts::Model::find_lineage(&ctx.db, &parent).await.unwrap();
+    assert_eq!(lineage.variations.len(), 1);
+    let variation = arts::Entity::find_by_id(lineage.variations[0].id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(variation.parent_id, Some(art.id));
+    assert_eq!(
+        variation.variation_instruction.as_deref(),
+        Some("make it autumn")
+    );
+    assert_eq!(variation.theme.as_deref(), Some("harbors"));
+    assert_ne!(variation.prompt, parent.prompt);
+    assert_eq!(job.art_id, Some(variation.id));
+
+    let retried = art_service::vary_art_with_progress(
+        &ctx,
+        art.id,
+        art.uuid,
+        "make it autumn",
+        true,
+        job.job_payload().unwrap().request_id,
+    )
+    .await
+    .unwrap();
+    assert_eq!(retried.id, variation.id);
+    assert_eq!(
+        arts::Model::find_lineage(&ctx.db, &parent)
+            .await
+            .unwrap()
+            .variations
+            .len(),
+        1
+    );
+    assert!(
+        variation
+            .prompt_meta_prompt
+            .as_deref()
+            .is_some_and(|meta| meta.contains("make it autumn")
+                && meta.contains("A harbor at dawn, in watercolor."))
+    );
+
+    let detail = server
+        .get(&format!("/backoffice/arts/{}", variation.id))
+        .await
+        .text();
+    assert!(
+        detail.contains(&format!("/backoffice/arts/{}", art.id)),
+        "{detail}"
+    );
+    let parent_detail = server
+        .get(&format!("/backoffice/arts/{}", art.id))
+        .await
+        .text();
+    assert!(
+        parent_detail.contains(&format!("/backoffice/arts/{}\"", variation.id)),
+        "{parent_detail}"
+    );
+
+    let replace = server
+        .post(&format!("/backoffice/arts/{}/variation", art.id))
+        .form(&VariationBody {
+            instruction: "as a charcoal sketch",
+            mode: "replace",
+        })
+        .await;
+    assert_eq!(replace.status_code(), 303, "{}", replace.text());
+
+    let varied = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(varied.title, "Vary me");
+    assert_ne!(varied.prompt, "A harbor at dawn, in watercolor.");
+    assert_eq!(
+        varied.variation_instruction.as_deref(),
+        Some("as a charcoal sketch")
+    );
+    assert_eq!(varied.parent_id, None);
+    assert_eq!(
+        arts::Model::find_lineage(&ctx.db, &varied)
+            .await
+            .unwrap()
+            .variations
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn a_rerender_can_be_rolled_back_from_the_revision_history() {
+    let (ctx, mut server) = boot_server().await;
+    let art = arts::Model::create(
+        &ctx.db,
+        &ArtParams {
+            image: STANDARD.encode("liked-render"),
+            prompt: "Prompt people liked".to_string(),
+            title: "Keep me".to_string(),
+            model: Some("liked-model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let rerender = server
+        .post(&format!("/backoffice/arts/{}/rerender", art.id))
+        .await;
+    assert_eq!(rerender.status_code(), 303, "{}", rerender.text());
+    let rerendered = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(rerendered.image, art.image);
+
+    let history = art_revisions::Model::find_history(&ctx.db, &rerendered)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].reason, "rerender");
+    assert!(
+        history[0]
+            .changes
+            .iter()
+            .any(|change| change.field == "Image")
+    );
+
+    let detail = server
+        .get(&format!("/backoffice/arts/{}", art.id))
+        .await
+        .text();
+    assert!(detail.contains("before rerender"), "{detail}");
+    let thumbnail = server
+        .get(&format!(
+            "/backoffice/arts/{}/revisions/{}",
+            art.id, history[0].id
+        ))
+        .await;
+    assert_eq!(thumbnail.as_bytes().as_ref(), b"liked-render");
+
+    let restore = server
+        .post(&format!(
+            "/backoffice/arts/{}/revisions/{}/restore",
+            art.id, history[0].id
+        ))
+        .await;
+    assert_eq!(restore.status_code(), 303, "{}", restore.text());
+    let restored = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(restored.image, art.image);
+    assert_eq!(restored.model.as_deref(), Some("liked-model"));
+}
+#[tokio::test]
+#[serial]
+async fn shows_and_exports_art_provenance() {
+    let (ctx, mut server) = boot_server().await;
+    let art = arts::Model::create(
+        &ctx.db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "Prompt with provenance".to_string(),
+            title: "Traceable art".to_string(),
+            model: Some("OpenRouter: image-model".to_string()),
+            provenance: Provenance {
+                prompt_model: Some("OpenRouter: prompt-model".to_string()),
+                title_model: Some("OpenRouter: title-model".to_string()),
+                prompt_meta_prompt: Some("Write an image prompt".to_string()),
+                title_meta_prompt: Some("Write a title".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let export = server
+        .get(&format!("/backoffice/arts/{}/export.json", art.id))
+        .await;
+    assert_eq!(export.status_code(), 303, "{}", export.text());
+
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let details = server.get(&format!("/backoffice/arts/{}", art.id)).await;
+    let body = details.text();
+    assert!(body.contains("OpenRouter: prompt-model"), "{body}");
+    assert!(body.contains("Write a title"), "{body}");
+
+    let export = server
+        .get(&format!("/backoffice/arts/{}/export.json", art.id))
+        .await;
+    assert_eq!(export.status_code(), 200, "{}", export.text());
+    let json: serde_json::Value = export.json();
+    assert_eq!(json["image_model"], "OpenRouter: image-model");
+    assert_eq!(
+        json["provenance"]["prompt_model"],
+        "OpenRouter: prompt-model"
+    );
+    assert_eq!(json["provenance"]["title_model"], "OpenRouter: title-model");
+    assert_eq!(
+        json["provenance"]["prompt_meta_prompt"],
+        "Write an image prompt"
+    );
+    assert_eq!(json["provenance"]["title_meta_prompt"], "Write a title");
+    assert!(json.get("image").is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn can_preview_and_save_prompt_template_versions() {
+    let (ctx, mut server) = boot_server().await;
+    prompt_templates::Entity::delete_many()
+        .exec(&ctx.db)
+        .await
+        .unwrap();
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let index = server.get("/backoffice/templates").await;
+    let body = index.text();
+    assert_eq!(index.status_code(), 200, "{body}");
+    assert!(body.contains("Mix image prompt"), "{body}");
+
+    let invalid = server
+        .post("/backoffice/templates/title")
+        .form(&TemplateBody {
+            body: "A title for {{DESCRIPTION}}",
+            note: "",
+            activate: "on",
+            action: "save",
+        })
+        .await;
+    let body = invalid.text();
+    assert_eq!(invalid.status_code(), 200, "{body}");
+    assert!(body.contains("missing {{TITLES}}"), "{body}");
+
+    let draft = "Short title like {{TITLES}} for: {{DESCRIPTION}}";
+    let preview = server
+        .post("/backoffice/templates/title")
+        .form(&TemplateBody {
+            body: draft,
+            note: "",
+            activate: "on",
+            action: "preview",
+        })
+        .await;
+    let body = preview.text();
+    assert_eq!(preview.status_code(), 200, "{body}");
+    assert!(body.contains("Dry run"), "{body}");
+    assert!(body.contains("Short title like"), "{body}");
+    assert!(
+        prompt_templates::Model::find_versions(&ctx.db, TemplateKind::Title)
+            .await
             .unwrap()
-            .into_make_service_with_connect_info::<SocketAddr>(),
-        config,
+            .is_empty()
+    );
+
+    let save = server
+        .post("/backoffice/templates/title")
+        .form(&TemplateBody {
+            body: draft,
+            note: "Shorter titles",
+            activate: "on",
+            action: "save",
+        })
+        .await;
+    assert_eq!(save.status_code(), 303, "{}", save.text());
+    save.assert_header("location", "/backoffice/templates/title");
+    let version = prompt_templates::Model::find_active(&ctx.db, TemplateKind::Title)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(version.version, 1);
+    assert_eq!(version.note.as_deref(), Some("Shorter titles"));
+
+    let art = art_service::create_art(&ctx, &CreateArtParams::default())
+        .await
+        .unwrap();
+    assert_eq!(art.title_template_id, Some(version.id));
+    assert_eq!(art.prompt_template_id, None);
+    assert!(
+        art.title_meta_prompt
+            .as_deref()
+            .is_some_and(|prompt| prompt.starts_with("Short title like")),
+        "{:?}",
+        art.title_meta_prompt
+    );
+
+    let details = server.get(&format!("/backoffice/arts/{}", art.id)).await;
+    let body = details.text();
+    assert!(body.contains("title v1"), "{body}");
+}
+
+#[tokio::test]
+#[serial]
+async fn candidates_are_rendered_side_by_side_and_one_is_published() {
+    let (mut ctx, mut server) = boot_server().await;
+    let mut settings = ctx.config.settings.clone().unwrap();
+    settings["candidates"] = serde_json::json!(3);
+    ctx.config.settings = Some(settings);
+
+    let art = art_service::create_art(&ctx, &CreateArtParams::default())
+        .await
+        .unwrap();
+    assert_eq!(art.status, PublicationStatus::Draft.as_str());
+    let candidates = art_candidates::Model::find_by_art(&ctx.db, art.id)
+        .await
+        .unwrap();
+    assert_eq!(candidates.len(), 3);
+
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let details = server.get(&format!("/backoffice/arts/{}", art.id)).await;
+    let body = details.text();
+    assert!(body.contains("Publish This One"), "{body}");
+    assert!(
+        body.contains(&format!(
+            "/backoffice/arts/{}/candidates/{}/pick",
+            art.id, candidates[2].id
+        )),
+        "{body}"
+    );
+
+    let image = server
+        .get(&format!(
+            "/backoffice/arts/{}/candidates/{}",
+            art.id, candidates[2].id
+        ))
+        .await;
+    assert_eq!(image.status_code(), 200);
+    image.assert_header("content-type", "image/webp");
+
+    let pick = server
+        .post(&format!(
+            "/backoffice/arts/{}/candidates/{}/pick",
+            art.id, candidates[2].id
+        ))
+        .await;
+    assert_eq!(pick.status_code(), 303, "{}", pick.text());
+    pick.assert_header("location", format!("/backoffice/arts/{}", art.id));
+    assert!(
+        art_candidates::Model::find_by_art(&ctx.db, art.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let details = server.get(&format!("/backoffice/arts/{}", art.id)).await;
+    assert!(!details.text().contains("Publish This One"));
+
+    let published = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(published.status, PublicationStatus::Published.as_str());
+    assert_eq!(published.model, candidates[2].model);
+
+    let replaced = art_service::replace_art(&ctx, art.id, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(replaced.image, published.image);
+    assert_eq!(replaced.prompt, published.prompt);
+    let candidates = art_candidates::Model::find_by_art(&ctx.db, art.id)
+        .await
+        .unwrap();
+    assert_eq!(candidates.len(), 3);
+    let unchanged = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unchanged.image, published.image);
+    assert_eq!(unchanged.title, published.title);
+
+    let changes = art_candidates::Entity::find_by_id(candidates[0].id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap()
+        .changes
+        .unwrap();
+    let picked = art_candidates::Model::pick(&ctx.db, art.id, candidates[0].id)
+        .await
+        .unwrap();
+    assert_eq!(picked.model, candidates[0].model);
+    assert_eq!(changes["prompt"], picked.prompt);
+    assert_eq!(changes["title"], picked.title);
+}
+
+#[tokio::test]
+#[serial]
+async fn drafts_wait_in_the_approval_queue_until_published() {
+    let (mut ctx, mut server) = boot_server().await;
+    let mut settings = ctx.config.settings.clone().unwrap();
+    settings["require_approval"] = serde_json::json!(true);
+    ctx.config.settings = Some(settings);
+
+    let art = art_service::create_art(&ctx, &CreateArtParams::default())
+        .await
+        .unwrap();
+    assert_eq!(art.status, PublicationStatus::Draft.as_str());
+    assert!(
+        art.diversity_score
+            .is_some_and(|score| (0.0..=1.0).contains(&score))
+    );
+
+    let public = server.get(&format!("/{}", art.id)).await;
+    assert_eq!(public.status_code(), 404);
+    let image = server.get(&format!("/img/{}.webp", art.id)).await;
+    assert_eq!(image.status_code(), 404);
+    let private = server
+        .get(&format!("/backoffice/arts/{}/image", art.id))
+        .await;
+    assert_eq!(private.status_code(), 303);
+
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let private = server
+        .get(&format!("/backoffice/arts/{}/image", art.id))
+        .await;
+    assert_eq!(private.status_code(), 200);
+    private.assert_header("cache-control", "private, max-age=86400");
+
+    let queue = server.get("/backoffice/approvals").await;
+    let body = queue.text();
+    assert!(
+        body.contains(&format!("/backoffice/arts/{}/status", art.id)),
+        "{body}"
+    );
+
+    let publish = server
+        .post(&format!("/backoffice/arts/{}/status", art.id))
+        .form(&StatusBody {
+            status: "published",
+            from: "approvals",
+        })
+        .await;
+    assert_eq!(publish.status_code(), 303, "{}", publish.text());
+    publish.assert_header("location", "/backoffice/approvals");
+
+    let public = server.get(&format!("/{}", art.id)).await;
+    assert_eq!(public.status_code(), 200);
+    assert!(public.text().contains(&art.title));
+    let image = server.get(&format!("/img/{}.webp", art.id)).await;
+    assert_eq!(image.status_code(), 200);
+
+    let invalid = server
+        .post(&format!("/backoffice/arts/{}/status", art.id))
+        .form(&StatusBody {
+            status: "archived",
+            from: "",
+        })
+        .await;
+    assert_eq!(invalid.status_code(), 400);
+
+    let reject = server
+        .post(&format!("/backoffice/arts/{}/status", art.id))
+        .form(&StatusBody {
+            status: "rejected",
+            from: "",
+        })
+        .await;
+    reject.assert_header("location", format!("/backoffice/arts/{}", art.id));
+    let public = server.get(&format!("/{}", art.id)).await;
+    assert_eq!(public.status_code(), 404);
+    let image = server.get(&format!("/img/{}.webp", art.id)).await;
+    assert_eq!(image.status_code(), 404);
+}
+
+#[tokio::test]
+#[serial]
+async fn scheduled_runs_rerender_flagged_arts_and_show_on_the_dashboard() {
+    let (ctx, mut server) = boot_server().await;
+    let art = arts::Model::create(
+        &ctx.db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "Prompt to rerender".to_string(),
+            title: "Flag me".to_string(),
+            model: Some("seed-model".to_string()),
+            ..Default::default()
+        },
     )
+    .await
     .unwrap();
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
 
-    (boot.app_context, server)
+    let flag = server
+        .post(&format!("/backoffice/arts/{}/rerender-flag", art.id))
+        .form(&RerenderFlagBody { flagged: true })
+        .await;
+    assert_eq!(flag.status_code(), 303, "{}", flag.text());
+    let details = server.get(&format!("/backoffice/arts/{}", art.id)).await;
+    assert!(details.text().contains("Remove Flag"));
+
+    let run = scheduler::run_job(
+        &ctx,
+        "nightly-rerender",
+        ScheduledJobKind::RerenderFlagged,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        run.status,
+        RunStatus::Completed.as_str(),
+        "{:?}",
+        run.message
+    );
+    let rerendered = arts::Entity::find_by_id(art.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!rerendered.rerender_flagged);
+    assert_ne!(rerendered.model.as_deref(), Some("seed-model"));
+
+    let created = scheduler::run_job(&ctx, "daily-art", ScheduledJobKind::CreateArt, None)
+        .await
+        .unwrap();
+    assert_eq!(created.status, RunStatus::Completed.as_str());
+    assert!(created.message.unwrap().starts_with("Created art #"));
+
+    let planned = scheduled_runs::Model::create(
+        &ctx.db,
+        "daily-art",
+        ScheduledJobKind::CreateArt.as_str(),
+        RunStatus::Scheduled,
+        chrono::Utc::now().into(),
+    )
+    .await
+    .unwrap();
+    let dashboard = server.get("/backoffice").await;
+    let body = dashboard.text();
+    assert!(body.contains("next due"), "{body}");
+    assert!(body.contains("Rerendered 1 flagged arts"), "{body}");
+
+    scheduled_runs::Model::interrupt_unfinished(
+        &ctx.db,
+        (chrono::Utc::now() - chrono::Duration::hours(1)).into(),
+    )
+    .await
+    .unwrap();
+    let kept = scheduled_runs::Entity::find_by_id(planned.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(kept.status, RunStatus::Scheduled.as_str());
+
+    scheduled_runs::Model::interrupt_unfinished(
+        &ctx.db,
+        (chrono::Utc::now() + chrono::Duration::seconds(1)).into(),
+    )
+    .await
+    .unwrap();
+    let interrupted = scheduled_runs::Entity::find_by_id(planned.id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(interrupted.status, RunStatus::Interrupted.as_str());
+}
+
+#[tokio::test]
+#[serial]
+async fn near_duplicate_images_are_reported_and_can_be_rejected() {
+    let (mut ctx, mut server) = boot_server().await;
+    let image = RgbImage::from_fn(64, 48, |x, y| {
+        Rgb([
+            u8::try_from(x * 4).unwrap(),
+            40,
+            u8::try_from(y * 5).unwrap(),
+        ])
+    });
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+    let image = STANDARD.encode(bytes.into_inner());
+
+    let mut saved = Vec::new();
+    for title in ["Twin One", "Twin Two"] {
+        saved.push(
+            arts::Model::create(
+                &ctx.db,
+                &ArtParams {
+                    image: image.clone(),
+                    prompt: format!("Prompt of {title}"),
+                    title: title.to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap(),
+        );
+    }
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+
+    let report = server.get("/backoffice/duplicates").await;
+    let body = report.text();
+    assert!(
+        body.contains(&format!("Art #{} · Twin One", saved[0].id)),
+        "{body}"
+    );
+    assert!(
+        body.contains(&format!("Art #{} · Twin Two", saved[1].id)),
+        "{body}"
+    );
+    assert!(body.contains("distance 0"), "{body}");
+
+    let mut settings = ctx.config.settings.clone().unwrap();
+    settings["duplicates"] = serde_json::json!({
+        "threshold": 64,
+        "action": "reject",
+        "attempts": 2,
+    });
+    ctx.config.settings = Some(settings);
+    let err = art_service::create_art(&ctx, &CreateArtParams::default())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("near-duplicate"), "{err}");
+}
+
+#[tokio::test]
+#[serial]
+async fn uploads_an_image_as_a_new_art() {
+    let (ctx, mut server) = boot_server().await;
+    server.add_cookie(backoffice_auth::session_cookie(&ctx).unwrap());
+    let form = |image: Vec<u8>| {
+        MultipartForm::new()
+            .add_part(
+                "image",
+                Part::bytes(image)
+                    .file_name("scan.jpg")
+                    .mime_type("image/jpeg"),
+            )
+            .add_text("title", " Restored Harbour ")
+            .add_text("prompt", "A harbour at dawn, restored from a print.")
+            .add_text("model", "film scan")
+    };
+
+    let image = RgbImage::from_fn(30, 20, |x, y| {
+        Rgb([
+            u8::try_from(x * 8).unwrap(),
+            u8::try_from(y * 12).unwrap(),
+            70,
+        ])
+    });
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::Jpeg).unwrap();
+
+    let uploaded = server
+        .post("/backoffice/upload")
+        .multipart(form(bytes.into_inner()))
+        .await;
+    assert_eq!(uploaded.status_code(), 303, "{}", uploaded.text());
+    let location = uploaded.header("location");
+    let art_id = location
+        .to_str()
+        .unwrap()
+        .strip_prefix("/backoffice/arts/")
+        .unwrap()
+        .parse::<i32>()
+        .unwrap();
+    let art = arts::Entity::find_by_id(art_id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(art.title, "Restored Harbour");
+    assert_eq!(art.model.as_deref(), Some("film scan"));
+    assert_eq!((art.width, art.height), (Some(30), Some(20)));
+    assert!(art.image_hash.is_some());
+    let webp = STANDARD.decode(&art.image).unwrap();
+    assert_eq!(image::guess_format(&webp).unwrap(), ImageFormat::WebP);
+
+    let undecodable = server
+        .post("/backoffice/upload")
+        .multipart(form(b"\x89PNG\r\n\x1a\nnot really".to_vec()))
+        .await;
+    assert_eq!(undecodable.status_code(), 422);
+    let body = undecodable.text();
+    assert!(body.contains("The image could not be decoded"), "{body}");
+    assert!(body.contains("Restored Harbour"), "{body}");
+
+    let oversized = server
+        .post("/backoffice/upload")
+        .multipart(form(vec![0; 6 * 1024 * 1024]))
+        .await;
+    assert_eq!(oversized.status_code(), 413);
+    assert!(
+        oversized.text().contains("larger than the payload limit"),
+        "{}",
+        oversized.text()
+    );
 }
@@ -0,0 +1,25 @@
+use std::net::SocketAddr;
+
+use axum_test::{TestServer, TestServerConfig};
+use loco_rs::{app::AppContext, testing::request::boot_test};
+use oxidized_canvas::app::App;
+
+/// Boots the app behind a test server that keeps cookies and hands handlers
+/// the client's socket address, as the mix limits need.
+pub async fn boot_server() -> (AppContext, TestServer) {
+    let boot = boot_test::<App>().await.unwrap();
+    let config = TestServerConfig {
+        default_content_type: Some("application/json".to_string()),
+        save_cookies: true,
+        ..Default::default()
+    };
+    let server = TestServer::new_with_config(
+        boot.router
+            .unwrap()
+            .into_make_service_with_connect_info::<SocketAddr>(),
+        config,
+    )
+    .unwrap();
+
+    (boot.app_context, server)
+}
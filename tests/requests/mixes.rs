@@ -0,0 +1,401 @@
+use std::io::Cursor;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use image::{ImageFormat, Rgb, RgbImage};
+use oxidized_canvas::{
+    models::{
+        arts::{self, ArtParams},
+        generation_jobs::{self, JobKind, JobPayload},
+        mixarts::{self, MixArtParams},
+        mixes::{self, MixParams, PublicationStatus},
+        staged_mixes,
+    },
+    services::mix_limits::{self, Refusal},
+};
+use sea_orm::{EntityTrait, QueryOrder};
+use serial_test::serial;
+use uuid::Uuid;
+
+use super::helpers::boot_server;
+
+fn solid_webp(color: [u8; 3]) -> String {
+    let mut buffer = Cursor::new(Vec::new());
+    RgbImage::from_pixel(16, 16, Rgb(color))
+        .write_to(&mut buffer, ImageFormat::WebP)
+        .unwrap();
+    STANDARD.encode(buffer.into_inner())
+}
+
+#[tokio::test]
+#[serial]
+async fn mixes_inherit_from_the_source_images() {
+    let (ctx, server) = boot_server().await;
+    let mut art_ids = Vec::new();
+    for title in ["Red one", "Red two"] {
+        let art = arts::Model::create(
+            &ctx.db,
+            &ArtParams {
+                image: solid_webp([210, 30, 30]),
+                prompt: format!("Prompt for {title}"),
+                title: title.to_string(),
+                model: Some("seed-model".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        art_ids.push(art.id);
+    }
+
+    let request_id = Uuid::new_v4();
+    let response = server
+        .post("/mix")
+        .json(&serde_json::json!({
+            "art_ids": art_ids,
+            "request_id": request_id.to_string(),
+        }))
+        .await;
+    assert_eq!(response.status_code(), 202, "{}", response.text());
+
+    // the test config generates offline, so the mix job runs to completion inline
+    let mix = mixes::Model::find_by_request_id(&ctx.db, request_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mix.model, "Procedural: local/procedural");
+    let mut linked = mixarts::Model::find_art_ids(&ctx.db, mix.id).await.unwrap();
+    linked.sort_unstable();
+    assert_eq!(linked, art_ids);
+
+    let bytes = STANDARD.decode(&mix.image).unwrap();
+    let mean = image::load_from_memory(&bytes)
+        .unwrap()
+        .resize_exact(1, 1, image::imageops::FilterType::Triangle)
+        .to_rgb8();
+    let [r, g, b] = mean.get_pixel(0, 0).0;
+    assert!(r > 150 && g < 90 && b < 90, "mean color {r},{g},{b}");
+}
+
+#[tokio::test]
+#[serial]
+async fn visitors_past_their_mix_rate_are_turned_away() {
+    let (ctx, server) = boot_server().await;
+    let mut art_ids = Vec::new();
+    for title in ["Rate one", "Rate two"] {
+        let art = arts::Model::create(
+            &ctx.db,
+            &ArtParams {
+                image: solid_webp([30, 90, 210]),
+                prompt: format!("Prompt for {title}"),
+                title: title.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        art_ids.push(art.id);
+    }
+    let mix = || {
+        server.post("/mix").json(&serde_json::json!({
+            "art_ids": art_ids,
+            "request_id": Uuid::new_v4().to_string(),
+        }))
+    };
+
+    // the test config lets a visitor start 3 mixes per window
+    for _ in 0..3 {
+        let response = mix().await;
+        assert_eq!(response.status_code(), 202, "{}", response.text());
+    }
+    let refused = mix().await;
+    assert_eq!(refused.status_code(), 429, "{}", refused.text());
+    let body = refused.json::<serde_json::Value>();
+    assert!(
+        body["message"]
+            .as_str()
+            .is_some_and(|message| message.contains("mixed a lot")),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn mixes_requested_at_once_stay_within_the_daily_budget() {
+    let (mut ctx, _server) = boot_server().await;
+    let mut art_ids = Vec::new();
+    for title in ["Budget one", "Budget two"] {
+        let art = arts::Model::create(
+            &ctx.db,
+            &ArtParams {
+                image: solid_webp([200, 200, 40]),
+                prompt: format!("Prompt for {title}"),
+                title: title.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        art_ids.push(art.id);
+    }
+    let midnight = chrono::Utc::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let started_today = generation_jobs::Model::count_since(&ctx.db, JobKind::Mix, midnight.into())
+        .await
+        .unwrap();
+    let mut settings = ctx.config.settings.clone().unwrap();
+    settings["mix_limits"]["daily_budget"] = serde_json::json!(started_today + 2);
+    ctx.config.settings = Some(settings);
+
+    let ip = "203.0.113.9".parse().ok();
+    let mix = || {
+        mix_limits::enqueue(
+            &ctx,
+            ip,
+            JobPayload {
+                art_ids: art_ids.clone(),
+                request_id: Some(Uuid::new_v4()),
+                ..Default::default()
+            },
+        )
+    };
+    let (first, second, third, fourth) = tokio::join!(mix(), mix(), mix(), mix());
+    let outcomes = [first, second, third, fourth].map(Result::unwrap);
+
+    assert_eq!(outcomes.iter().filter(|outcome| outcome.is_ok()).count(), 2);
+    assert!(
+        outcomes
+            .iter()
+            .filter_map(|outcome| outcome.as_ref().err())
+            .all(|refusal| *refusal == Refusal::ClosedForToday)
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn mix_sources_are_deduplicated_and_checked() {
+    let (ctx, server) = boot_server().await;
+    let mut art_ids = Vec::new();
+    for title in ["Source one", "Source two"] {
+        let art = arts::Model::create(
+            &ctx.db,
+            &ArtParams {
+                image: solid_webp([40, 160, 60]),
+                prompt: format!("Prompt for {title}"),
+                title: title.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        art_ids.push(art.id);
+    }
+    let (first, second) = (art_ids[0], art_ids[1]);
+    let missing = second + 1_000_000;
+    let mix = |ids: Vec<i32>| {
+        server.post("/mix").json(&serde_json::json!({
+            "art_ids": ids,
+            "request_id": Uuid::new_v4().to_string(),
+        }))
+    };
+
+    // the default bounds take from 2 to 6 distinct arts
+    for (ids, expected) in [
+        (vec![first], "at least 2"),
+        (vec![first, first, first], "at least 2"),
+        ((first..first + 7).collect(), "at most 6"),
+        (vec![first, missing], &format!("#{missing}")),
+    ] {
+        let response = mix(ids.clone()).await;
+        assert_eq!(response.status_code(), 422, "{ids:?}: {}", response.text());
+        let body = response.json::<serde_json::Value>();
+        let errors = body["errors"]["art_ids"].as_array().unwrap();
+        assert!(
+            errors
+                .iter()
+                .any(|error| error.as_str().unwrap().contains(expected)),
+            "{ids:?}: {body}"
+        );
+    }
+
+    let response = mix(vec![second, first, second]).await;
+    assert_eq!(response.status_code(), 202, "{}", response.text());
+    let mix = mixes::Entity::find()
+        .order_by_desc(mixes::mixes::Column::Id)
+        .one(&ctx.db)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut linked = mixarts::Model::find_art_ids(&ctx.db, mix.id).await.unwrap();
+    linked.sort_unstable();
+    assert_eq!(linked, art_ids);
+}
+
+#[tokio::test]
+#[serial]
+async fn a_retried_mix_saves_its_staged_render_instead_of_painting_again() {
+    let (ctx, server) = boot_server().await;
+    let mut art_ids = Vec::new();
+    for title in ["Retry one", "Retry two"] {
+        let art = arts::Model::create(
+            &ctx.db,
+            &ArtParams {
+                image: solid_webp([200, 200, 40]),
+                prompt: format!("Prompt for {title}"),
+                title: title.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        art_ids.push(art.id);
+    }
+    // a previous attempt rendered the mix, then failed to save it
+    let request_id = Uuid::new_v4();
+    let staged_image = solid_webp([20, 20, 20]);
+    staged_mixes::Model::stage(
+        &ctx.db,
+        request_id,
+        &MixParams {
+            image: staged_image.clone(),
+            prompt: "The prompt already paid for".to_string(),
+            title: "Already painted".to_string(),
+            model: "earlier-model".to_string(),
+            ..Default::default()
+        },
+        &art_ids,
+    )
+    .await
+    .unwrap();
+
+    let response = server
+        .post("/mix")
+        .json(&serde_json::json!({
+            "art_ids": art_ids,
+            "request_id": request_id.to_string(),
+        }))
+        .await;
+    assert_eq!(response.status_code(), 202, "{}", response.text());
+
+    let mix = mixes::Model::find_by_request_id(&ctx.db, request_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mix.title, "Already painted");
+    assert_eq!(mix.image, staged_image);
+    assert_eq!(mix.model, "earlier-model");
+    let mut linked = mixarts::Model::find_art_ids(&ctx.db, mix.id).await.unwrap();
+    linked.sort_unstable();
+    assert_eq!(linked, art_ids);
+}
+
+#[tokio::test]
+#[serial]
+async fn published_mixes_are_listed_with_their_sources() {
+    let (ctx, server) = boot_server().await;
+    let mut art_ids = Vec::new();
+    for title in ["Index source one", "Index source two"] {
+        let art = arts::Model::create(
+            &ctx.db,
+            &ArtParams {
+                image: solid_webp([120, 60, 160]),
+                prompt: format!("Prompt for {title}"),
+                title: title.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        art_ids.push(art.id);
+    }
+    let mut mix_ids = Vec::new();
+    for n in 1..=7 {
+        let status = if n == 4 {
+            PublicationStatus::Draft
+        } else {
+            PublicationStatus::Published
+        };
+        let mix = mixes::Model::create(
+            &ctx.db,
+            &MixParams {
+                image: solid_webp([120, 60, 160]),
+                prompt: format!("Index prompt {n}"),
+                title: format!("Index mix {n}"),
+                model: "mix-model".to_string(),
+                status,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        mixarts::Model::create(
+            &ctx.db,
+            &MixArtParams {
+                mix_id: mix.id,
+                art_ids: art_ids.clone(),
+            },
+        )
+        .await
+        .unwrap();
+        mix_ids.push(mix.id);
+    }
+    let draft = mix_ids[3];
+    let newest = mix_ids[6];
+    let ids = |body: &serde_json::Value| {
+        body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| i32::try_from(item["id"].as_i64().unwrap()).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    // newest first going back, skipping the draft
+    let before = server
+        .get(&format!("/mixes/api/before/{}", newest + 1))
+        .await;
+    before.assert_status_ok();
+    let before = before.json::<serde_json::Value>();
+    assert_eq!(
+        ids(&before),
+        vec![newest, mix_ids[5], mix_ids[4], mix_ids[2], mix_ids[1]]
+    );
+    assert_eq!(before["results"][0]["art_ids"], serde_json::json!(art_ids));
+    assert_eq!(before["results"][0]["title"], "Index mix 7");
+
+    // oldest first going forward
+    let after = server
+        .get(&format!("/mixes/api/after/{}", mix_ids[1]))
+        .await
+        .json::<serde_json::Value>();
+    assert_eq!(
+        ids(&after),
+        vec![mix_ids[2], mix_ids[4], mix_ids[5], newest]
+    );
+
+    let index = server.get("/mixes").await;
+    index.assert_status_ok();
+    let index = index.text();
+    assert!(index.contains("Index mix 7"), "{index}");
+    assert!(!index.contains("Index mix 4"), "{index}");
+    assert!(
+        index.contains(&format!("href=\"/{}\"", art_ids[0])),
+        "{index}"
+    );
+
+    let feed = server
+        .get(&format!("/mixes/infinite?id={}", mix_ids[5]))
+        .await;
+    feed.assert_status_ok();
+    let feed = feed.text();
+    assert!(feed.contains("Index mix 6"), "{feed}");
+    assert!(!feed.contains("Index mix 7"), "{feed}");
+    assert!(feed.contains(&format!("#M{}", mix_ids[5])), "{feed}");
+
+    server
+        .get(&format!("/mixes/infinite?id={draft}"))
+        .await
+        .assert_status_not_found();
+}
@@ -1 +1,3 @@
 mod backoffice;
+mod helpers;
+mod mixes;
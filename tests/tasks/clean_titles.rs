@@ -1,7 +1,12 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
 use loco_rs::{task, testing::request::boot_test};
-use oxidized_canvas::app::App;
+use oxidized_canvas::{
+    app::App,
+    models::arts::{self, ArtParams},
+};
 
 use loco_rs::boot::run_task;
+use sea_orm::EntityTrait;
 use serial_test::serial;
 
 #[tokio::test]
@@ -19,3 +24,54 @@ async fn test_can_run_clean_titles() {
         .is_ok()
     );
 }
+
+#[tokio::test]
+#[serial]
+async fn clean_titles_previews_then_cleans() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "'Ink drawing of a  harbor.'".to_string(),
+            title: "**Title:** \"Harbor Hush.\"".to_string(),
+            model: Some("seed-model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let dry_run = task::Vars::from_cli_args(vec![("dry_run".to_string(), "true".to_string())]);
+    run_task::<App>(
+        &boot.app_context,
+        Some(&"clean_titles".to_string()),
+        &dry_run,
+    )
+    .await
+    .unwrap();
+    let unchanged = arts::Entity::find_by_id(art.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unchanged.title, art.title);
+    assert_eq!(unchanged.prompt, art.prompt);
+
+    run_task::<App>(
+        &boot.app_context,
+        Some(&"clean_titles".to_string()),
+        &task::Vars::default(),
+    )
+    .await
+    .unwrap();
+    let cleaned = arts::Entity::find_by_id(art.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cleaned.title, "Harbor Hush");
+    assert_eq!(cleaned.prompt, "Ink drawing of a harbor.");
+    assert_eq!(cleaned.updated_at, art.updated_at);
+}
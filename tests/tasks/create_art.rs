@@ -1,9 +1,26 @@
 use loco_rs::{task, testing::request::boot_test};
-use oxidized_canvas::app::App;
+use oxidized_canvas::{
+    app::App,
+    models::{
+        arts,
+        style_presets::{self, StylePresetParams},
+    },
+};
 
 use loco_rs::boot::run_task;
+use sea_orm::{EntityTrait, QueryOrder};
 use serial_test::serial;
 
+/// The art a task run just created; `created_at` only has second precision.
+async fn latest_art(db: &sea_orm::DatabaseConnection) -> arts::Model {
+    arts::Entity::find()
+        .order_by_desc(arts::arts::Column::Id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
 #[tokio::test]
 #[serial]
 async fn test_can_run_create_art() {
@@ -19,3 +36,79 @@ async fn test_can_run_create_art() {
         .is_ok()
     );
 }
+
+#[tokio::test]
+#[serial]
+async fn create_art_renders_and_records_the_requested_options() {
+    let boot = boot_test::<App>().await.unwrap();
+    let vars = task::Vars::from_cli_args(vec![
+        ("aspect_ratio".to_string(), "3:4".to_string()),
+        ("seed".to_string(), "42".to_string()),
+        ("negative_prompt".to_string(), "text".to_string()),
+    ]);
+
+    run_task::<App>(&boot.app_context, Some(&"create_art".to_string()), &vars)
+        .await
+        .unwrap();
+
+    let art = latest_art(&boot.app_context.db).await;
+    assert_eq!(art.aspect_ratio.as_deref(), Some("3:4"));
+    assert_eq!(art.seed, Some(42));
+    assert_eq!(art.negative_prompt.as_deref(), Some("text"));
+    assert_eq!((art.width, art.height), (Some(384), Some(512)));
+    assert_eq!(art.generation_options().aspect_ratio(), "3:4");
+}
+
+#[tokio::test]
+#[serial]
+async fn create_art_follows_and_records_the_theme_and_style_preset() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let preset = style_presets::Model::save(
+        db,
+        &StylePresetParams {
+            name: "Linocut".to_string(),
+            directive: "linocut print, bold carved lines".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    let vars = task::Vars::from_cli_args(vec![
+        ("theme".to_string(), "winter".to_string()),
+        ("style".to_string(), "linocut".to_string()),
+    ]);
+
+    run_task::<App>(&boot.app_context, Some(&"create_art".to_string()), &vars)
+        .await
+        .unwrap();
+
+    let art = latest_art(db).await;
+    assert_eq!(art.theme.as_deref(), Some("winter"));
+    assert_eq!(art.style.as_deref(), Some("linocut"));
+    assert_eq!(art.style_preset_id, Some(preset.id));
+    assert!(
+        art.prompt
+            .ends_with("Style: linocut print, bold carved lines"),
+        "{}",
+        art.prompt
+    );
+    assert!(
+        art.prompt_meta_prompt
+            .as_deref()
+            .unwrap()
+            .contains("about this theme: winter")
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn create_art_rejects_malformed_options() {
+    let boot = boot_test::<App>().await.unwrap();
+    let vars = task::Vars::from_cli_args(vec![("aspect_ratio".to_string(), "wide".to_string())]);
+
+    assert!(
+        run_task::<App>(&boot.app_context, Some(&"create_art".to_string()), &vars)
+            .await
+            .is_err()
+    );
+}
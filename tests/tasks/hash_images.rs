@@ -0,0 +1,63 @@
+use std::io::Cursor;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use image::{ImageFormat, Rgb, RgbImage};
+use loco_rs::{boot::run_task, task, testing::request::boot_test};
+use oxidized_canvas::{
+    app::App,
+    common::image_hash,
+    models::arts::{self, ArtParams},
+};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+fn png() -> Vec<u8> {
+    let image = RgbImage::from_fn(64, 48, |x, y| {
+        Rgb([
+            u8::try_from(x * 4).unwrap(),
+            u8::try_from(y * 5).unwrap(),
+            90,
+        ])
+    });
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+#[tokio::test]
+#[serial]
+async fn hashes_arts_saved_without_a_hash() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let bytes = png();
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode(&bytes),
+            prompt: "A gradient".to_string(),
+            title: "Hash me".to_string(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(art.image_hash, image_hash::dhash(&bytes));
+    assert!(art.image_hash.is_some());
+
+    arts::Model::set_image_hash(db, art.id, None).await.unwrap();
+    run_task::<App>(
+        &boot.app_context,
+        Some(&"hash_images".to_string()),
+        &task::Vars::default(),
+    )
+    .await
+    .unwrap();
+
+    let hashed = arts::Entity::find_by_id(art.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(hashed.image_hash, image_hash::dhash(&bytes));
+    assert_eq!(hashed.updated_at, art.updated_at);
+}
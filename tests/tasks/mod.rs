@@ -1,6 +1,10 @@
 pub mod seed;
 
 pub mod create_art;
+pub mod hash_images;
 pub mod import_arts;
+pub mod repair_mixes;
+pub mod replace_art;
+pub mod rerender_flagged;
 
 pub mod clean_titles;
@@ -0,0 +1,127 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::{boot::run_task, task, testing::request::boot_test};
+use oxidized_canvas::{
+    app::App,
+    models::{
+        arts::{self, ArtParams},
+        mixarts,
+        mixes::{self, MixParams, PublicationStatus},
+        staged_mixes,
+    },
+};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+use uuid::Uuid;
+
+#[tokio::test]
+#[serial]
+async fn saves_staged_renders_and_unpublishes_sourceless_mixes() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let art = arts::Model::create(
+        db,
+        &ArtParams {
+            image: STANDARD.encode("repair-source"),
+            prompt: "A lone source".to_string(),
+            title: "Repair source".to_string(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let sourceless = mixes::Model::create(
+        db,
+        &MixParams {
+            image: STANDARD.encode("dangling-mix"),
+            prompt: "A mix that lost its sources".to_string(),
+            title: "Dangling mix".to_string(),
+            model: "mix-model".to_string(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    // a render whose request already has a mix can't be saved again, and the
+    // repair goes on past it
+    let conflicting_id = Uuid::new_v4();
+    mixes::Model::create(
+        db,
+        &MixParams {
+            image: STANDARD.encode("already-saved"),
+            prompt: "A mix saved for the request".to_string(),
+            title: "Already saved".to_string(),
+            model: "mix-model".to_string(),
+            request_id: Some(conflicting_id),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let conflicting = staged_mixes::Model::stage(
+        db,
+        conflicting_id,
+        &MixParams {
+            image: STANDARD.encode("conflicting-render"),
+            prompt: "A render saved twice".to_string(),
+            title: "Conflicting render".to_string(),
+            model: "mix-model".to_string(),
+            ..Default::default()
+        },
+        &[art.id],
+    )
+    .await
+    .unwrap();
+    let request_id = Uuid::new_v4();
+    staged_mixes::Model::stage(
+        db,
+        request_id,
+        &MixParams {
+            image: STANDARD.encode("orphaned-render"),
+            prompt: "A render whose save failed".to_string(),
+            title: "Orphaned render".to_string(),
+            model: "mix-model".to_string(),
+            ..Default::default()
+        },
+        &[art.id],
+    )
+    .await
+    .unwrap();
+
+    let vars = task::Vars::from_cli_args(vec![("apply".to_string(), "true".to_string())]);
+    run_task::<App>(&boot.app_context, Some(&"repair_mixes".to_string()), &vars)
+        .await
+        .unwrap();
+
+    let saved = mixes::Model::find_by_request_id(db, request_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.title, "Orphaned render");
+    assert_eq!(
+        mixarts::Model::find_art_ids(db, saved.id).await.unwrap(),
+        vec![art.id]
+    );
+    assert!(
+        staged_mixes::Model::find_by_request_id(db, request_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let still_staged = staged_mixes::Model::find_by_request_id(db, conflicting_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(still_staged.id, conflicting.id);
+    assert!(still_staged.last_error.is_some());
+    staged_mixes::Entity::delete_by_id(still_staged.id)
+        .exec(db)
+        .await
+        .unwrap();
+
+    let unpublished = mixes::Entity::find_by_id(sourceless.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unpublished.status, PublicationStatus::Draft.as_str());
+}
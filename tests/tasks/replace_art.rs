@@ -0,0 +1,42 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::{boot::run_task, task, testing::request::boot_test};
+use oxidized_canvas::{
+    app::App,
+    models::arts::{self, ArtParams},
+};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn test_can_run_replace_art() {
+    let boot = boot_test::<App>().await.unwrap();
+    let art = arts::Model::create(
+        &boot.app_context.db,
+        &ArtParams {
+            image: STANDARD.encode("fake-image-bytes"),
+            prompt: "Prompt to replace".to_string(),
+            title: "Replace me".to_string(),
+            model: Some("seed-model".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let vars = task::Vars::from_cli_args(vec![("id".to_string(), art.id.to_string())]);
+    run_task::<App>(&boot.app_context, Some(&"replace_art".to_string()), &vars)
+        .await
+        .unwrap();
+
+    let replaced = arts::Entity::find_by_id(art.id)
+        .one(&boot.app_context.db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(replaced.prompt, "Prompt to replace");
+    assert_eq!(
+        replaced.model.as_deref(),
+        Some("Procedural: local/procedural")
+    );
+}
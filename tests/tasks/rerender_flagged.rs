@@ -0,0 +1,59 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use loco_rs::{boot::run_task, task, testing::request::boot_test};
+use oxidized_canvas::{
+    app::App,
+    models::arts::{self, ArtParams},
+};
+use sea_orm::EntityTrait;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn rerenders_flagged_arts_and_clears_the_flag() {
+    let boot = boot_test::<App>().await.unwrap();
+    let db = &boot.app_context.db;
+    let create = |title: &str| ArtParams {
+        image: STANDARD.encode("fake-image-bytes"),
+        prompt: "Prompt to rerender".to_string(),
+        title: title.to_string(),
+        model: Some("seed-model".to_string()),
+        ..Default::default()
+    };
+    let flagged = arts::Model::create(db, &create("Flagged")).await.unwrap();
+    let untouched = arts::Model::create(db, &create("Untouched")).await.unwrap();
+    arts::Model::set_rerender_flag(db, flagged.id, true)
+        .await
+        .unwrap();
+
+    run_task::<App>(
+        &boot.app_context,
+        Some(&"rerender_flagged".to_string()),
+        &task::Vars::default(),
+    )
+    .await
+    .unwrap();
+
+    let rerendered = arts::Entity::find_by_id(flagged.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!rerendered.rerender_flagged);
+    assert_eq!(rerendered.prompt, "Prompt to rerender");
+    assert_eq!(
+        rerendered.model.as_deref(),
+        Some("Procedural: local/procedural")
+    );
+    let untouched = arts::Entity::find_by_id(untouched.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(untouched.model.as_deref(), Some("seed-model"));
+    assert!(
+        !arts::Model::find_rerender_flagged_ids(db)
+            .await
+            .unwrap()
+            .contains(&flagged.id)
+    );
+}